@@ -0,0 +1,69 @@
+#version 450
+
+const uint MAX_POINT_LIGHTS = 4u;
+const float SHININESS = 32.0;
+const float SPECULAR_STRENGTH = 0.3;
+
+struct PointLight {
+    // Range in w
+    vec4 position;
+    vec4 color;
+};
+
+layout(set = 1, binding = 0) uniform Lights {
+    vec4 ambient;
+    vec4 cameraPosition;
+    vec4 directionalDirection;
+    vec4 directionalColor;
+    PointLight pointLights[MAX_POINT_LIGHTS];
+    uint pointLightCount;
+} lights;
+
+layout(location = 0) in vec2 fragTexCoord;
+layout(location = 1) in vec3 fragColor;
+layout(location = 2) in vec3 fragPosition;
+layout(location = 3) in vec3 fragNormal;
+
+layout(binding = 1) uniform sampler2D texSampler;
+
+layout(location = 0) out vec4 outColor;
+
+vec3 blinnPhong(vec3 toLight, vec3 radiance, vec3 normal, vec3 toCamera, vec3 albedo) {
+    float diffuse = max(dot(normal, toLight), 0.0);
+    vec3 halfway = normalize(toLight + toCamera);
+    float specular = diffuse > 0.0
+        ? pow(max(dot(normal, halfway), 0.0), SHININESS) * SPECULAR_STRENGTH
+        : 0.0;
+    return radiance * (albedo * diffuse + specular);
+}
+
+void main() {
+    vec4 texel = texture(texSampler, fragTexCoord);
+    vec3 albedo = texel.rgb * fragColor;
+    vec3 normal = normalize(fragNormal);
+    vec3 toCamera = normalize(lights.cameraPosition.xyz - fragPosition);
+
+    vec3 color = lights.ambient.rgb * albedo;
+    color += blinnPhong(
+        -lights.directionalDirection.xyz,
+        lights.directionalColor.rgb,
+        normal,
+        toCamera,
+        albedo
+    );
+    for (uint i = 0u; i < min(lights.pointLightCount, MAX_POINT_LIGHTS); i++) {
+        PointLight light = lights.pointLights[i];
+        vec3 toLight = light.position.xyz - fragPosition;
+        float lightDistance = length(toLight);
+        float falloff = clamp(1.0 - lightDistance / light.position.w, 0.0, 1.0);
+        color += blinnPhong(
+            toLight / lightDistance,
+            light.color.rgb * falloff * falloff,
+            normal,
+            toCamera,
+            albedo
+        );
+    }
+
+    outColor = vec4(color, texel.a);
+}
@@ -0,0 +1,29 @@
+#version 450
+
+layout(push_constant) uniform Spatial  {
+    mat4 viewProjection;
+} spatial;
+
+layout(location = 0) in vec3 inPosition;
+layout(location = 1) in vec3 inTexCoords;
+layout(location = 2) in vec3 inVertexColor;
+layout(location = 3) in vec3 inNormal;
+// Per instance, takes locations 4 to 7
+layout(location = 4) in mat4 inModel;
+
+layout(location = 0) out vec2 fragTexCoord;
+layout(location = 1) out vec3 fragColor;
+layout(location = 2) out vec3 fragPosition;
+layout(location = 3) out vec3 fragNormal;
+
+void main() {
+    vec4 worldPosition = inModel * vec4(inPosition, 1.0);
+    gl_Position = spatial.viewProjection * worldPosition;
+    // Only read when drawing points
+    gl_PointSize = 1.0;
+    fragTexCoord = vec2(inTexCoords.x, 1.0 - inTexCoords.y);
+    fragColor = inVertexColor;
+    fragPosition = worldPosition.xyz;
+    // Objects are only scaled uniformly, so the model matrix keeps normals perpendicular
+    fragNormal = mat3(inModel) * inNormal;
+}
@@ -1,18 +1,22 @@
 #version 450
 
 layout(push_constant) uniform Spatial  {
-    mat4 mvp;
+    mat4 viewProjection;
 } spatial;
 
 layout(location = 0) in vec3 inPosition;
 layout(location = 1) in vec3 inTexCoords;
 layout(location = 2) in vec3 inVertexColor;
+// Per instance, takes locations 4 to 7
+layout(location = 4) in mat4 inModel;
 
 layout(location = 0) out vec2 fragTexCoord;
 layout(location = 1) out vec3 fragColor;
 
 void main() {
-    gl_Position = spatial.mvp * vec4(inPosition, 1.0);
+    gl_Position = spatial.viewProjection * inModel * vec4(inPosition, 1.0);
+    // Only read when drawing points
+    gl_PointSize = 1.0;
     fragTexCoord = vec2(inTexCoords.x, 1.0 - inTexCoords.y);
     fragColor = inVertexColor;
 }
@@ -2,10 +2,11 @@ mod asset_locator;
 
 use std::{collections::HashMap, path::Path};
 
+use image::{Rgba, RgbaImage};
 use uuid::Uuid;
 
 pub use crate::assets::asset_locator::AssetLocator;
-use crate::rendering::material::Material;
+use crate::rendering::material::{Material, SamplerSettings};
 use crate::rendering::shader::{Shader, ShaderStage};
 use crate::rendering::{mesh::Mesh, texture::Texture};
 
@@ -24,9 +25,13 @@ pub trait Asset {
 }
 
 pub const FALLBACK_TEXTURE: &str = "fallback_texture";
+/// Plain white texture, for materials coloured by their vertices.
+pub const WHITE_TEXTURE: &str = "white_texture";
 pub const DEFAULT_FRAG_SHADER: &str = "unlit_frag";
 pub const DEFAULT_VERT_SHADER: &str = "unlit_vert";
 pub const DEFAULT_MATERIAL: &str = "default_material";
+/// Blinn-Phong shaded material, multiplying the texture by the vertex colour.
+pub const LIT_MATERIAL: &str = "lit_material";
 
 pub struct Assets {
     asset_locator: AssetLocator,
@@ -45,6 +50,11 @@ impl Assets {
             .expect("make sure fallback texture is present in the assets folder");
         fallback_texture.id = new_uuid();
 
+        let white_texture = Texture {
+            id: new_uuid(),
+            image: RgbaImage::from_pixel(1, 1, Rgba([255; 4])),
+        };
+
         let mut unlit_vert_shader = Shader::from_asset(
             &locator,
             Path::new("shaders/unlit.vert"),
@@ -61,25 +71,59 @@ impl Assets {
         .unwrap();
         unlit_frag_shader.id = new_uuid();
 
+        let mut lit_vert_shader =
+            Shader::from_asset(&locator, Path::new("shaders/lit.vert"), ShaderStage::Vertex)
+                .unwrap();
+        lit_vert_shader.id = new_uuid();
+
+        let mut lit_frag_shader = Shader::from_asset(
+            &locator,
+            Path::new("shaders/lit.frag"),
+            ShaderStage::Fragment,
+        )
+        .unwrap();
+        lit_frag_shader.id = new_uuid();
+
         let default_material = Material {
             id: new_uuid(),
             fragment_shader_id: unlit_frag_shader.id,
             vertex_shader_id: unlit_vert_shader.id,
             texture_id: fallback_texture.id,
+            sampler: SamplerSettings::default(),
+        };
+
+        let lit_material = Material {
+            id: new_uuid(),
+            fragment_shader_id: lit_frag_shader.id,
+            vertex_shader_id: lit_vert_shader.id,
+            texture_id: white_texture.id,
+            sampler: SamplerSettings::default(),
         };
 
         let name_map = HashMap::from_iter([
             (FALLBACK_TEXTURE.to_string(), fallback_texture.id),
+            (WHITE_TEXTURE.to_string(), white_texture.id),
             (DEFAULT_VERT_SHADER.to_string(), unlit_vert_shader.id),
             (DEFAULT_FRAG_SHADER.to_string(), unlit_frag_shader.id),
+            ("lit_vert".to_string(), lit_vert_shader.id),
+            ("lit_frag".to_string(), lit_frag_shader.id),
             (DEFAULT_MATERIAL.to_string(), default_material.id),
+            (LIT_MATERIAL.to_string(), lit_material.id),
+        ]);
+        let textures = HashMap::from_iter([
+            (fallback_texture.id, fallback_texture),
+            (white_texture.id, white_texture),
         ]);
-        let textures = HashMap::from_iter([(fallback_texture.id, fallback_texture)]);
         let shaders = HashMap::from_iter([
             (unlit_vert_shader.id, unlit_vert_shader),
             (unlit_frag_shader.id, unlit_frag_shader),
+            (lit_vert_shader.id, lit_vert_shader),
+            (lit_frag_shader.id, lit_frag_shader),
+        ]);
+        let materials = HashMap::from_iter([
+            (default_material.id, default_material),
+            (lit_material.id, lit_material),
         ]);
-        let materials = HashMap::from_iter([(default_material.id, default_material)]);
 
         Self {
             asset_locator: locator,
@@ -70,14 +70,14 @@ impl FreeCameraMouseControl {
         let mut rot_up_down = Rotation3::identity();
 
         let mouse_offset = input_state.mouse_offset();
-        let lmb = Key::MouseButton(MouseButton::Left);
-        let lmb_held = input_state.is_held(lmb);
+        let rmb = Key::MouseButton(MouseButton::Right);
+        let rmb_held = input_state.is_held(rmb);
 
-        if input_state.is_pressed(lmb) {
+        if input_state.is_pressed(rmb) {
             self.grab_cursor(window);
         }
 
-        if lmb_held {
+        if rmb_held {
             let modifier = self.sensitivity * time_delta;
             rot_left_right = Rotation3::from_axis_angle(
                 &self.up,
@@ -89,11 +89,11 @@ impl FreeCameraMouseControl {
             );
         }
 
-        if input_state.is_released(lmb) {
+        if input_state.is_released(rmb) {
             self.release_cursor(window);
         }
 
-        if !lmb_held {
+        if !rmb_held {
             let look_offset = (self.sensitivity * time_delta).to_radians();
             if input_state.is_held(Key::KeyboardKey(VirtualKeyCode::Up)) {
                 rot_up_down = Rotation3::from_axis_angle(&self.camera_right, look_offset);
@@ -1,18 +1,27 @@
 mod camera_control;
 mod free_camera_mouse_control;
 mod free_camera_touch_control;
+mod orbit_camera_control;
+mod top_down_camera_control;
 
 use nalgebra::{Matrix4, Point3, Vector3};
 
 pub use camera_control::CameraControl;
 pub use free_camera_mouse_control::FreeCameraMouseControl;
 pub use free_camera_touch_control::FreeCameraTouchControl;
+pub use orbit_camera_control::OrbitCameraControl;
+pub use top_down_camera_control::TopDownCameraControl;
 
+use crate::math::Point2D;
+use crate::picking::Ray;
 use crate::rendering::projection::Projection;
 
 pub struct Camera {
     pub position: Point3<f32>,
     pub direction: Vector3<f32>,
+    /// Direction pointing up on the screen, must not be parallel to `direction`. World up
+    /// unless set otherwise.
+    pub up: Vector3<f32>,
     projection: Projection,
 }
 
@@ -21,20 +30,22 @@ impl Camera {
         Self {
             position: *position,
             direction: *direction,
+            up: Vector3::y(),
             projection,
         }
     }
 
     pub fn matrix(&self) -> Matrix4<f32> {
         let projection = self.projection.matrix();
-        let view = Matrix4::look_at_rh(
-            &self.position,
-            &(self.position + self.direction),
-            &Vector3::y(),
-        );
+        let view = Matrix4::look_at_rh(&self.position, &(self.position + self.direction), &self.up);
         projection * view
     }
 
+    /// World ray through `position`, in pixels of a `width` by `height` viewport.
+    pub fn ray(&self, position: Point2D, width: f32, height: f32) -> Option<Ray> {
+        Ray::from_screen(&self.matrix(), position, width, height)
+    }
+
     pub fn set_viewport_dimensions(&mut self, width: f32, height: f32) {
         self.projection.set_viewport_dimensions(width, height);
     }
@@ -0,0 +1,280 @@
+use nalgebra::{Point3, Vector3};
+use winit::window::Window;
+
+use crate::camera::camera_control::CameraControl;
+use crate::camera::Camera;
+use crate::input_state::{InputState, Key, MouseButton, VirtualKeyCode};
+use crate::math::{ease_in_out, Point2D};
+
+const MIN_PITCH: f32 = 10.0;
+const MAX_PITCH: f32 = 89.0;
+const PRESET_PITCH: f32 = 50.0;
+/// Zoom limits relative to the preset distance.
+const MIN_ZOOM: f32 = 0.4;
+const MAX_ZOOM: f32 = 2.5;
+/// Fraction of the distance that one line of scrolling zooms by.
+const ZOOM_STEP: f32 = 0.1;
+/// Seconds a transition to a preset takes.
+const TRANSITION_TIME: f32 = 0.6;
+const PRESET_KEYS: [(VirtualKeyCode, CameraPreset); 3] = [
+    (VirtualKeyCode::Key1, CameraPreset::White),
+    (VirtualKeyCode::Key2, CameraPreset::Black),
+    (VirtualKeyCode::Key3, CameraPreset::TopDown),
+];
+
+/// Placement of a camera around the point it looks at, angles in degrees.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Orbit {
+    /// Angle around the vertical axis, 0 on the +z side of the target.
+    pub yaw: f32,
+    /// Angle above the horizontal plane.
+    pub pitch: f32,
+    pub distance: f32,
+}
+
+impl Orbit {
+    /// Camera position orbiting `target`.
+    pub fn eye(&self, target: &Point3<f32>) -> Point3<f32> {
+        let yaw = self.yaw.to_radians();
+        let pitch = self.pitch.to_radians();
+        let offset = Vector3::new(
+            yaw.sin() * pitch.cos(),
+            pitch.sin(),
+            yaw.cos() * pitch.cos(),
+        );
+
+        target + offset * self.distance
+    }
+
+    /// The orbit `t` of the way to `to`, turning around the shorter way.
+    pub fn lerp(&self, to: &Orbit, t: f32) -> Orbit {
+        let yaw_delta = (to.yaw - self.yaw + 180.0).rem_euclid(360.0) - 180.0;
+        Orbit {
+            yaw: self.yaw + yaw_delta * t,
+            pitch: self.pitch + (to.pitch - self.pitch) * t,
+            distance: self.distance + (to.distance - self.distance) * t,
+        }
+    }
+}
+
+/// Views of the board the orbit camera can be sent to. The board is expected to face white's
+/// side at yaw 0.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CameraPreset {
+    White,
+    Black,
+    /// Looking straight down, keeping the side the camera is on.
+    TopDown,
+}
+
+struct Transition {
+    from: Orbit,
+    to: Orbit,
+    elapsed: f32,
+}
+
+/// Camera circling a target, rotated with the right mouse button, the arrow keys or two fingers
+/// and zoomed with the scroll wheel or a pinch. The number keys 1, 2 and 3 move it to the
+/// white, black and top-down presets.
+pub struct OrbitCameraControl {
+    camera: Camera,
+    target: Point3<f32>,
+    orbit: Orbit,
+    preset_distance: f32,
+    sensitivity: f32,
+    transition: Option<Transition>,
+    /// Distance between and middle of the two fingers of a pinch in the last frame.
+    pinch: Option<(f64, Point2D)>,
+}
+
+impl OrbitCameraControl {
+    /// Creates the control looking at `target` from the white preset `distance` away.
+    pub fn new(camera: Camera, target: Point3<f32>, distance: f32, sensitivity: f32) -> Self {
+        let mut control = Self {
+            camera,
+            target,
+            orbit: Orbit {
+                yaw: 0.0,
+                pitch: PRESET_PITCH,
+                distance,
+            },
+            preset_distance: distance,
+            sensitivity,
+            transition: None,
+            pinch: None,
+        };
+        control.place_camera();
+
+        control
+    }
+
+    /// Starts moving the camera to `preset`.
+    pub fn set_preset(&mut self, preset: CameraPreset) {
+        let (yaw, pitch) = match preset {
+            CameraPreset::White => (0.0, PRESET_PITCH),
+            CameraPreset::Black => (180.0, PRESET_PITCH),
+            CameraPreset::TopDown => (self.orbit.yaw, MAX_PITCH),
+        };
+        self.transition = Some(Transition {
+            from: self.orbit,
+            to: Orbit {
+                yaw,
+                pitch,
+                distance: self.preset_distance,
+            },
+            elapsed: 0.0,
+        });
+    }
+
+    fn step_transition(&mut self, time_delta: f32) {
+        let Some(transition) = &mut self.transition else {
+            return;
+        };
+
+        transition.elapsed += time_delta;
+        let t = transition.elapsed / TRANSITION_TIME;
+        self.orbit = transition.from.lerp(&transition.to, ease_in_out(t));
+        if t >= 1.0 {
+            self.transition = None;
+        }
+    }
+
+    /// Yaw and pitch change in degrees and the factor to scale the distance by, from the mouse,
+    /// the arrow keys and two fingers.
+    fn orbit_input(&mut self, input_state: &InputState, time_delta: f32) -> (f32, f32, f32) {
+        let modifier = self.sensitivity * time_delta;
+        let mut yaw = 0.0;
+        let mut pitch = 0.0;
+        let mut zoom = (1.0 - ZOOM_STEP).powf(input_state.scroll_delta());
+
+        if input_state.is_held(Key::MouseButton(MouseButton::Right)) {
+            let offset = input_state.mouse_offset();
+            yaw -= offset.x as f32 * modifier;
+            pitch += offset.y as f32 * modifier;
+        }
+
+        for (key, yaw_sign, pitch_sign) in [
+            (VirtualKeyCode::Left, 1.0, 0.0),
+            (VirtualKeyCode::Right, -1.0, 0.0),
+            (VirtualKeyCode::Up, 0.0, 1.0),
+            (VirtualKeyCode::Down, 0.0, -1.0),
+        ] {
+            if input_state.is_held(Key::KeyboardKey(key)) {
+                yaw += yaw_sign * self.sensitivity * time_delta;
+                pitch += pitch_sign * self.sensitivity * time_delta;
+            }
+        }
+
+        let mut touches = input_state.touches();
+        let pinch = match (touches.next(), touches.next(), touches.next()) {
+            (Some(a), Some(b), None) => {
+                let (a, b) = (a.move_position, b.move_position);
+                let distance = (a.x - b.x).hypot(a.y - b.y);
+                let middle = Point2D::new((a.x + b.x) / 2.0, (a.y + b.y) / 2.0);
+                Some((distance, middle))
+            }
+            _ => None,
+        };
+        if let (Some((last_distance, last_middle)), Some((distance, middle))) = (self.pinch, pinch)
+        {
+            yaw -= (middle.x - last_middle.x) as f32 * modifier;
+            pitch += (middle.y - last_middle.y) as f32 * modifier;
+            if distance > 0.0 {
+                zoom *= (last_distance / distance) as f32;
+            }
+        }
+        self.pinch = pinch;
+
+        (yaw, pitch, zoom)
+    }
+
+    fn place_camera(&mut self) {
+        let eye = self.orbit.eye(&self.target);
+        self.camera.position = eye;
+        self.camera.direction = self.target - eye;
+    }
+}
+
+impl CameraControl for OrbitCameraControl {
+    fn camera(&self) -> &Camera {
+        &self.camera
+    }
+
+    fn camera_mut(&mut self) -> &mut Camera {
+        &mut self.camera
+    }
+
+    fn update(&mut self, _window: &Window, input_state: &InputState, time_delta: f32) {
+        for (key, preset) in PRESET_KEYS {
+            if input_state.is_pressed(Key::KeyboardKey(key)) {
+                self.set_preset(preset);
+            }
+        }
+
+        let (yaw, pitch, zoom) = self.orbit_input(input_state, time_delta);
+        if self.transition.is_some() {
+            self.step_transition(time_delta);
+        } else {
+            self.orbit.yaw = (self.orbit.yaw + yaw).rem_euclid(360.0);
+            self.orbit.pitch = (self.orbit.pitch + pitch).clamp(MIN_PITCH, MAX_PITCH);
+            self.orbit.distance = (self.orbit.distance * zoom).clamp(
+                self.preset_distance * MIN_ZOOM,
+                self.preset_distance * MAX_ZOOM,
+            );
+        }
+
+        self.place_camera();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn assert_near(a: Point3<f32>, b: Point3<f32>) {
+        assert!((a - b).norm() < 1e-4, "{a} != {b}");
+    }
+
+    #[test]
+    fn test_orbit_eye() {
+        let target = Point3::new(1.0, 0.0, -1.0);
+        let white = Orbit {
+            yaw: 0.0,
+            pitch: 0.0,
+            distance: 2.0,
+        };
+        assert_near(white.eye(&target), Point3::new(1.0, 0.0, 1.0));
+
+        let black = Orbit {
+            yaw: 180.0,
+            ..white
+        };
+        assert_near(black.eye(&target), Point3::new(1.0, 0.0, -3.0));
+
+        let top = Orbit {
+            pitch: 90.0,
+            ..white
+        };
+        assert_near(top.eye(&target), Point3::new(1.0, 2.0, -1.0));
+    }
+
+    #[test]
+    fn test_orbit_lerp() {
+        let from = Orbit {
+            yaw: 170.0,
+            pitch: 20.0,
+            distance: 1.0,
+        };
+        let to = Orbit {
+            yaw: -170.0,
+            pitch: 60.0,
+            distance: 3.0,
+        };
+
+        let middle = from.lerp(&to, 0.5);
+        assert!((middle.yaw - 180.0).abs() < 1e-4);
+        assert_eq!(middle.pitch, 40.0);
+        assert_eq!(middle.distance, 2.0);
+        assert_eq!(from.lerp(&to, 0.0), from);
+    }
+}
@@ -0,0 +1,104 @@
+use nalgebra::{Point3, Vector3};
+use winit::window::Window;
+
+use crate::camera::camera_control::CameraControl;
+use crate::camera::Camera;
+use crate::input_state::{InputState, Key, VirtualKeyCode};
+use crate::rendering::projection::Projection;
+
+/// Distance of the camera from the target. Only has to keep the scene between the clip planes.
+const EYE_DISTANCE: f32 = 20.0;
+const NEAR: f32 = 0.1;
+const FAR: f32 = 50.0;
+/// Pixels a finger may move during a tap.
+const TAP_SLOP: f64 = 12.0;
+
+/// Orthographic camera looking straight down at a region, for a 2D view of the board. The
+/// number keys 1 and 2 turn it to white's and black's side, and tapping with two fingers turns
+/// it to the other side.
+pub struct TopDownCameraControl {
+    camera: Camera,
+    target: Point3<f32>,
+    /// Angle around the vertical axis of the side at the bottom of the screen, 0 for the +z
+    /// side of the target.
+    yaw: f32,
+    tap: TwoFingerTap,
+}
+
+/// Progress of a tap with two fingers, from the first finger down to the last one up.
+#[derive(Default)]
+struct TwoFingerTap {
+    fingers: usize,
+    moved: bool,
+}
+
+impl TopDownCameraControl {
+    /// Creates the control fitting a `width` by `length` region around `target` on the screen,
+    /// with white's side at the bottom.
+    pub fn new(target: Point3<f32>, width: f32, length: f32) -> Self {
+        let projection = Projection::orthographic(width, length, NEAR, FAR);
+        let camera = Camera::new(&target, &-Vector3::y(), projection);
+
+        let mut control = Self {
+            camera,
+            target,
+            yaw: 0.0,
+            tap: TwoFingerTap::default(),
+        };
+        control.place_camera();
+
+        control
+    }
+
+    /// Whether two fingers were just lifted without moving, the others having been lifted
+    /// before.
+    fn two_finger_tap(&mut self, input_state: &InputState) -> bool {
+        let mut fingers = 0;
+        for touch in input_state.touches() {
+            fingers += 1;
+            let start = touch.start_position;
+            let position = touch.end_position.unwrap_or(touch.move_position);
+            if (position.x - start.x).hypot(position.y - start.y) > TAP_SLOP {
+                self.tap.moved = true;
+            }
+        }
+
+        if fingers > 0 {
+            self.tap.fingers = self.tap.fingers.max(fingers);
+            return false;
+        }
+        let tap = std::mem::take(&mut self.tap);
+        tap.fingers == 2 && !tap.moved
+    }
+
+    fn place_camera(&mut self) {
+        // Looking straight down, the far side of the board is up on the screen
+        let yaw = self.yaw.to_radians();
+        self.camera.up = Vector3::new(-yaw.sin(), 0.0, -yaw.cos());
+        self.camera.position = self.target + Vector3::y() * EYE_DISTANCE;
+        self.camera.direction = -Vector3::y();
+    }
+}
+
+impl CameraControl for TopDownCameraControl {
+    fn camera(&self) -> &Camera {
+        &self.camera
+    }
+
+    fn camera_mut(&mut self) -> &mut Camera {
+        &mut self.camera
+    }
+
+    fn update(&mut self, _window: &Window, input_state: &InputState, _time_delta: f32) {
+        for (key, yaw) in [(VirtualKeyCode::Key1, 0.0), (VirtualKeyCode::Key2, 180.0)] {
+            if input_state.is_pressed(Key::KeyboardKey(key)) {
+                self.yaw = yaw;
+            }
+        }
+        if self.two_finger_tap(input_state) {
+            self.yaw = (self.yaw + 180.0) % 360.0;
+        }
+
+        self.place_camera();
+    }
+}
@@ -0,0 +1,149 @@
+use crate::chess::moves::CastleSide;
+use crate::chess::piece::{Color, Piece, PieceKind};
+use crate::chess::position::{CastlingRights, Position};
+use crate::chess::square::Square;
+
+/// Number of distinct Chess960 start positions.
+pub const CHESS960_POSITIONS: u16 = 960;
+
+/// Placement of the two knights among the five squares left after the bishops and queen,
+/// indexed as in the Scharnagl numbering.
+const KNIGHT_PLACEMENTS: [(usize, usize); 10] = [
+    (0, 1),
+    (0, 2),
+    (0, 3),
+    (0, 4),
+    (1, 2),
+    (1, 3),
+    (1, 4),
+    (2, 3),
+    (2, 4),
+    (3, 4),
+];
+
+impl Position {
+    /// The Chess960 start position with the given Scharnagl index, from 0 to 959. Index 518 is
+    /// the standard start position.
+    pub fn chess960(index: u16) -> Option<Self> {
+        if index >= CHESS960_POSITIONS {
+            return None;
+        }
+
+        let mut back_rank: [Option<PieceKind>; 8] = [None; 8];
+        let mut n = index as usize;
+
+        // Light squared bishop on b, d, f or h, dark squared one on a, c, e or g
+        back_rank[2 * (n % 4) + 1] = Some(PieceKind::Bishop);
+        n /= 4;
+        back_rank[2 * (n % 4)] = Some(PieceKind::Bishop);
+        n /= 4;
+
+        let mut place_on_empty = |nth: usize, kind: PieceKind| {
+            let file = (0..8).filter(|&f| back_rank[f].is_none()).nth(nth).unwrap();
+            back_rank[file] = Some(kind);
+        };
+        place_on_empty(n % 6, PieceKind::Queen);
+        n /= 6;
+
+        // The second knight goes one earlier as the first one took up an empty square
+        let (first, second) = KNIGHT_PLACEMENTS[n];
+        place_on_empty(first, PieceKind::Knight);
+        place_on_empty(second - 1, PieceKind::Knight);
+
+        // The king always stands between the two rooks
+        place_on_empty(0, PieceKind::Rook);
+        place_on_empty(0, PieceKind::King);
+        place_on_empty(0, PieceKind::Rook);
+
+        let mut position = Position::empty();
+        let mut rights = CastlingRights::none();
+        for (file, kind) in back_rank.iter().enumerate() {
+            let file = file as u8;
+            let kind = kind.expect("every back rank square is filled");
+            for color in [Color::White, Color::Black] {
+                let rank = color.back_rank();
+                let pawn_rank = (rank as i8 + color.forward()) as u8;
+                position.set_piece(Square::new(file, rank), Some(Piece::new(color, kind)));
+                position.set_piece(
+                    Square::new(file, pawn_rank),
+                    Some(Piece::new(color, PieceKind::Pawn)),
+                );
+            }
+
+            if kind == PieceKind::Rook {
+                let side = if rights.has(Color::White, CastleSide::QueenSide) {
+                    CastleSide::KingSide
+                } else {
+                    CastleSide::QueenSide
+                };
+                rights.set_rook_file(Color::White, side, Some(file));
+                rights.set_rook_file(Color::Black, side, Some(file));
+            }
+        }
+        position.set_castling_rights(rights);
+
+        Some(position)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashSet;
+
+    use super::*;
+    use crate::chess::perft::perft;
+
+    #[test]
+    fn test_all_start_positions() {
+        assert_eq!(Position::chess960(518), Some(Position::starting()));
+        assert_eq!(
+            Position::chess960(0).unwrap().to_fen(),
+            "bbqnnrkr/pppppppp/8/8/8/8/PPPPPPPP/BBQNNRKR w KQkq - 0 1"
+        );
+        assert_eq!(Position::chess960(CHESS960_POSITIONS), None);
+
+        let mut seen = HashSet::new();
+        for index in 0..CHESS960_POSITIONS {
+            let position = Position::chess960(index).unwrap();
+            assert!(seen.insert(position.to_fen()));
+
+            let files = |kind| -> Vec<u8> {
+                (0..8)
+                    .filter(|&f| {
+                        position.piece_at(Square::new(f, 0)) == Some(Piece::new(Color::White, kind))
+                    })
+                    .collect()
+            };
+            let (bishops, rooks, king) = (
+                files(PieceKind::Bishop),
+                files(PieceKind::Rook),
+                files(PieceKind::King),
+            );
+            assert_ne!(bishops[0] % 2, bishops[1] % 2);
+            assert!(rooks[0] < king[0] && king[0] < rooks[1]);
+        }
+    }
+
+    #[test]
+    fn test_chess960_perft() {
+        for (fen, expected) in [
+            (
+                "bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9",
+                [21, 528, 12189],
+            ),
+            (
+                "2nnrbkr/p1qppppp/8/1ppb4/6PP/3PP3/PPP2P2/BQNNRBKR w HEhe - 1 9",
+                [21, 807, 18002],
+            ),
+            (
+                "b1q1rrkb/pppppppp/3nn3/8/P7/1PPP4/4PPPP/BQNNRKRB w GE - 1 9",
+                [20, 479, 10471],
+            ),
+        ] {
+            let position = Position::from_fen(fen).unwrap();
+            for (depth, nodes) in expected.into_iter().enumerate() {
+                assert_eq!(perft(&position, depth as u32 + 1), nodes, "{fen}");
+            }
+        }
+    }
+}
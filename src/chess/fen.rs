@@ -0,0 +1,353 @@
+use thiserror::Error;
+
+use crate::chess::moves::CastleSide;
+use crate::chess::piece::{Color, Piece, PieceKind};
+use crate::chess::position::{CastlingRights, Position};
+use crate::chess::square::Square;
+
+pub const STARTING_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum FenError {
+    #[error("expected 4 or 6 space separated fields, found {0}")]
+    WrongFieldCount(usize),
+    #[error("expected 8 ranks in piece placement, found {0}")]
+    WrongRankCount(usize),
+    #[error("rank {0} does not describe exactly 8 squares")]
+    InvalidRankLength(u8),
+    #[error("invalid piece character '{0}'")]
+    InvalidPiece(char),
+    #[error("{0:?} must have exactly one king")]
+    InvalidKingCount(Color),
+    #[error("invalid side to move \"{0}\"")]
+    InvalidSideToMove(String),
+    #[error("invalid castling rights \"{0}\"")]
+    InvalidCastlingRights(String),
+    #[error("invalid en passant square \"{0}\"")]
+    InvalidEnPassant(String),
+    #[error("invalid halfmove clock \"{0}\"")]
+    InvalidHalfmoveClock(String),
+    #[error("invalid fullmove number \"{0}\"")]
+    InvalidFullmoveNumber(String),
+}
+
+impl Position {
+    /// Parses a position in Forsyth-Edwards Notation. The halfmove clock and fullmove number
+    /// fields may be omitted, as they commonly are in EPD files.
+    pub fn from_fen(fen: &str) -> Result<Self, FenError> {
+        let fields: Vec<&str> = fen.split_whitespace().collect();
+        if fields.len() != 4 && fields.len() != 6 {
+            return Err(FenError::WrongFieldCount(fields.len()));
+        }
+
+        let mut position = Position::empty();
+        parse_placement(&mut position, fields[0])?;
+
+        let side_to_move = match fields[1] {
+            "w" => Color::White,
+            "b" => Color::Black,
+            other => return Err(FenError::InvalidSideToMove(other.to_string())),
+        };
+        position.set_side_to_move(side_to_move);
+
+        let castling_rights = parse_castling_rights(&position, fields[2])?;
+        position.set_castling_rights(castling_rights);
+
+        // The square is behind a pawn the other side just pushed two squares
+        let en_passant_rank = match side_to_move {
+            Color::White => 5,
+            Color::Black => 2,
+        };
+        let en_passant = match fields[3] {
+            "-" => None,
+            name => match Square::parse(name) {
+                Some(sq) if sq.rank() == en_passant_rank => Some(sq),
+                _ => return Err(FenError::InvalidEnPassant(name.to_string())),
+            },
+        };
+        position.set_en_passant(en_passant);
+
+        if fields.len() == 6 {
+            let halfmove_clock = fields[4]
+                .parse()
+                .map_err(|_| FenError::InvalidHalfmoveClock(fields[4].to_string()))?;
+            let fullmove_number = fields[5]
+                .parse()
+                .ok()
+                .filter(|&n| n > 0)
+                .ok_or_else(|| FenError::InvalidFullmoveNumber(fields[5].to_string()))?;
+            position.set_halfmove_clock(halfmove_clock);
+            position.set_fullmove_number(fullmove_number);
+        }
+
+        for color in [Color::White, Color::Black] {
+            let kings = position
+                .pieces()
+                .filter(|(_, p)| *p == Piece::new(color, PieceKind::King))
+                .count();
+            if kings != 1 {
+                return Err(FenError::InvalidKingCount(color));
+            }
+        }
+
+        Ok(position)
+    }
+
+    /// Formats the position in Forsyth-Edwards Notation. Castling rights use X-FEN, which is
+    /// plain FEN for standard chess and only names the rook file when `K` or `Q` would be
+    /// ambiguous in Chess960.
+    pub fn to_fen(&self) -> String {
+        self.format_fen(false)
+    }
+
+    /// Formats the position in Shredder-FEN, which gives castling rights as rook files,
+    /// e.g. `HAha` rather than `KQkq`.
+    pub fn to_shredder_fen(&self) -> String {
+        self.format_fen(true)
+    }
+
+    fn format_fen(&self, shredder: bool) -> String {
+        let mut fen = String::with_capacity(90);
+        for rank in (0..8).rev() {
+            let mut empty = 0;
+            for file in 0..8 {
+                match self.piece_at(Square::new(file, rank)) {
+                    Some(piece) => {
+                        if empty > 0 {
+                            fen.push_str(&empty.to_string());
+                            empty = 0;
+                        }
+                        fen.push(piece.to_char());
+                    }
+                    None => empty += 1,
+                }
+            }
+            if empty > 0 {
+                fen.push_str(&empty.to_string());
+            }
+            if rank > 0 {
+                fen.push('/');
+            }
+        }
+
+        fen.push_str(match self.side_to_move() {
+            Color::White => " w ",
+            Color::Black => " b ",
+        });
+
+        let rights = self.castling_rights();
+        let mut any_rights = false;
+        for color in [Color::White, Color::Black] {
+            for side in [CastleSide::KingSide, CastleSide::QueenSide] {
+                if let Some(file) = rights.rook_file(color, side) {
+                    let c = if shredder || !self.is_outermost_rook(color, side, file) {
+                        (b'A' + file) as char
+                    } else if side == CastleSide::KingSide {
+                        'K'
+                    } else {
+                        'Q'
+                    };
+                    fen.push(match color {
+                        Color::White => c,
+                        Color::Black => c.to_ascii_lowercase(),
+                    });
+                    any_rights = true;
+                }
+            }
+        }
+        if !any_rights {
+            fen.push('-');
+        }
+
+        match self.en_passant() {
+            Some(sq) => fen.push_str(&format!(" {sq}")),
+            None => fen.push_str(" -"),
+        }
+
+        fen.push_str(&format!(
+            " {} {}",
+            self.halfmove_clock(),
+            self.fullmove_number()
+        ));
+
+        fen
+    }
+
+    /// Whether no other rook of `color` stands between the rook on `file` and the edge of the
+    /// board on `side`, which is what `K` and `Q` refer to in X-FEN.
+    fn is_outermost_rook(&self, color: Color, side: CastleSide, file: u8) -> bool {
+        let rook = Some(Piece::new(color, PieceKind::Rook));
+        let mut beyond = match side {
+            CastleSide::KingSide => file + 1..8,
+            CastleSide::QueenSide => 0..file,
+        };
+        !beyond.any(|f| self.piece_at(Square::new(f, color.back_rank())) == rook)
+    }
+}
+
+fn parse_placement(position: &mut Position, placement: &str) -> Result<(), FenError> {
+    let ranks: Vec<&str> = placement.split('/').collect();
+    if ranks.len() != 8 {
+        return Err(FenError::WrongRankCount(ranks.len()));
+    }
+
+    for (ix, rank_str) in ranks.iter().enumerate() {
+        let rank = 7 - ix as u8;
+        let mut file = 0;
+        for c in rank_str.chars() {
+            if let Some(skip) = c.to_digit(10).filter(|d| (1..=8).contains(d)) {
+                file += skip as u8;
+            } else {
+                let piece = Piece::from_char(c).ok_or(FenError::InvalidPiece(c))?;
+                if file >= 8 {
+                    return Err(FenError::InvalidRankLength(rank + 1));
+                }
+                position.set_piece(Square::new(file, rank), Some(piece));
+                file += 1;
+            }
+
+            if file > 8 {
+                return Err(FenError::InvalidRankLength(rank + 1));
+            }
+        }
+
+        if file != 8 {
+            return Err(FenError::InvalidRankLength(rank + 1));
+        }
+    }
+
+    Ok(())
+}
+
+/// Parses standard, X-FEN and Shredder-FEN castling rights. `K` and `Q` refer to the outermost
+/// rook on that side of the king, a file letter to the rook on that file. Rights for a king or rook
+/// that is not on its back rank are dropped rather than rejected, as such FENs are common in the
+/// wild.
+fn parse_castling_rights(position: &Position, field: &str) -> Result<CastlingRights, FenError> {
+    let mut rights = CastlingRights::none();
+    if field == "-" {
+        return Ok(rights);
+    }
+
+    for c in field.chars() {
+        let color = if c.is_ascii_uppercase() {
+            Color::White
+        } else {
+            Color::Black
+        };
+        let rank = color.back_rank();
+        let rook = Some(Piece::new(color, PieceKind::Rook));
+        let is_rook = |file: &u8| position.piece_at(Square::new(*file, rank)) == rook;
+        let Some(king_file) = position
+            .king_square(color)
+            .filter(|sq| sq.rank() == rank)
+            .map(|sq| sq.file())
+        else {
+            continue;
+        };
+
+        let (side, rook_file) = match c.to_ascii_lowercase() {
+            'k' => (CastleSide::KingSide, (king_file + 1..8).rev().find(is_rook)),
+            'q' => (CastleSide::QueenSide, (0..king_file).find(is_rook)),
+            letter @ 'a'..='h' => {
+                let file = letter as u8 - b'a';
+                let side = if file > king_file {
+                    CastleSide::KingSide
+                } else {
+                    CastleSide::QueenSide
+                };
+                (side, Some(file).filter(|f| *f != king_file && is_rook(f)))
+            }
+            _ => return Err(FenError::InvalidCastlingRights(field.to_string())),
+        };
+        if rook_file.is_some() {
+            rights.set_rook_file(color, side, rook_file);
+        }
+    }
+
+    Ok(rights)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_starting_fen_round_trips() {
+        let position = Position::from_fen(STARTING_FEN).unwrap();
+        assert_eq!(position, Position::starting());
+        assert_eq!(position.to_fen(), STARTING_FEN);
+    }
+
+    #[test]
+    fn test_fen_round_trips() {
+        let fen = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R b Kq e3 3 17";
+        assert_eq!(Position::from_fen(fen).unwrap().to_fen(), fen);
+    }
+
+    #[test]
+    fn test_chess960_castling_rights() {
+        let shredder = "bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9";
+        let position = Position::from_fen(shredder).unwrap();
+        assert_eq!(
+            position
+                .castling_rights()
+                .rook_file(Color::White, CastleSide::QueenSide),
+            Some(5)
+        );
+        assert_eq!(position.to_shredder_fen(), shredder);
+        assert_eq!(
+            position.to_fen(),
+            "bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w KQkq - 2 9"
+        );
+        assert_eq!(Position::from_fen(&position.to_fen()).unwrap(), position);
+
+        // With two rooks on the same side only the inner one needs its file spelled out
+        let x_fen = "4k3/8/8/8/8/8/8/RR2K3 w B - 0 1";
+        let position = Position::from_fen(x_fen).unwrap();
+        assert_eq!(position.to_fen(), x_fen);
+        assert_eq!(position.to_shredder_fen(), x_fen);
+    }
+
+    #[test]
+    fn test_invalid_fens_are_rejected() {
+        let cases = [
+            (
+                "8/8/8/8/8/8/8/8 w - -",
+                FenError::InvalidKingCount(Color::White),
+            ),
+            ("k7/8/8/8/8/8/8 w - -", FenError::WrongRankCount(7)),
+            ("k7/8/8/8/8/8/8/K8 w - -", FenError::InvalidRankLength(1)),
+            ("k7/8/8/8/8/8/8/K6X w - -", FenError::InvalidPiece('X')),
+            (
+                "k7/8/8/8/8/8/8/K7 x - -",
+                FenError::InvalidSideToMove("x".into()),
+            ),
+            (
+                "k7/8/8/8/8/8/8/K7 w KX -",
+                FenError::InvalidCastlingRights("KX".into()),
+            ),
+            (
+                "k7/8/8/8/8/8/8/K7 w - e4",
+                FenError::InvalidEnPassant("e4".into()),
+            ),
+            (
+                "k7/8/8/8/8/8/8/K7 w - e3",
+                FenError::InvalidEnPassant("e3".into()),
+            ),
+            (
+                "k7/8/8/8/8/8/8/K7 b - e6",
+                FenError::InvalidEnPassant("e6".into()),
+            ),
+            (
+                "k7/8/8/8/8/8/8/K7 w - - 0 0",
+                FenError::InvalidFullmoveNumber("0".into()),
+            ),
+            ("k7/8/8/8/8/8/8/K7 w", FenError::WrongFieldCount(2)),
+        ];
+
+        for (fen, error) in cases {
+            assert_eq!(Position::from_fen(fen), Err(error), "{fen}");
+        }
+    }
+}
@@ -0,0 +1,238 @@
+use thiserror::Error;
+
+use crate::chess::moves::Move;
+use crate::chess::piece::Color;
+use crate::chess::position::Position;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GameStatus {
+    Ongoing,
+    Checkmate { winner: Color },
+    Stalemate,
+    Draw(DrawReason),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DrawReason {
+    ThreefoldRepetition,
+    FiftyMoveRule,
+    InsufficientMaterial,
+}
+
+#[derive(Debug, Error, PartialEq, Eq)]
+#[error("illegal move {0}")]
+pub struct IllegalMoveError(pub Move);
+
+/// A game played from some initial position, keeping the history needed to take moves back.
+#[derive(Clone, Debug)]
+pub struct Game {
+    initial_position: Position,
+    position: Position,
+    history: Vec<(Move, Position)>,
+}
+
+impl Default for Game {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Game {
+    pub fn new() -> Self {
+        Self::from_position(Position::starting())
+    }
+
+    pub fn from_position(position: Position) -> Self {
+        Self {
+            initial_position: position.clone(),
+            position,
+            history: Vec::new(),
+        }
+    }
+
+    pub fn initial_position(&self) -> &Position {
+        &self.initial_position
+    }
+
+    pub fn position(&self) -> &Position {
+        &self.position
+    }
+
+    pub fn side_to_move(&self) -> Color {
+        self.position.side_to_move()
+    }
+
+    pub fn legal_moves(&self) -> Vec<Move> {
+        self.position.legal_moves()
+    }
+
+    pub fn moves(&self) -> impl Iterator<Item = Move> + '_ {
+        self.history.iter().map(|(mv, _)| *mv)
+    }
+
+    /// Every position of the game from the initial one up to and including the current one.
+    pub fn positions(&self) -> impl Iterator<Item = &Position> + '_ {
+        self.history
+            .iter()
+            .map(|(_, position)| position)
+            .chain(std::iter::once(&self.position))
+    }
+
+    /// How many times the current position has occurred in the game, counting the current
+    /// occurrence.
+    pub fn repetition_count(&self) -> usize {
+        let hash = self.position.zobrist();
+        // Positions before the last capture or pawn move cannot repeat
+        let reversible = self.position.halfmove_clock() as usize;
+        self.history
+            .iter()
+            .rev()
+            .take(reversible)
+            .filter(|(_, position)| position.zobrist() == hash)
+            .count()
+            + 1
+    }
+
+    pub fn play(&mut self, mv: Move) -> Result<(), IllegalMoveError> {
+        if !self.position.is_legal(mv) {
+            return Err(IllegalMoveError(mv));
+        }
+
+        let previous = self.position.clone();
+        self.position.make_move(mv);
+        self.history.push((mv, previous));
+
+        Ok(())
+    }
+
+    /// Takes back the last move, returning it.
+    pub fn undo(&mut self) -> Option<Move> {
+        self.history.pop().map(|(mv, previous)| {
+            self.position = previous;
+            mv
+        })
+    }
+
+    /// Checkmate and stalemate take precedence over the draw rules, which are applied
+    /// automatically rather than having to be claimed.
+    pub fn status(&self) -> GameStatus {
+        if !self.legal_moves().is_empty() {
+            if self.position.is_insufficient_material() {
+                GameStatus::Draw(DrawReason::InsufficientMaterial)
+            } else if self.position.halfmove_clock() >= 100 {
+                GameStatus::Draw(DrawReason::FiftyMoveRule)
+            } else if self.repetition_count() >= 3 {
+                GameStatus::Draw(DrawReason::ThreefoldRepetition)
+            } else {
+                GameStatus::Ongoing
+            }
+        } else if self.position.is_check() {
+            GameStatus::Checkmate {
+                winner: self.side_to_move().opposite(),
+            }
+        } else {
+            GameStatus::Stalemate
+        }
+    }
+
+    pub fn is_over(&self) -> bool {
+        self.status() != GameStatus::Ongoing
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::chess::moves::MoveKind;
+    use crate::chess::piece::{Piece, PieceKind};
+    use crate::chess::square::Square;
+
+    fn mv(from: (u8, u8), to: (u8, u8), kind: MoveKind) -> Move {
+        Move::new(Square::new(from.0, from.1), Square::new(to.0, to.1), kind)
+    }
+
+    #[test]
+    fn test_fools_mate_is_checkmate() {
+        let mut game = Game::new();
+        game.play(mv((5, 1), (5, 2), MoveKind::Normal)).unwrap();
+        game.play(mv((4, 6), (4, 4), MoveKind::DoublePush)).unwrap();
+        game.play(mv((6, 1), (6, 3), MoveKind::DoublePush)).unwrap();
+        game.play(mv((3, 7), (7, 3), MoveKind::Normal)).unwrap();
+
+        assert_eq!(
+            game.status(),
+            GameStatus::Checkmate {
+                winner: Color::Black
+            }
+        );
+    }
+
+    #[test]
+    fn test_stalemate() {
+        let mut position = Position::empty();
+        position.set_piece(Square::A8, Some(Piece::new(Color::Black, PieceKind::King)));
+        position.set_piece(
+            Square::new(1, 5),
+            Some(Piece::new(Color::White, PieceKind::Queen)),
+        );
+        position.set_piece(
+            Square::new(2, 6),
+            Some(Piece::new(Color::White, PieceKind::King)),
+        );
+        position.set_side_to_move(Color::Black);
+
+        assert_eq!(
+            Game::from_position(position).status(),
+            GameStatus::Stalemate
+        );
+    }
+
+    #[test]
+    fn test_illegal_move_is_rejected_and_undo_restores_position() {
+        let mut game = Game::new();
+        let illegal = mv((4, 1), (4, 4), MoveKind::Normal);
+        assert_eq!(game.play(illegal), Err(IllegalMoveError(illegal)));
+
+        game.play(mv((4, 1), (4, 3), MoveKind::DoublePush)).unwrap();
+        assert_eq!(game.side_to_move(), Color::Black);
+        assert!(game.undo().is_some());
+        assert_eq!(game.position(), &Position::starting());
+    }
+
+    #[test]
+    fn test_draw_rules() {
+        let mut game = Game::new();
+        let shuffle = [
+            mv((6, 0), (5, 2), MoveKind::Normal),
+            mv((6, 7), (5, 5), MoveKind::Normal),
+            mv((5, 2), (6, 0), MoveKind::Normal),
+            mv((5, 5), (6, 7), MoveKind::Normal),
+        ];
+        for mv in shuffle.iter().chain(&shuffle) {
+            assert_eq!(game.status(), GameStatus::Ongoing);
+            game.play(*mv).unwrap();
+        }
+        assert_eq!(game.repetition_count(), 3);
+        assert_eq!(
+            game.status(),
+            GameStatus::Draw(DrawReason::ThreefoldRepetition)
+        );
+
+        let mut game =
+            Game::from_position(Position::from_fen("4k3/8/8/8/8/8/8/R3K3 w - - 99 80").unwrap());
+        game.play(mv((0, 0), (0, 1), MoveKind::Normal)).unwrap();
+        assert_eq!(game.status(), GameStatus::Draw(DrawReason::FiftyMoveRule));
+
+        for (fen, insufficient) in [
+            ("4k3/8/8/8/8/8/8/4K3 w - - 0 1", true),
+            ("4k3/8/8/8/8/8/8/2N1K3 w - - 0 1", true),
+            ("4kb2/8/8/8/8/8/8/2B1K3 w - - 0 1", true),
+            ("4k1b1/8/8/8/8/8/8/2B1K3 w - - 0 1", false),
+            ("4k3/8/8/8/8/8/8/1NN1K3 w - - 0 1", false),
+            ("4k3/8/8/8/8/8/P7/4K3 w - - 0 1", false),
+        ] {
+            let position = Position::from_fen(fen).unwrap();
+            assert_eq!(position.is_insufficient_material(), insufficient, "{fen}");
+        }
+    }
+}
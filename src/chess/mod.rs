@@ -0,0 +1,27 @@
+mod chess960;
+mod fen;
+mod game;
+mod movegen;
+mod moves;
+mod perft;
+mod pgn;
+mod piece;
+mod position;
+mod replay;
+mod san;
+pub mod search;
+mod square;
+pub mod uci;
+mod zobrist;
+
+pub use chess960::CHESS960_POSITIONS;
+pub use fen::{FenError, STARTING_FEN};
+pub use game::{DrawReason, Game, GameStatus, IllegalMoveError};
+pub use moves::{CastleSide, Move, MoveKind};
+pub use perft::{divide, perft};
+pub use pgn::{GameResult, PgnError, PgnGame, PgnMove};
+pub use piece::{Color, Piece, PieceKind};
+pub use position::{CastlingRights, Position};
+pub use replay::Replay;
+pub use san::SanError;
+pub use square::Square;
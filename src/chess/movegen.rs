@@ -0,0 +1,269 @@
+use crate::chess::moves::{CastleSide, Move, MoveKind};
+use crate::chess::piece::{Color, Piece, PieceKind};
+use crate::chess::position::{
+    Position, BISHOP_DIRECTIONS, KING_OFFSETS, KNIGHT_OFFSETS, ROOK_DIRECTIONS,
+};
+use crate::chess::square::Square;
+
+impl Position {
+    /// All legal moves for the side to move.
+    pub fn legal_moves(&self) -> Vec<Move> {
+        let mut moves = Vec::with_capacity(64);
+        self.pseudo_legal_moves(&mut moves);
+
+        let us = self.side_to_move();
+        moves.retain(|&mv| {
+            let mut next = self.clone();
+            next.make_move(mv);
+            next.king_square(us)
+                .map(|sq| !next.is_attacked(sq, us.opposite()))
+                .unwrap_or(true)
+        });
+
+        moves
+    }
+
+    pub fn is_legal(&self, mv: Move) -> bool {
+        self.legal_moves().contains(&mv)
+    }
+
+    /// Moves that follow piece movement rules but may leave the own king in check.
+    fn pseudo_legal_moves(&self, moves: &mut Vec<Move>) {
+        let us = self.side_to_move();
+        for (from, piece) in self.pieces().filter(|(_, p)| p.color == us) {
+            match piece.kind {
+                PieceKind::Pawn => self.pawn_moves(from, moves),
+                PieceKind::Knight => self.step_moves(from, &KNIGHT_OFFSETS, moves),
+                PieceKind::Bishop => self.slider_moves(from, &BISHOP_DIRECTIONS, moves),
+                PieceKind::Rook => self.slider_moves(from, &ROOK_DIRECTIONS, moves),
+                PieceKind::Queen => {
+                    self.slider_moves(from, &BISHOP_DIRECTIONS, moves);
+                    self.slider_moves(from, &ROOK_DIRECTIONS, moves);
+                }
+                PieceKind::King => {
+                    self.step_moves(from, &KING_OFFSETS, moves);
+                    self.castling_moves(from, moves);
+                }
+            }
+        }
+    }
+
+    fn is_enemy(&self, square: Square) -> bool {
+        self.piece_at(square)
+            .map(|p| p.color != self.side_to_move())
+            .unwrap_or(false)
+    }
+
+    fn pawn_moves(&self, from: Square, moves: &mut Vec<Move>) {
+        let us = self.side_to_move();
+        let forward = us.forward();
+        let start_rank = match us {
+            Color::White => 1,
+            Color::Black => 6,
+        };
+        let promotion_rank = us.opposite().back_rank();
+
+        let push = |to: Square, moves: &mut Vec<Move>| {
+            if to.rank() == promotion_rank {
+                for kind in PieceKind::PROMOTIONS {
+                    moves.push(Move::new(from, to, MoveKind::Promotion(kind)));
+                }
+            } else {
+                moves.push(Move::new(from, to, MoveKind::Normal));
+            }
+        };
+
+        if let Some(one) = from.offset(0, forward) {
+            if self.piece_at(one).is_none() {
+                push(one, moves);
+                if from.rank() == start_rank {
+                    let two = one.offset(0, forward).unwrap();
+                    if self.piece_at(two).is_none() {
+                        moves.push(Move::new(from, two, MoveKind::DoublePush));
+                    }
+                }
+            }
+        }
+
+        for file_delta in [-1, 1] {
+            if let Some(to) = from.offset(file_delta, forward) {
+                if self.is_enemy(to) {
+                    push(to, moves);
+                } else if self.en_passant() == Some(to) {
+                    moves.push(Move::new(from, to, MoveKind::EnPassant));
+                }
+            }
+        }
+    }
+
+    fn step_moves(&self, from: Square, offsets: &[(i8, i8)], moves: &mut Vec<Move>) {
+        for &(df, dr) in offsets {
+            if let Some(to) = from.offset(df, dr) {
+                if self.piece_at(to).is_none() || self.is_enemy(to) {
+                    moves.push(Move::new(from, to, MoveKind::Normal));
+                }
+            }
+        }
+    }
+
+    fn slider_moves(&self, from: Square, directions: &[(i8, i8)], moves: &mut Vec<Move>) {
+        for &(df, dr) in directions {
+            let mut current = from.offset(df, dr);
+            while let Some(to) = current {
+                match self.piece_at(to) {
+                    None => moves.push(Move::new(from, to, MoveKind::Normal)),
+                    Some(_) => {
+                        if self.is_enemy(to) {
+                            moves.push(Move::new(from, to, MoveKind::Normal));
+                        }
+                        break;
+                    }
+                }
+                current = to.offset(df, dr);
+            }
+        }
+    }
+
+    /// Castling as generalized for Chess960, which covers standard chess as well: every square
+    /// the king and rook cross or land on must be empty apart from the two of them, and the king
+    /// may not be in check or cross an attacked square.
+    fn castling_moves(&self, from: Square, moves: &mut Vec<Move>) {
+        let us = self.side_to_move();
+        let them = us.opposite();
+        let rank = us.back_rank();
+        if from.rank() != rank || self.is_attacked(from, them) {
+            return;
+        }
+
+        let span = |a: u8, b: u8| a.min(b)..=a.max(b);
+        for side in [CastleSide::KingSide, CastleSide::QueenSide] {
+            let Some(rook_file) = self.castling_rights().rook_file(us, side) else {
+                continue;
+            };
+            if self.piece_at(Square::new(rook_file, rank)) != Some(Piece::new(us, PieceKind::Rook))
+            {
+                continue;
+            }
+
+            let king_file = from.file();
+            let king_to = side.king_file();
+            let path_clear = span(king_file, king_to)
+                .chain(span(rook_file, side.rook_file()))
+                .filter(|&f| f != king_file && f != rook_file)
+                .all(|f| self.piece_at(Square::new(f, rank)).is_none());
+            let path_safe = span(king_file, king_to)
+                .filter(|&f| f != king_file)
+                .all(|f| !self.is_attacked(Square::new(f, rank), them));
+
+            if path_clear && path_safe {
+                moves.push(Move::new(
+                    from,
+                    Square::new(king_to, rank),
+                    MoveKind::Castle(side),
+                ));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::chess::position::CastlingRights;
+
+    fn position_with(pieces: &[(Square, Color, PieceKind)], side_to_move: Color) -> Position {
+        let mut position = Position::empty();
+        for &(sq, color, kind) in pieces {
+            position.set_piece(sq, Some(Piece::new(color, kind)));
+        }
+        position.set_side_to_move(side_to_move);
+        position
+    }
+
+    #[test]
+    fn test_starting_position_has_20_moves() {
+        assert_eq!(Position::starting().legal_moves().len(), 20);
+    }
+
+    #[test]
+    fn test_pinned_piece_cannot_move() {
+        let position = position_with(
+            &[
+                (Square::E1, Color::White, PieceKind::King),
+                (Square::new(4, 3), Color::White, PieceKind::Knight),
+                (Square::E8, Color::Black, PieceKind::Rook),
+                (Square::A8, Color::Black, PieceKind::King),
+            ],
+            Color::White,
+        );
+
+        assert!(position
+            .legal_moves()
+            .iter()
+            .all(|mv| mv.from == Square::E1));
+    }
+
+    #[test]
+    fn test_castling_through_attacked_square_is_illegal() {
+        let mut position = position_with(
+            &[
+                (Square::E1, Color::White, PieceKind::King),
+                (Square::H1, Color::White, PieceKind::Rook),
+                (Square::A1, Color::White, PieceKind::Rook),
+                (Square::F8, Color::Black, PieceKind::Rook),
+                (Square::A8, Color::Black, PieceKind::King),
+            ],
+            Color::White,
+        );
+        let mut rights = CastlingRights::none();
+        rights.set(Color::White, CastleSide::KingSide, true);
+        rights.set(Color::White, CastleSide::QueenSide, true);
+        position.set_castling_rights(rights);
+
+        let castles: Vec<Move> = position
+            .legal_moves()
+            .into_iter()
+            .filter(|mv| matches!(mv.kind, MoveKind::Castle(_)))
+            .collect();
+        assert_eq!(
+            castles,
+            vec![Move::new(
+                Square::E1,
+                Square::C1,
+                MoveKind::Castle(CastleSide::QueenSide)
+            )]
+        );
+    }
+
+    #[test]
+    fn test_en_passant_and_promotion() {
+        let mut position = position_with(
+            &[
+                (Square::E1, Color::White, PieceKind::King),
+                (Square::new(4, 4), Color::White, PieceKind::Pawn),
+                (Square::new(1, 6), Color::White, PieceKind::Pawn),
+                (Square::new(3, 6), Color::Black, PieceKind::Pawn),
+                (Square::H8, Color::Black, PieceKind::King),
+            ],
+            Color::Black,
+        );
+
+        let double_push = Move::new(Square::new(3, 6), Square::new(3, 4), MoveKind::DoublePush);
+        assert!(position.is_legal(double_push));
+        position.make_move(double_push);
+        assert_eq!(position.en_passant(), Some(Square::new(3, 5)));
+
+        let en_passant = Move::new(Square::new(4, 4), Square::new(3, 5), MoveKind::EnPassant);
+        assert!(position.is_legal(en_passant));
+
+        let promotions = position
+            .legal_moves()
+            .into_iter()
+            .filter(|mv| mv.promotion().is_some())
+            .count();
+        assert_eq!(promotions, 4);
+
+        position.make_move(en_passant);
+        assert_eq!(position.piece_at(Square::new(3, 4)), None);
+    }
+}
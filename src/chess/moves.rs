@@ -0,0 +1,69 @@
+use std::fmt;
+
+use crate::chess::piece::PieceKind;
+use crate::chess::square::Square;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum CastleSide {
+    KingSide,
+    QueenSide,
+}
+
+impl CastleSide {
+    /// File the king ends up on, the same in standard chess and Chess960.
+    pub fn king_file(self) -> u8 {
+        match self {
+            CastleSide::KingSide => 6,
+            CastleSide::QueenSide => 2,
+        }
+    }
+
+    /// File the rook ends up on.
+    pub fn rook_file(self) -> u8 {
+        match self {
+            CastleSide::KingSide => 5,
+            CastleSide::QueenSide => 3,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum MoveKind {
+    Normal,
+    DoublePush,
+    EnPassant,
+    Castle(CastleSide),
+    Promotion(PieceKind),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Move {
+    pub from: Square,
+    pub to: Square,
+    pub kind: MoveKind,
+}
+
+impl Move {
+    pub fn new(from: Square, to: Square, kind: MoveKind) -> Self {
+        Self { from, to, kind }
+    }
+
+    pub fn promotion(&self) -> Option<PieceKind> {
+        match self.kind {
+            MoveKind::Promotion(kind) => Some(kind),
+            _ => None,
+        }
+    }
+}
+
+/// Formats the move in coordinate notation, e.g. `e2e4` or `e7e8q`.
+impl fmt::Display for Move {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", self.from, self.to)?;
+        if let Some(kind) = self.promotion() {
+            write!(f, "{}", kind.to_char().to_ascii_lowercase())?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,110 @@
+use crate::chess::moves::Move;
+use crate::chess::position::Position;
+
+/// Counts the leaf nodes of the legal move tree `depth` plies deep.
+pub fn perft(position: &Position, depth: u32) -> u64 {
+    if depth == 0 {
+        return 1;
+    }
+
+    let moves = position.legal_moves();
+    if depth == 1 {
+        return moves.len() as u64;
+    }
+
+    moves
+        .into_iter()
+        .map(|mv| {
+            let mut next = position.clone();
+            next.make_move(mv);
+            perft(&next, depth - 1)
+        })
+        .sum()
+}
+
+/// Perft node counts split by root move, for narrowing down move generation bugs against a
+/// reference engine.
+pub fn divide(position: &Position, depth: u32) -> Vec<(Move, u64)> {
+    assert!(depth > 0, "divide needs at least one ply");
+
+    position
+        .legal_moves()
+        .into_iter()
+        .map(|mv| {
+            let mut next = position.clone();
+            next.make_move(mv);
+            (mv, perft(&next, depth - 1))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::chess::fen::STARTING_FEN;
+
+    const KIWIPETE: &str = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+    const POSITION_3: &str = "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1";
+    const POSITION_4: &str = "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1";
+    const POSITION_5: &str = "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8";
+    const POSITION_6: &str =
+        "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10";
+
+    fn assert_perft(fen: &str, expected: &[u64]) {
+        let position = Position::from_fen(fen).unwrap();
+        for (depth, &nodes) in expected.iter().enumerate() {
+            let depth = depth as u32 + 1;
+            assert_eq!(perft(&position, depth), nodes, "{fen} at depth {depth}");
+        }
+    }
+
+    #[test]
+    fn test_perft_starting_position() {
+        assert_perft(STARTING_FEN, &[20, 400, 8902, 197281]);
+    }
+
+    #[test]
+    fn test_perft_kiwipete() {
+        assert_perft(KIWIPETE, &[48, 2039, 97862]);
+    }
+
+    #[test]
+    fn test_perft_position_3() {
+        assert_perft(POSITION_3, &[14, 191, 2812, 43238]);
+    }
+
+    #[test]
+    fn test_perft_position_4() {
+        assert_perft(POSITION_4, &[6, 264, 9467]);
+    }
+
+    #[test]
+    fn test_perft_position_5() {
+        assert_perft(POSITION_5, &[44, 1486, 62379]);
+    }
+
+    #[test]
+    fn test_perft_position_6() {
+        assert_perft(POSITION_6, &[46, 2079, 89890]);
+    }
+
+    #[test]
+    fn test_divide_sums_to_perft() {
+        let position = Position::from_fen(KIWIPETE).unwrap();
+        let divided = divide(&position, 2);
+        assert_eq!(divided.len(), 48);
+        assert_eq!(divided.iter().map(|(_, n)| n).sum::<u64>(), 2039);
+    }
+
+    // Deeper counts take a while in debug builds, run with `cargo test --release -- --ignored`
+    #[test]
+    #[ignore]
+    fn test_perft_deep() {
+        assert_perft(STARTING_FEN, &[20, 400, 8902, 197281, 4865609]);
+        assert_perft(KIWIPETE, &[48, 2039, 97862, 4085603]);
+        assert_perft(POSITION_3, &[14, 191, 2812, 43238, 674624]);
+        assert_perft(POSITION_4, &[6, 264, 9467, 422333]);
+        assert_perft(POSITION_5, &[44, 1486, 62379, 2103487]);
+        assert_perft(POSITION_6, &[46, 2079, 89890, 3894594]);
+    }
+}
@@ -0,0 +1,600 @@
+use std::fmt;
+use std::io::{self, Read};
+use std::iter::Peekable;
+use std::path::Path;
+use std::str::Chars;
+
+use thiserror::Error;
+
+use crate::assets::AssetLocator;
+use crate::chess::fen::FenError;
+use crate::chess::game::{Game, GameStatus};
+use crate::chess::moves::Move;
+use crate::chess::piece::Color;
+use crate::chess::position::Position;
+use crate::chess::san::SanError;
+
+const MAX_LINE_LENGTH: usize = 80;
+
+#[derive(Debug, Error)]
+pub enum PgnError {
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    #[error("invalid FEN tag: {0}")]
+    Fen(#[from] FenError),
+    #[error("move {ply}: {source}")]
+    San { ply: usize, source: SanError },
+    #[error("unterminated {0}")]
+    Unterminated(&'static str),
+    #[error("unexpected \"{0}\"")]
+    UnexpectedToken(String),
+    #[error("variation does not follow a move")]
+    OrphanVariation,
+    #[error("no games found")]
+    NoGames,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GameResult {
+    WhiteWins,
+    BlackWins,
+    Draw,
+    Unknown,
+}
+
+impl GameResult {
+    fn parse(token: &str) -> Option<Self> {
+        match token {
+            "1-0" => Some(GameResult::WhiteWins),
+            "0-1" => Some(GameResult::BlackWins),
+            "1/2-1/2" => Some(GameResult::Draw),
+            "*" => Some(GameResult::Unknown),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for GameResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            GameResult::WhiteWins => "1-0",
+            GameResult::BlackWins => "0-1",
+            GameResult::Draw => "1/2-1/2",
+            GameResult::Unknown => "*",
+        })
+    }
+}
+
+impl From<GameStatus> for GameResult {
+    fn from(status: GameStatus) -> Self {
+        match status {
+            GameStatus::Ongoing => GameResult::Unknown,
+            GameStatus::Checkmate {
+                winner: Color::White,
+            } => GameResult::WhiteWins,
+            GameStatus::Checkmate {
+                winner: Color::Black,
+            } => GameResult::BlackWins,
+            GameStatus::Stalemate | GameStatus::Draw(_) => GameResult::Draw,
+        }
+    }
+}
+
+/// A move in a PGN game together with its annotations.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PgnMove {
+    pub mv: Move,
+    pub san: String,
+    pub nags: Vec<u8>,
+    /// Comment placed before the move, only expected at the start of a game or variation.
+    pub leading_comment: Option<String>,
+    pub comment: Option<String>,
+    /// Alternatives to this move, each played from the position before it.
+    pub variations: Vec<Vec<PgnMove>>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PgnGame {
+    pub tags: Vec<(String, String)>,
+    pub initial_position: Position,
+    pub moves: Vec<PgnMove>,
+    pub result: GameResult,
+}
+
+impl PgnGame {
+    /// Reads all games from a PGN file in the assets.
+    pub fn from_asset(locator: &AssetLocator, path: &Path) -> Result<Vec<Self>, PgnError> {
+        let mut reader = locator.open(path)?;
+        let mut text = String::new();
+        reader.read_to_string(&mut text)?;
+
+        Self::parse_all(&text)
+    }
+
+    pub fn parse_all(text: &str) -> Result<Vec<Self>, PgnError> {
+        let tokens = tokenize(text)?;
+        let mut parser = Parser { tokens, next: 0 };
+        let mut games = Vec::new();
+        while parser.peek().is_some() {
+            games.push(parser.game()?);
+        }
+
+        Ok(games)
+    }
+
+    /// Builds a PGN record of the moves played in `game`.
+    pub fn from_game(game: &Game, tags: Vec<(String, String)>) -> Self {
+        let mut position = game.initial_position().clone();
+        let moves = game
+            .moves()
+            .map(|mv| {
+                let san = position.san(mv);
+                position.make_move(mv);
+                PgnMove {
+                    mv,
+                    san,
+                    nags: Vec::new(),
+                    leading_comment: None,
+                    comment: None,
+                    variations: Vec::new(),
+                }
+            })
+            .collect();
+
+        Self {
+            tags,
+            initial_position: game.initial_position().clone(),
+            moves,
+            result: game.status().into(),
+        }
+    }
+
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tags
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| v.as_str())
+    }
+
+    pub fn mainline(&self) -> impl Iterator<Item = Move> + '_ {
+        self.moves.iter().map(|m| m.mv)
+    }
+
+    /// Creates a game at the initial position, ready to have the mainline replayed on it.
+    pub fn game(&self) -> Game {
+        Game::from_position(self.initial_position.clone())
+    }
+
+    pub fn to_pgn(&self) -> String {
+        let mut pgn = String::new();
+        for (name, value) in &self.tags {
+            if name == "Result" {
+                continue;
+            }
+            let value = value.replace('\\', "\\\\").replace('"', "\\\"");
+            pgn.push_str(&format!("[{name} \"{value}\"]\n"));
+        }
+        pgn.push_str(&format!("[Result \"{}\"]\n", self.result));
+        if self.initial_position != Position::starting() && self.tag("FEN").is_none() {
+            pgn.push_str("[SetUp \"1\"]\n");
+            pgn.push_str(&format!("[FEN \"{}\"]\n", self.initial_position.to_fen()));
+        }
+        pgn.push('\n');
+
+        let mut words = Vec::new();
+        write_line(&mut words, &self.moves, &self.initial_position);
+        words.push(self.result.to_string());
+
+        let mut line_length = 0;
+        for word in words {
+            if line_length > 0 && line_length + word.len() + 1 > MAX_LINE_LENGTH {
+                pgn.push('\n');
+                line_length = 0;
+            } else if line_length > 0 {
+                pgn.push(' ');
+                line_length += 1;
+            }
+            pgn.push_str(&word);
+            line_length += word.len();
+        }
+        pgn.push('\n');
+
+        pgn
+    }
+}
+
+fn write_line(words: &mut Vec<String>, moves: &[PgnMove], position: &Position) {
+    let mut position = position.clone();
+    let mut needs_number = true;
+    for m in moves {
+        if let Some(comment) = &m.leading_comment {
+            words.push(format!("{{{}}}", escape_comment(comment)));
+            needs_number = true;
+        }
+
+        let number = position.fullmove_number();
+        match position.side_to_move() {
+            Color::White => words.push(format!("{number}. {}", m.san)),
+            Color::Black if needs_number => words.push(format!("{number}... {}", m.san)),
+            Color::Black => words.push(m.san.clone()),
+        }
+        needs_number = false;
+
+        for nag in &m.nags {
+            words.push(format!("${nag}"));
+        }
+        if let Some(comment) = &m.comment {
+            words.push(format!("{{{}}}", escape_comment(comment)));
+            needs_number = true;
+        }
+        for variation in &m.variations {
+            let mut variation_words = Vec::new();
+            write_line(&mut variation_words, variation, &position);
+            words.push(format!("({})", variation_words.join(" ")));
+            needs_number = true;
+        }
+
+        position.make_move(m.mv);
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Token {
+    Tag(String, String),
+    Comment(String),
+    Nag(u8),
+    VariationStart,
+    VariationEnd,
+    Result(GameResult),
+    San(String),
+}
+
+fn tokenize(text: &str) -> Result<Vec<Token>, PgnError> {
+    let mut tokens = Vec::new();
+    let mut chars = text.chars().peekable();
+    let mut line_start = true;
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\n' => {
+                line_start = true;
+                continue;
+            }
+            // Escape mechanism: the rest of the line is ignored
+            '%' if line_start => {
+                chars.by_ref().take_while(|&c| c != '\n').for_each(drop);
+                continue;
+            }
+            ';' => {
+                let comment: String = chars.by_ref().take_while(|&c| c != '\n').collect();
+                tokens.push(Token::Comment(comment.trim().to_string()));
+                line_start = true;
+                continue;
+            }
+            '{' => {
+                let mut comment = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        // Only escapes written by `escape_comment`, other backslashes are kept
+                        Some('\\') => match chars.next_if(|&c| c == '}' || c == '\\') {
+                            Some(c) => comment.push(c),
+                            None => comment.push('\\'),
+                        },
+                        Some(c) => comment.push(c),
+                        None => return Err(PgnError::Unterminated("comment")),
+                    }
+                }
+                tokens.push(Token::Comment(comment.trim().to_string()));
+            }
+            '[' => tokens.push(parse_tag(&mut chars)?),
+            '(' => tokens.push(Token::VariationStart),
+            ')' => tokens.push(Token::VariationEnd),
+            '*' => tokens.push(Token::Result(GameResult::Unknown)),
+            '$' => {
+                let mut digits = String::new();
+                while let Some(d) = chars.next_if(char::is_ascii_digit) {
+                    digits.push(d);
+                }
+                let nag = digits
+                    .parse()
+                    .map_err(|_| PgnError::UnexpectedToken(format!("${digits}")))?;
+                tokens.push(Token::Nag(nag));
+            }
+            '!' | '?' => {
+                let mut suffix = c.to_string();
+                while let Some(s) = chars.next_if(|&c| c == '!' || c == '?') {
+                    suffix.push(s);
+                }
+                let nag = match suffix.as_str() {
+                    "!" => 1,
+                    "?" => 2,
+                    "!!" => 3,
+                    "??" => 4,
+                    "!?" => 5,
+                    "?!" => 6,
+                    _ => return Err(PgnError::UnexpectedToken(suffix)),
+                };
+                tokens.push(Token::Nag(nag));
+            }
+            c if c.is_whitespace() || c == '.' => {}
+            c if c.is_ascii_alphanumeric() => {
+                let mut symbol = c.to_string();
+                while let Some(s) =
+                    chars.next_if(|&c| c.is_ascii_alphanumeric() || "_+#=:-/".contains(c))
+                {
+                    symbol.push(s);
+                }
+
+                if let Some(result) = GameResult::parse(&symbol) {
+                    tokens.push(Token::Result(result));
+                } else if !symbol.chars().all(|c| c.is_ascii_digit()) {
+                    tokens.push(Token::San(symbol));
+                }
+            }
+            c => return Err(PgnError::UnexpectedToken(c.to_string())),
+        }
+        line_start = false;
+    }
+
+    Ok(tokens)
+}
+
+/// Parses a tag following its opening bracket. The value is a string in which `"` and `\` are
+/// escaped with a backslash, so it may contain brackets.
+fn parse_tag(chars: &mut Peekable<Chars>) -> Result<Token, PgnError> {
+    skip_whitespace(chars);
+    let mut name = String::new();
+    while let Some(c) = chars.next_if(|&c| !c.is_whitespace() && c != '"' && c != ']') {
+        name.push(c);
+    }
+    skip_whitespace(chars);
+    if name.is_empty() || chars.next() != Some('"') {
+        return Err(PgnError::UnexpectedToken(format!("[{name}")));
+    }
+
+    let mut value = String::new();
+    loop {
+        match chars.next() {
+            Some('"') => break,
+            Some('\\') => match chars.next() {
+                Some(c) => value.push(c),
+                None => return Err(PgnError::Unterminated("tag value")),
+            },
+            Some(c) => value.push(c),
+            None => return Err(PgnError::Unterminated("tag value")),
+        }
+    }
+
+    skip_whitespace(chars);
+    if chars.next() != Some(']') {
+        return Err(PgnError::Unterminated("tag"));
+    }
+
+    Ok(Token::Tag(name, value))
+}
+
+fn skip_whitespace(chars: &mut Peekable<Chars>) {
+    while chars.next_if(|c| c.is_whitespace()).is_some() {}
+}
+
+/// Escapes `}` and `\` in a brace comment, which the tokenizer reads back.
+fn escape_comment(comment: &str) -> String {
+    comment.replace('\\', "\\\\").replace('}', "\\}")
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    next: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.next)
+    }
+
+    fn advance(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.next).cloned();
+        self.next += 1;
+        token
+    }
+
+    fn game(&mut self) -> Result<PgnGame, PgnError> {
+        let mut tags = Vec::new();
+        while let Some(Token::Tag(..)) = self.peek() {
+            if let Some(Token::Tag(name, value)) = self.advance() {
+                tags.push((name, value));
+            }
+        }
+
+        let initial_position = match tags.iter().find(|(name, _)| name == "FEN") {
+            Some((_, fen)) => Position::from_fen(fen)?,
+            None => Position::starting(),
+        };
+
+        let moves = self.line(&initial_position, 0)?;
+        let result = match self.peek() {
+            Some(Token::Result(result)) => {
+                let result = *result;
+                self.advance();
+                result
+            }
+            _ => tags
+                .iter()
+                .find(|(name, _)| name == "Result")
+                .and_then(|(_, value)| GameResult::parse(value))
+                .unwrap_or(GameResult::Unknown),
+        };
+
+        Ok(PgnGame {
+            tags,
+            initial_position,
+            moves,
+            result,
+        })
+    }
+
+    /// Parses moves up to the end of the game or the enclosing variation.
+    fn line(&mut self, position: &Position, depth: usize) -> Result<Vec<PgnMove>, PgnError> {
+        let mut moves: Vec<PgnMove> = Vec::new();
+        let mut position = position.clone();
+        let mut previous_position = position.clone();
+        let mut pending_comment = None;
+
+        loop {
+            match self.peek() {
+                None | Some(Token::Tag(..)) | Some(Token::Result(_)) if depth == 0 => break,
+                None | Some(Token::Tag(..)) | Some(Token::Result(_)) => {
+                    return Err(PgnError::Unterminated("variation"));
+                }
+                Some(Token::VariationEnd) if depth == 0 => {
+                    return Err(PgnError::UnexpectedToken(")".to_string()));
+                }
+                Some(Token::VariationEnd) => {
+                    self.advance();
+                    break;
+                }
+                _ => {}
+            }
+
+            match self.advance().unwrap() {
+                Token::San(san) => {
+                    let mv = position.parse_san(&san).map_err(|source| PgnError::San {
+                        ply: moves.len() + 1,
+                        source,
+                    })?;
+                    moves.push(PgnMove {
+                        mv,
+                        san: position.san(mv),
+                        nags: Vec::new(),
+                        leading_comment: pending_comment.take(),
+                        comment: None,
+                        variations: Vec::new(),
+                    });
+                    previous_position = position.clone();
+                    position.make_move(mv);
+                }
+                Token::Nag(nag) => match moves.last_mut() {
+                    Some(last) => last.nags.push(nag),
+                    None => return Err(PgnError::UnexpectedToken(format!("${nag}"))),
+                },
+                Token::Comment(comment) => match moves.last_mut() {
+                    Some(last) if last.comment.is_none() => last.comment = Some(comment),
+                    Some(last) => {
+                        let existing = last.comment.as_mut().unwrap();
+                        existing.push(' ');
+                        existing.push_str(&comment);
+                    }
+                    None => pending_comment = Some(comment),
+                },
+                Token::VariationStart => {
+                    if moves.is_empty() {
+                        return Err(PgnError::OrphanVariation);
+                    }
+                    let variation = self.line(&previous_position, depth + 1)?;
+                    moves.last_mut().unwrap().variations.push(variation);
+                }
+                Token::Tag(..) | Token::Result(_) | Token::VariationEnd => unreachable!(),
+            }
+        }
+
+        Ok(moves)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const ANNOTATED: &str = r#"
+[Event "Test \"quoted\""]
+[Site "?"]
+[Result "1-0"]
+
+{Opening comment} 1. e4 e5 2. Nf3 $1 {Develops} (2. f4 exf4 (2... d5) 3. Bc4) 2... Nc6
+3. Bb5!? a6 ; rest of line comment
+4. Ba4 1-0
+
+[Event "Second"]
+1. d4 d5 *
+"#;
+
+    #[test]
+    fn test_annotated_pgn_parses() {
+        let games = PgnGame::parse_all(ANNOTATED).unwrap();
+        assert_eq!(games.len(), 2);
+
+        let game = &games[0];
+        assert_eq!(game.tag("Event"), Some("Test \"quoted\""));
+        assert_eq!(game.result, GameResult::WhiteWins);
+
+        let sans: Vec<&str> = game.moves.iter().map(|m| m.san.as_str()).collect();
+        assert_eq!(sans, ["e4", "e5", "Nf3", "Nc6", "Bb5", "a6", "Ba4"]);
+        assert_eq!(
+            game.moves[0].leading_comment.as_deref(),
+            Some("Opening comment")
+        );
+        assert_eq!(game.moves[2].nags, [1]);
+        assert_eq!(game.moves[2].comment.as_deref(), Some("Develops"));
+        assert_eq!(game.moves[4].nags, [5]);
+        assert_eq!(
+            game.moves[5].comment.as_deref(),
+            Some("rest of line comment")
+        );
+
+        let variation = &game.moves[2].variations[0];
+        assert_eq!(variation.len(), 3);
+        assert_eq!(variation[1].variations[0][0].san, "d5");
+
+        assert_eq!(games[1].result, GameResult::Unknown);
+        assert_eq!(games[1].moves.len(), 2);
+    }
+
+    #[test]
+    fn test_pgn_round_trips() {
+        let game = &PgnGame::parse_all(ANNOTATED).unwrap()[0];
+        let written = game.to_pgn();
+        let reread = &PgnGame::parse_all(&written).unwrap()[0];
+        assert_eq!(reread, game);
+    }
+
+    #[test]
+    fn test_escaped_tags_and_comments_round_trip() {
+        let pgn = r#"[Event "Blitz [3+2]"]
+[Site "C:\\games \"club\""]
+[Result "*"]
+
+1. e4 {Threatens \} nothing} *
+"#;
+        let mut game = PgnGame::parse_all(pgn).unwrap().remove(0);
+        assert_eq!(game.tag("Event"), Some("Blitz [3+2]"));
+        assert_eq!(game.tag("Site"), Some(r#"C:\games "club""#));
+        assert_eq!(
+            game.moves[0].comment.as_deref(),
+            Some("Threatens } nothing")
+        );
+
+        game.moves[0].leading_comment = Some(r"{braces} and a \ backslash".to_string());
+        let reread = PgnGame::parse_all(&game.to_pgn()).unwrap().remove(0);
+        assert_eq!(reread, game);
+    }
+
+    #[test]
+    fn test_illegal_move_is_reported() {
+        let error = PgnGame::parse_all("1. e4 e5 2. Ke3 *").unwrap_err();
+        assert!(matches!(error, PgnError::San { ply: 3, .. }));
+    }
+
+    #[test]
+    fn test_bundled_game_loads() {
+        let locator = AssetLocator::new();
+        let games = PgnGame::from_asset(&locator, Path::new("games/immortal.pgn")).unwrap();
+        let pgn = &games[0];
+
+        let mut game = pgn.game();
+        for mv in pgn.mainline() {
+            game.play(mv).unwrap();
+        }
+        assert_eq!(GameResult::from(game.status()), pgn.result);
+    }
+}
@@ -0,0 +1,355 @@
+use crate::chess::moves::{CastleSide, Move, MoveKind};
+use crate::chess::piece::{Color, Piece, PieceKind};
+use crate::chess::square::Square;
+use crate::chess::zobrist;
+
+pub(crate) const KNIGHT_OFFSETS: [(i8, i8); 8] = [
+    (1, 2),
+    (2, 1),
+    (2, -1),
+    (1, -2),
+    (-1, -2),
+    (-2, -1),
+    (-2, 1),
+    (-1, 2),
+];
+pub(crate) const KING_OFFSETS: [(i8, i8); 8] = [
+    (0, 1),
+    (1, 1),
+    (1, 0),
+    (1, -1),
+    (0, -1),
+    (-1, -1),
+    (-1, 0),
+    (-1, 1),
+];
+pub(crate) const ROOK_DIRECTIONS: [(i8, i8); 4] = [(0, 1), (1, 0), (0, -1), (-1, 0)];
+pub(crate) const BISHOP_DIRECTIONS: [(i8, i8); 4] = [(1, 1), (1, -1), (-1, -1), (-1, 1)];
+
+/// Which rooks each side may still castle with, stored as the rook's file so that Chess960
+/// positions with rooks away from the corners are covered too.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct CastlingRights([[Option<u8>; 2]; 2]);
+
+impl CastlingRights {
+    pub fn none() -> Self {
+        Self::default()
+    }
+
+    /// All four rights with the rooks in the corners, as in standard chess.
+    pub fn all() -> Self {
+        Self([[Some(7), Some(0)]; 2])
+    }
+
+    pub fn has(&self, color: Color, side: CastleSide) -> bool {
+        self.rook_file(color, side).is_some()
+    }
+
+    /// Grants or revokes a right, with the rook in the corner when granted.
+    pub fn set(&mut self, color: Color, side: CastleSide, allowed: bool) {
+        let corner = match side {
+            CastleSide::KingSide => 7,
+            CastleSide::QueenSide => 0,
+        };
+        self.set_rook_file(color, side, allowed.then_some(corner));
+    }
+
+    /// File of the rook `color` may castle with on `side`.
+    pub fn rook_file(&self, color: Color, side: CastleSide) -> Option<u8> {
+        self.0[color.index()][side as usize]
+    }
+
+    pub fn set_rook_file(&mut self, color: Color, side: CastleSide, file: Option<u8>) {
+        self.0[color.index()][side as usize] = file;
+    }
+}
+
+/// A chess position: piece placement plus everything needed to decide which moves are legal.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Position {
+    board: [Option<Piece>; 64],
+    side_to_move: Color,
+    castling_rights: CastlingRights,
+    en_passant: Option<Square>,
+    halfmove_clock: u32,
+    fullmove_number: u32,
+    /// Zobrist hash of everything but the en passant square, kept up to date by the setters.
+    hash: u64,
+}
+
+impl Default for Position {
+    fn default() -> Self {
+        Self::starting()
+    }
+}
+
+impl Position {
+    pub fn empty() -> Self {
+        Self {
+            board: [None; 64],
+            side_to_move: Color::White,
+            castling_rights: CastlingRights::none(),
+            en_passant: None,
+            halfmove_clock: 0,
+            fullmove_number: 1,
+            hash: zobrist::turn_key(Color::White),
+        }
+    }
+
+    pub fn starting() -> Self {
+        use PieceKind::*;
+        const BACK_RANK: [PieceKind; 8] = [Rook, Knight, Bishop, Queen, King, Bishop, Knight, Rook];
+
+        let mut position = Self::empty();
+        for (file, kind) in BACK_RANK.iter().enumerate() {
+            let file = file as u8;
+            position.set_piece(Square::new(file, 0), Some(Piece::new(Color::White, *kind)));
+            position.set_piece(Square::new(file, 1), Some(Piece::new(Color::White, Pawn)));
+            position.set_piece(Square::new(file, 6), Some(Piece::new(Color::Black, Pawn)));
+            position.set_piece(Square::new(file, 7), Some(Piece::new(Color::Black, *kind)));
+        }
+        position.set_castling_rights(CastlingRights::all());
+
+        position
+    }
+
+    pub fn piece_at(&self, square: Square) -> Option<Piece> {
+        self.board[square.index()]
+    }
+
+    pub fn set_piece(&mut self, square: Square, piece: Option<Piece>) {
+        let slot = &mut self.board[square.index()];
+        if let Some(old) = *slot {
+            self.hash ^= zobrist::piece_key(old, square);
+        }
+        if let Some(new) = piece {
+            self.hash ^= zobrist::piece_key(new, square);
+        }
+        *slot = piece;
+    }
+
+    pub fn pieces(&self) -> impl Iterator<Item = (Square, Piece)> + '_ {
+        Square::all().filter_map(|sq| self.piece_at(sq).map(|p| (sq, p)))
+    }
+
+    pub fn side_to_move(&self) -> Color {
+        self.side_to_move
+    }
+
+    pub fn set_side_to_move(&mut self, color: Color) {
+        self.hash ^= zobrist::turn_key(self.side_to_move) ^ zobrist::turn_key(color);
+        self.side_to_move = color;
+    }
+
+    pub fn castling_rights(&self) -> CastlingRights {
+        self.castling_rights
+    }
+
+    pub fn set_castling_rights(&mut self, rights: CastlingRights) {
+        self.hash ^= zobrist::castling_key(self.castling_rights) ^ zobrist::castling_key(rights);
+        self.castling_rights = rights;
+    }
+
+    pub fn en_passant(&self) -> Option<Square> {
+        self.en_passant
+    }
+
+    pub fn set_en_passant(&mut self, square: Option<Square>) {
+        self.en_passant = square;
+    }
+
+    pub fn halfmove_clock(&self) -> u32 {
+        self.halfmove_clock
+    }
+
+    pub fn set_halfmove_clock(&mut self, value: u32) {
+        self.halfmove_clock = value;
+    }
+
+    pub fn fullmove_number(&self) -> u32 {
+        self.fullmove_number
+    }
+
+    pub fn set_fullmove_number(&mut self, value: u32) {
+        self.fullmove_number = value;
+    }
+
+    /// Zobrist hash identifying the position for repetition detection, transposition tables and
+    /// opening books. Move clocks are not part of it. See `zobrist` for how it is computed.
+    pub fn zobrist(&self) -> u64 {
+        match self.en_passant {
+            Some(sq) if self.en_passant_capturable(sq) => self.hash ^ zobrist::en_passant_key(sq),
+            _ => self.hash,
+        }
+    }
+
+    fn en_passant_capturable(&self, square: Square) -> bool {
+        let us = self.side_to_move;
+        let pawn = Some(Piece::new(us, PieceKind::Pawn));
+        [-1, 1].iter().any(|&df| {
+            square
+                .offset(df, -us.forward())
+                .is_some_and(|sq| self.piece_at(sq) == pawn)
+        })
+    }
+
+    /// Whether neither side has enough material left to deliver checkmate: bare kings, a single
+    /// minor piece, or only bishops that all stand on squares of the same colour.
+    pub fn is_insufficient_material(&self) -> bool {
+        let mut knights = 0;
+        let mut bishops_by_square_color = [0; 2];
+        for (square, piece) in self.pieces() {
+            match piece.kind {
+                PieceKind::King => {}
+                PieceKind::Knight => knights += 1,
+                PieceKind::Bishop => {
+                    bishops_by_square_color[((square.file() + square.rank()) % 2) as usize] += 1
+                }
+                _ => return false,
+            }
+        }
+
+        let [dark, light] = bishops_by_square_color;
+        knights + dark + light <= 1 || knights == 0 && (dark == 0 || light == 0)
+    }
+
+    pub fn king_square(&self, color: Color) -> Option<Square> {
+        self.pieces()
+            .find(|(_, p)| p.color == color && p.kind == PieceKind::King)
+            .map(|(sq, _)| sq)
+    }
+
+    /// Whether the side to move is in check.
+    pub fn is_check(&self) -> bool {
+        let us = self.side_to_move;
+        self.king_square(us)
+            .map(|sq| self.is_attacked(sq, us.opposite()))
+            .unwrap_or(false)
+    }
+
+    /// Whether any piece of color `by` attacks `square`.
+    pub fn is_attacked(&self, square: Square, by: Color) -> bool {
+        let is = |sq: Option<Square>, kinds: &[PieceKind]| {
+            sq.and_then(|sq| self.piece_at(sq))
+                .map(|p| p.color == by && kinds.contains(&p.kind))
+                .unwrap_or(false)
+        };
+
+        let pawn_rank = -by.forward();
+        if is(square.offset(-1, pawn_rank), &[PieceKind::Pawn])
+            || is(square.offset(1, pawn_rank), &[PieceKind::Pawn])
+        {
+            return true;
+        }
+
+        if KNIGHT_OFFSETS
+            .iter()
+            .any(|&(df, dr)| is(square.offset(df, dr), &[PieceKind::Knight]))
+        {
+            return true;
+        }
+
+        if KING_OFFSETS
+            .iter()
+            .any(|&(df, dr)| is(square.offset(df, dr), &[PieceKind::King]))
+        {
+            return true;
+        }
+
+        let slider_attacks = |directions: &[(i8, i8)], kinds: &[PieceKind]| {
+            directions.iter().any(|&(df, dr)| {
+                let mut current = square.offset(df, dr);
+                while let Some(sq) = current {
+                    if let Some(p) = self.piece_at(sq) {
+                        return p.color == by && kinds.contains(&p.kind);
+                    }
+                    current = sq.offset(df, dr);
+                }
+                false
+            })
+        };
+
+        slider_attacks(&ROOK_DIRECTIONS, &[PieceKind::Rook, PieceKind::Queen])
+            || slider_attacks(&BISHOP_DIRECTIONS, &[PieceKind::Bishop, PieceKind::Queen])
+    }
+
+    /// Piece captured by `mv`, if any. Does not check that the move is legal.
+    pub fn captured_piece(&self, mv: Move) -> Option<Piece> {
+        match mv.kind {
+            MoveKind::EnPassant => self.piece_at(Square::new(mv.to.file(), mv.from.rank())),
+            MoveKind::Castle(_) => None,
+            _ => self.piece_at(mv.to),
+        }
+    }
+
+    /// Applies `mv` to the position without checking that it is legal.
+    pub fn make_move(&mut self, mv: Move) {
+        let piece = self
+            .piece_at(mv.from)
+            .expect("a move must start on an occupied square");
+        let us = piece.color;
+        let captured = self.captured_piece(mv);
+
+        if piece.kind == PieceKind::Pawn || captured.is_some() {
+            self.halfmove_clock = 0;
+        } else {
+            self.halfmove_clock += 1;
+        }
+
+        self.set_piece(mv.from, None);
+        match mv.kind {
+            MoveKind::EnPassant => {
+                self.set_piece(Square::new(mv.to.file(), mv.from.rank()), None);
+                self.set_piece(mv.to, Some(piece));
+            }
+            MoveKind::Promotion(kind) => {
+                self.set_piece(mv.to, Some(Piece::new(us, kind)));
+            }
+            MoveKind::Castle(side) => {
+                // In Chess960 the king may land where the rook stood or the other way around, so
+                // both leave the board before either is placed
+                let rank = us.back_rank();
+                let rook_from = self
+                    .castling_rights
+                    .rook_file(us, side)
+                    .map(|file| Square::new(file, rank))
+                    .expect("castling requires the right to castle");
+                let rook_to = Square::new(side.rook_file(), rank);
+                let rook = self.piece_at(rook_from);
+                self.set_piece(rook_from, None);
+                self.set_piece(mv.to, Some(piece));
+                self.set_piece(rook_to, rook);
+            }
+            MoveKind::Normal | MoveKind::DoublePush => {
+                self.set_piece(mv.to, Some(piece));
+            }
+        }
+
+        self.en_passant = match mv.kind {
+            MoveKind::DoublePush => mv.from.offset(0, us.forward()),
+            _ => None,
+        };
+
+        let mut rights = self.castling_rights;
+        if piece.kind == PieceKind::King {
+            rights.set(us, CastleSide::KingSide, false);
+            rights.set(us, CastleSide::QueenSide, false);
+        }
+        for color in [Color::White, Color::Black] {
+            for side in [CastleSide::KingSide, CastleSide::QueenSide] {
+                let rook_square = rights
+                    .rook_file(color, side)
+                    .map(|file| Square::new(file, color.back_rank()));
+                if rook_square == Some(mv.from) || rook_square == Some(mv.to) {
+                    rights.set(color, side, false);
+                }
+            }
+        }
+        self.set_castling_rights(rights);
+
+        if us == Color::Black {
+            self.fullmove_number += 1;
+        }
+        self.set_side_to_move(us.opposite());
+    }
+}
@@ -0,0 +1,51 @@
+use crate::chess::game::Game;
+use crate::chess::moves::Move;
+
+/// Steps a game back and forth through a recorded list of moves, e.g. the mainline of a PGN.
+#[derive(Clone, Debug)]
+pub struct Replay {
+    moves: Vec<Move>,
+    ply: usize,
+}
+
+impl Replay {
+    /// Creates a replay positioned before the first move. The game it is used with must be at the
+    /// position the moves were recorded from.
+    pub fn new(moves: impl IntoIterator<Item = Move>) -> Self {
+        Self {
+            moves: moves.into_iter().collect(),
+            ply: 0,
+        }
+    }
+
+    pub fn ply(&self) -> usize {
+        self.ply
+    }
+
+    pub fn len(&self) -> usize {
+        self.moves.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.moves.is_empty()
+    }
+
+    pub fn step_forward(&mut self, game: &mut Game) -> Option<Move> {
+        let mv = *self.moves.get(self.ply)?;
+        game.play(mv).ok()?;
+        self.ply += 1;
+
+        Some(mv)
+    }
+
+    pub fn step_back(&mut self, game: &mut Game) -> Option<Move> {
+        if self.ply == 0 {
+            return None;
+        }
+
+        let mv = game.undo()?;
+        self.ply -= 1;
+
+        Some(mv)
+    }
+}
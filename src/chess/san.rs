@@ -0,0 +1,209 @@
+use thiserror::Error;
+
+use crate::chess::moves::{CastleSide, Move, MoveKind};
+use crate::chess::piece::PieceKind;
+use crate::chess::position::Position;
+use crate::chess::square::Square;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum SanError {
+    #[error("\"{0}\" is not valid SAN")]
+    Invalid(String),
+    #[error("\"{0}\" is not a legal move in this position")]
+    Illegal(String),
+    #[error("\"{0}\" is ambiguous in this position")]
+    Ambiguous(String),
+}
+
+impl Position {
+    /// Formats a legal move in Standard Algebraic Notation, e.g. `Nbd7`, `exd6` or `e8=Q+`.
+    pub fn san(&self, mv: Move) -> String {
+        let mut san = String::with_capacity(8);
+        let piece = self
+            .piece_at(mv.from)
+            .expect("a move must start on an occupied square");
+
+        match mv.kind {
+            MoveKind::Castle(CastleSide::KingSide) => san.push_str("O-O"),
+            MoveKind::Castle(CastleSide::QueenSide) => san.push_str("O-O-O"),
+            _ => {
+                let is_capture = self.captured_piece(mv).is_some();
+                if piece.kind == PieceKind::Pawn {
+                    if is_capture {
+                        san.push(file_char(mv.from));
+                    }
+                } else {
+                    san.push(piece.kind.to_char());
+                    san.push_str(&self.disambiguation(mv, piece.kind));
+                }
+
+                if is_capture {
+                    san.push('x');
+                }
+                san.push_str(&mv.to.to_string());
+
+                if let Some(kind) = mv.promotion() {
+                    san.push('=');
+                    san.push(kind.to_char());
+                }
+            }
+        }
+
+        let mut next = self.clone();
+        next.make_move(mv);
+        if next.is_check() {
+            san.push(if next.legal_moves().is_empty() {
+                '#'
+            } else {
+                '+'
+            });
+        }
+
+        san
+    }
+
+    /// Finds the legal move described by `san`. Check and annotation suffixes are ignored.
+    pub fn parse_san(&self, san: &str) -> Result<Move, SanError> {
+        let invalid = || SanError::Invalid(san.to_string());
+        let text = san.trim_end_matches(['+', '#', '!', '?']);
+
+        let castle_side = match text {
+            "O-O" | "0-0" => Some(CastleSide::KingSide),
+            "O-O-O" | "0-0-0" => Some(CastleSide::QueenSide),
+            _ => None,
+        };
+        if let Some(side) = castle_side {
+            return self
+                .legal_moves()
+                .into_iter()
+                .find(|mv| mv.kind == MoveKind::Castle(side))
+                .ok_or_else(|| SanError::Illegal(san.to_string()));
+        }
+
+        let (text, promotion) = match text.split_once('=') {
+            Some((text, promo)) => {
+                let mut chars = promo.chars();
+                let kind = chars
+                    .next()
+                    .and_then(PieceKind::from_char)
+                    .filter(|k| PieceKind::PROMOTIONS.contains(k))
+                    .ok_or_else(invalid)?;
+                if chars.next().is_some() {
+                    return Err(invalid());
+                }
+                (text, Some(kind))
+            }
+            None => (text, None),
+        };
+
+        let (kind, text) = match text.chars().next() {
+            Some(c) if c.is_ascii_uppercase() => {
+                (PieceKind::from_char(c).ok_or_else(invalid)?, &text[1..])
+            }
+            _ => (PieceKind::Pawn, text),
+        };
+
+        if text.len() < 2 || !text.is_ascii() {
+            return Err(invalid());
+        }
+        let (qualifier, to) = text.split_at(text.len() - 2);
+        let to = Square::parse(to).ok_or_else(invalid)?;
+        let qualifier = qualifier.trim_end_matches('x');
+
+        let mut from_file = None;
+        let mut from_rank = None;
+        for c in qualifier.chars() {
+            match c {
+                'a'..='h' if from_file.is_none() => from_file = Some(c as u8 - b'a'),
+                '1'..='8' if from_rank.is_none() => from_rank = Some(c as u8 - b'1'),
+                _ => return Err(invalid()),
+            }
+        }
+
+        let mut candidates = self.legal_moves().into_iter().filter(|mv| {
+            mv.to == to
+                && mv.promotion() == promotion
+                && !matches!(mv.kind, MoveKind::Castle(_))
+                && self.piece_at(mv.from).map(|p| p.kind) == Some(kind)
+                && from_file.is_none_or(|f| mv.from.file() == f)
+                && from_rank.is_none_or(|r| mv.from.rank() == r)
+        });
+
+        match (candidates.next(), candidates.next()) {
+            (Some(mv), None) => Ok(mv),
+            (Some(_), Some(_)) => Err(SanError::Ambiguous(san.to_string())),
+            (None, _) => Err(SanError::Illegal(san.to_string())),
+        }
+    }
+
+    /// Shortest origin qualifier that tells `mv` apart from other legal moves of the same kind of
+    /// piece to the same square.
+    fn disambiguation(&self, mv: Move, kind: PieceKind) -> String {
+        let rivals: Vec<Square> = self
+            .legal_moves()
+            .into_iter()
+            .filter(|other| {
+                other.to == mv.to
+                    && other.from != mv.from
+                    && self.piece_at(other.from).map(|p| p.kind) == Some(kind)
+            })
+            .map(|other| other.from)
+            .collect();
+
+        if rivals.is_empty() {
+            String::new()
+        } else if rivals.iter().all(|sq| sq.file() != mv.from.file()) {
+            file_char(mv.from).to_string()
+        } else if rivals.iter().all(|sq| sq.rank() != mv.from.rank()) {
+            ((b'1' + mv.from.rank()) as char).to_string()
+        } else {
+            mv.from.to_string()
+        }
+    }
+}
+
+fn file_char(square: Square) -> char {
+    (b'a' + square.file()) as char
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_san_disambiguation() {
+        let position = Position::from_fen("3k4/8/8/R6R/8/8/8/R2K4 w - - 0 1").unwrap();
+        let cases = [
+            ("a5", "a3", "R5a3"),
+            ("a1", "a3", "R1a3"),
+            ("a5", "c5", "Rac5"),
+        ];
+        for (from, to, expected) in cases {
+            let mv = Move::new(
+                Square::parse(from).unwrap(),
+                Square::parse(to).unwrap(),
+                MoveKind::Normal,
+            );
+            assert_eq!(position.san(mv), expected);
+            assert_eq!(position.parse_san(expected), Ok(mv));
+        }
+
+        assert_eq!(
+            position.parse_san("Rc5"),
+            Err(SanError::Ambiguous("Rc5".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_san_special_moves() {
+        let position = Position::from_fen("r3k2r/1P6/8/3pP3/8/8/8/R3K2R w KQkq d6 0 1").unwrap();
+        for san in ["O-O", "O-O-O", "exd6", "bxa8=Q+", "b8=N"] {
+            let mv = position.parse_san(san).unwrap();
+            assert_eq!(position.san(mv), san);
+        }
+
+        let mate = Position::from_fen("k7/8/1K6/8/8/8/8/7R w - - 0 1").unwrap();
+        let mv = mate.parse_san("Rh8").unwrap();
+        assert_eq!(mate.san(mv), "Rh8#");
+    }
+}
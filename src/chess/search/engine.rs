@@ -0,0 +1,99 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+
+use crate::chess::game::Game;
+use crate::chess::search::{SearchInfo, SearchLimits, SearchResult, Searcher};
+
+/// Runs a `Searcher` on a background thread so the caller, e.g. the render loop, is never
+/// blocked while the engine thinks.
+pub struct Engine {
+    searcher: Option<Searcher>,
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<(Searcher, SearchResult)>>,
+}
+
+impl Default for Engine {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Engine {
+    pub fn new() -> Self {
+        Self::with_searcher(Searcher::new())
+    }
+
+    pub fn with_searcher(searcher: Searcher) -> Self {
+        Self {
+            stop: searcher.stop_flag(),
+            searcher: Some(searcher),
+            thread: None,
+        }
+    }
+
+    /// Starts searching the current position of `game`, stopping and discarding any search
+    /// already in progress. `on_info` is called from the search thread.
+    pub fn start(
+        &mut self,
+        game: &Game,
+        limits: SearchLimits,
+        mut on_info: impl FnMut(&SearchInfo) + Send + 'static,
+    ) {
+        self.stop();
+        self.wait();
+
+        let mut searcher = self.searcher.take().expect("searcher is idle after wait");
+        self.stop.store(false, Ordering::Relaxed);
+        let game = game.clone();
+        self.thread = Some(thread::spawn(move || {
+            let result = searcher.search(&game, limits, &mut on_info);
+            (searcher, result)
+        }));
+    }
+
+    pub fn is_thinking(&self) -> bool {
+        self.thread.is_some()
+    }
+
+    /// Asks the running search to finish early. Its result is still delivered by `poll` or
+    /// `wait`.
+    pub fn stop(&self) {
+        if self.thread.is_some() {
+            self.stop.store(true, Ordering::Relaxed);
+        }
+    }
+
+    /// Returns the result of the search once it has finished, without blocking.
+    pub fn poll(&mut self) -> Option<SearchResult> {
+        if self.thread.as_ref()?.is_finished() {
+            self.wait()
+        } else {
+            None
+        }
+    }
+
+    /// Blocks until the running search finishes and returns its result.
+    pub fn wait(&mut self) -> Option<SearchResult> {
+        let (searcher, result) = self.thread.take()?.join().expect("search thread panicked");
+        self.searcher = Some(searcher);
+
+        Some(result)
+    }
+
+    /// Clears the searcher's transposition table, waiting for any running search first.
+    pub fn new_game(&mut self) {
+        self.stop();
+        self.wait();
+        if let Some(searcher) = &mut self.searcher {
+            searcher.clear();
+        }
+    }
+}
+
+impl Drop for Engine {
+    fn drop(&mut self) {
+        self.stop();
+        self.wait();
+    }
+}
@@ -0,0 +1,142 @@
+use crate::chess::piece::{Color, PieceKind};
+use crate::chess::position::Position;
+use crate::chess::square::Square;
+
+/// Material value of each piece kind in centipawns, indexed by `PieceKind::index`.
+const PIECE_VALUES: [i32; 6] = [100, 320, 330, 500, 900, 0];
+
+/// Non-pawn material (both sides) at or below which the king table switches to the endgame one.
+const ENDGAME_MATERIAL: i32 = 1300;
+
+// Piece-square tables from White's point of view, laid out as seen from White's side of the
+// board: the first row is rank 8, the last row is rank 1.
+#[rustfmt::skip]
+const PAWN_TABLE: [i32; 64] = [
+     0,  0,  0,  0,  0,  0,  0,  0,
+    50, 50, 50, 50, 50, 50, 50, 50,
+    10, 10, 20, 30, 30, 20, 10, 10,
+     5,  5, 10, 25, 25, 10,  5,  5,
+     0,  0,  0, 20, 20,  0,  0,  0,
+     5, -5,-10,  0,  0,-10, -5,  5,
+     5, 10, 10,-20,-20, 10, 10,  5,
+     0,  0,  0,  0,  0,  0,  0,  0,
+];
+
+#[rustfmt::skip]
+const KNIGHT_TABLE: [i32; 64] = [
+    -50,-40,-30,-30,-30,-30,-40,-50,
+    -40,-20,  0,  0,  0,  0,-20,-40,
+    -30,  0, 10, 15, 15, 10,  0,-30,
+    -30,  5, 15, 20, 20, 15,  5,-30,
+    -30,  0, 15, 20, 20, 15,  0,-30,
+    -30,  5, 10, 15, 15, 10,  5,-30,
+    -40,-20,  0,  5,  5,  0,-20,-40,
+    -50,-40,-30,-30,-30,-30,-40,-50,
+];
+
+#[rustfmt::skip]
+const BISHOP_TABLE: [i32; 64] = [
+    -20,-10,-10,-10,-10,-10,-10,-20,
+    -10,  0,  0,  0,  0,  0,  0,-10,
+    -10,  0,  5, 10, 10,  5,  0,-10,
+    -10,  5,  5, 10, 10,  5,  5,-10,
+    -10,  0, 10, 10, 10, 10,  0,-10,
+    -10, 10, 10, 10, 10, 10, 10,-10,
+    -10,  5,  0,  0,  0,  0,  5,-10,
+    -20,-10,-10,-10,-10,-10,-10,-20,
+];
+
+#[rustfmt::skip]
+const ROOK_TABLE: [i32; 64] = [
+     0,  0,  0,  0,  0,  0,  0,  0,
+     5, 10, 10, 10, 10, 10, 10,  5,
+    -5,  0,  0,  0,  0,  0,  0, -5,
+    -5,  0,  0,  0,  0,  0,  0, -5,
+    -5,  0,  0,  0,  0,  0,  0, -5,
+    -5,  0,  0,  0,  0,  0,  0, -5,
+    -5,  0,  0,  0,  0,  0,  0, -5,
+     0,  0,  0,  5,  5,  0,  0,  0,
+];
+
+#[rustfmt::skip]
+const QUEEN_TABLE: [i32; 64] = [
+    -20,-10,-10, -5, -5,-10,-10,-20,
+    -10,  0,  0,  0,  0,  0,  0,-10,
+    -10,  0,  5,  5,  5,  5,  0,-10,
+     -5,  0,  5,  5,  5,  5,  0, -5,
+      0,  0,  5,  5,  5,  5,  0, -5,
+    -10,  5,  5,  5,  5,  5,  0,-10,
+    -10,  0,  5,  0,  0,  0,  0,-10,
+    -20,-10,-10, -5, -5,-10,-10,-20,
+];
+
+#[rustfmt::skip]
+const KING_MIDDLEGAME_TABLE: [i32; 64] = [
+    -30,-40,-40,-50,-50,-40,-40,-30,
+    -30,-40,-40,-50,-50,-40,-40,-30,
+    -30,-40,-40,-50,-50,-40,-40,-30,
+    -30,-40,-40,-50,-50,-40,-40,-30,
+    -20,-30,-30,-40,-40,-30,-30,-20,
+    -10,-20,-20,-20,-20,-20,-20,-10,
+     20, 20,  0,  0,  0,  0, 20, 20,
+     20, 30, 10,  0,  0, 10, 30, 20,
+];
+
+#[rustfmt::skip]
+const KING_ENDGAME_TABLE: [i32; 64] = [
+    -50,-40,-30,-20,-20,-30,-40,-50,
+    -30,-20,-10,  0,  0,-10,-20,-30,
+    -30,-10, 20, 30, 30, 20,-10,-30,
+    -30,-10, 30, 40, 40, 30,-10,-30,
+    -30,-10, 30, 40, 40, 30,-10,-30,
+    -30,-10, 20, 30, 30, 20,-10,-30,
+    -30,-30,  0,  0,  0,  0,-30,-30,
+    -50,-30,-30,-30,-30,-30,-30,-50,
+];
+
+pub(crate) fn piece_value(kind: PieceKind) -> i32 {
+    PIECE_VALUES[kind.index()]
+}
+
+/// Static evaluation in centipawns from the point of view of the side to move.
+pub fn evaluate(position: &Position) -> i32 {
+    let non_pawn_material: i32 = position
+        .pieces()
+        .filter(|(_, p)| p.kind != PieceKind::Pawn)
+        .map(|(_, p)| piece_value(p.kind))
+        .sum();
+    let endgame = non_pawn_material <= ENDGAME_MATERIAL;
+
+    let score: i32 = position
+        .pieces()
+        .map(|(square, piece)| {
+            let table = match piece.kind {
+                PieceKind::Pawn => &PAWN_TABLE,
+                PieceKind::Knight => &KNIGHT_TABLE,
+                PieceKind::Bishop => &BISHOP_TABLE,
+                PieceKind::Rook => &ROOK_TABLE,
+                PieceKind::Queen => &QUEEN_TABLE,
+                PieceKind::King if endgame => &KING_ENDGAME_TABLE,
+                PieceKind::King => &KING_MIDDLEGAME_TABLE,
+            };
+            let value = piece_value(piece.kind) + table[table_index(square, piece.color)];
+            match piece.color {
+                Color::White => value,
+                Color::Black => -value,
+            }
+        })
+        .sum();
+
+    match position.side_to_move() {
+        Color::White => score,
+        Color::Black => -score,
+    }
+}
+
+fn table_index(square: Square, color: Color) -> usize {
+    let rank = match color {
+        Color::White => 7 - square.rank(),
+        Color::Black => square.rank(),
+    };
+    rank as usize * 8 + square.file() as usize
+}
@@ -0,0 +1,512 @@
+mod engine;
+mod eval;
+mod ordering;
+mod tt;
+
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::chess::game::Game;
+use crate::chess::moves::Move;
+use crate::chess::position::Position;
+
+use self::ordering::{mvv_lva, order_moves, Killers};
+use self::tt::{Bound, TranspositionTable};
+
+pub use engine::Engine;
+pub use eval::evaluate;
+
+/// Deepest ply the search will reach, including quiescence and check extensions.
+pub const MAX_PLY: usize = 64;
+
+const INFINITY: i32 = 32_000;
+const MATE: i32 = 31_000;
+const MATE_BOUND: i32 = MATE - MAX_PLY as i32;
+const DRAW: i32 = 0;
+
+const DEFAULT_HASH_MEGABYTES: usize = 16;
+/// How many nodes are searched between checks of the clock and the stop flag.
+const CHECK_INTERVAL: u64 = 1024;
+
+/// When to stop searching. Limits combine, the search ends when any of them is reached. With no
+/// limits set the search runs until stopped.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SearchLimits {
+    pub depth: Option<u32>,
+    pub nodes: Option<u64>,
+    pub time: Option<Duration>,
+}
+
+impl SearchLimits {
+    pub fn infinite() -> Self {
+        Self::default()
+    }
+
+    pub fn depth(depth: u32) -> Self {
+        Self {
+            depth: Some(depth),
+            ..Self::default()
+        }
+    }
+
+    pub fn nodes(nodes: u64) -> Self {
+        Self {
+            nodes: Some(nodes),
+            ..Self::default()
+        }
+    }
+
+    pub fn time(time: Duration) -> Self {
+        Self {
+            time: Some(time),
+            ..Self::default()
+        }
+    }
+}
+
+/// Search score from the point of view of the side to move.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Score {
+    Centipawns(i32),
+    /// Mate in this many moves, negative when the side to move is getting mated.
+    Mate(i32),
+}
+
+impl Score {
+    fn from_internal(score: i32) -> Self {
+        if score >= MATE_BOUND {
+            Score::Mate((MATE - score + 1) / 2)
+        } else if score <= -MATE_BOUND {
+            Score::Mate(-(MATE + score) / 2)
+        } else {
+            Score::Centipawns(score)
+        }
+    }
+}
+
+impl fmt::Display for Score {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Score::Centipawns(cp) => write!(f, "cp {cp}"),
+            Score::Mate(moves) => write!(f, "mate {moves}"),
+        }
+    }
+}
+
+/// Progress report for a completed iteration of iterative deepening.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SearchInfo {
+    pub depth: u32,
+    pub score: Score,
+    pub nodes: u64,
+    pub elapsed: Duration,
+    pub pv: Vec<Move>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SearchResult {
+    /// `None` only when there are no legal moves.
+    pub best_move: Option<Move>,
+    pub info: SearchInfo,
+}
+
+/// Iterative deepening alpha-beta search. Keeps its transposition table between searches, so the
+/// same searcher should be reused over the course of a game.
+pub struct Searcher {
+    tt: TranspositionTable,
+    killers: Killers,
+    stop: Arc<AtomicBool>,
+    limits: SearchLimits,
+    started: Instant,
+    nodes: u64,
+    aborted: bool,
+    /// Hashes of the positions leading up to the node being searched, for spotting repetitions.
+    history: Vec<u64>,
+}
+
+impl Default for Searcher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Searcher {
+    pub fn new() -> Self {
+        Self::with_hash_size(DEFAULT_HASH_MEGABYTES)
+    }
+
+    /// Creates a searcher whose transposition table uses about `megabytes` of memory.
+    pub fn with_hash_size(megabytes: usize) -> Self {
+        Self {
+            tt: TranspositionTable::new(megabytes),
+            killers: Killers::new(),
+            stop: Arc::new(AtomicBool::new(false)),
+            limits: SearchLimits::default(),
+            started: Instant::now(),
+            nodes: 0,
+            aborted: false,
+            history: Vec::new(),
+        }
+    }
+
+    /// Flag that makes a running search return as soon as possible once set. The search never
+    /// clears it, that is up to whoever sets it.
+    pub fn stop_flag(&self) -> Arc<AtomicBool> {
+        self.stop.clone()
+    }
+
+    /// Forgets everything learned from previous searches, e.g. when a new game starts.
+    pub fn clear(&mut self) {
+        self.tt.clear();
+        self.killers.clear();
+    }
+
+    /// Searches the current position of `game`, calling `on_info` after every completed
+    /// iteration.
+    pub fn search(
+        &mut self,
+        game: &Game,
+        limits: SearchLimits,
+        mut on_info: impl FnMut(&SearchInfo),
+    ) -> SearchResult {
+        let position = game.position();
+        self.limits = limits;
+        self.started = Instant::now();
+        self.nodes = 0;
+        self.aborted = false;
+        self.killers.clear();
+        self.history = game.positions().map(Position::zobrist).collect();
+        self.history.pop();
+
+        let mut result = SearchResult {
+            best_move: position.legal_moves().first().copied(),
+            info: SearchInfo {
+                depth: 0,
+                score: Score::Centipawns(evaluate(position)),
+                nodes: 0,
+                elapsed: Duration::ZERO,
+                pv: Vec::new(),
+            },
+        };
+        if result.best_move.is_none() {
+            return result;
+        }
+
+        let max_depth = limits.depth.unwrap_or(MAX_PLY as u32).min(MAX_PLY as u32);
+        for depth in 1..=max_depth {
+            let mut pv = Vec::new();
+            let score = self.negamax(position, depth as i32, 0, -INFINITY, INFINITY, &mut pv);
+            if self.aborted {
+                break;
+            }
+
+            result = SearchResult {
+                best_move: pv.first().copied().or(result.best_move),
+                info: SearchInfo {
+                    depth,
+                    score: Score::from_internal(score),
+                    nodes: self.nodes,
+                    elapsed: self.started.elapsed(),
+                    pv,
+                },
+            };
+            on_info(&result.info);
+
+            // A forced mate shorter than the search depth will not change with more depth, and an
+            // iteration that used half the time budget is unlikely to finish another one.
+            let mate_found = score.abs() >= MATE_BOUND && MATE - score.abs() <= depth as i32;
+            let out_of_time = limits
+                .time
+                .is_some_and(|time| self.started.elapsed() * 2 > time);
+            if mate_found || out_of_time {
+                break;
+            }
+        }
+
+        result.info.nodes = self.nodes;
+        result.info.elapsed = self.started.elapsed();
+        result
+    }
+
+    fn negamax(
+        &mut self,
+        position: &Position,
+        depth: i32,
+        ply: usize,
+        mut alpha: i32,
+        beta: i32,
+        pv: &mut Vec<Move>,
+    ) -> i32 {
+        pv.clear();
+        if self.should_stop() {
+            return DRAW;
+        }
+        self.nodes += 1;
+
+        let key = position.zobrist();
+        if ply > 0 && self.is_draw(position, key) {
+            return DRAW;
+        }
+
+        let in_check = position.is_check();
+        let depth = if in_check { depth + 1 } else { depth };
+        if depth <= 0 || ply >= MAX_PLY - 1 {
+            return self.quiescence(position, ply, alpha, beta);
+        }
+
+        let mut tt_move = None;
+        if let Some(entry) = self.tt.probe(key, ply) {
+            tt_move = entry.best_move;
+            if ply > 0 && entry.depth >= depth {
+                let cutoff = match entry.bound {
+                    Bound::Exact => true,
+                    Bound::Lower => entry.score >= beta,
+                    Bound::Upper => entry.score <= alpha,
+                };
+                if cutoff {
+                    return entry.score;
+                }
+            }
+        }
+
+        let mut moves = position.legal_moves();
+        if moves.is_empty() {
+            return if in_check { -MATE + ply as i32 } else { DRAW };
+        }
+        order_moves(position, &mut moves, tt_move, &self.killers, ply);
+
+        let original_alpha = alpha;
+        let mut best_score = -INFINITY;
+        let mut best_move = None;
+        let mut child_pv = Vec::new();
+        self.history.push(key);
+        for mv in moves {
+            let mut next = position.clone();
+            next.make_move(mv);
+            let score = -self.negamax(&next, depth - 1, ply + 1, -beta, -alpha, &mut child_pv);
+            if self.aborted {
+                break;
+            }
+
+            if score > best_score {
+                best_score = score;
+                best_move = Some(mv);
+                if score > alpha {
+                    alpha = score;
+                    pv.clear();
+                    pv.push(mv);
+                    pv.extend_from_slice(&child_pv);
+                }
+            }
+
+            if alpha >= beta {
+                if mvv_lva(position, mv).is_none() {
+                    self.killers.store(ply, mv);
+                }
+                break;
+            }
+        }
+        self.history.pop();
+        if self.aborted {
+            return DRAW;
+        }
+
+        let bound = if best_score <= original_alpha {
+            Bound::Upper
+        } else if best_score >= beta {
+            Bound::Lower
+        } else {
+            Bound::Exact
+        };
+        self.tt.store(key, ply, depth, best_score, bound, best_move);
+
+        best_score
+    }
+
+    /// Searches captures and promotions until the position is quiet, so the static evaluation is
+    /// not taken in the middle of an exchange.
+    fn quiescence(&mut self, position: &Position, ply: usize, mut alpha: i32, beta: i32) -> i32 {
+        if self.should_stop() {
+            return DRAW;
+        }
+        self.nodes += 1;
+
+        let stand_pat = evaluate(position);
+        if stand_pat >= beta || ply >= MAX_PLY - 1 {
+            return stand_pat;
+        }
+        alpha = alpha.max(stand_pat);
+
+        let mut captures: Vec<(Move, i32)> = position
+            .legal_moves()
+            .into_iter()
+            .filter_map(|mv| mvv_lva(position, mv).map(|score| (mv, score)))
+            .collect();
+        captures.sort_by_key(|&(_, score)| -score);
+
+        let mut best_score = stand_pat;
+        for (mv, _) in captures {
+            let mut next = position.clone();
+            next.make_move(mv);
+            let score = -self.quiescence(&next, ply + 1, -beta, -alpha);
+            if self.aborted {
+                return DRAW;
+            }
+
+            if score > best_score {
+                best_score = score;
+                if score >= beta {
+                    break;
+                }
+                alpha = alpha.max(score);
+            }
+        }
+
+        best_score
+    }
+
+    /// Draws by the fifty-move rule, insufficient material or repetition. Within the search a
+    /// single repetition is scored as a draw, as whatever was played in between could be repeated.
+    fn is_draw(&self, position: &Position, key: u64) -> bool {
+        let reversible = position.halfmove_clock() as usize;
+        reversible >= 100
+            || position.is_insufficient_material()
+            || self
+                .history
+                .iter()
+                .rev()
+                .take(reversible)
+                .skip(1)
+                .step_by(2)
+                .any(|&previous| previous == key)
+    }
+
+    fn should_stop(&mut self) -> bool {
+        if self.aborted {
+            return true;
+        }
+
+        if self.limits.nodes.is_some_and(|nodes| self.nodes >= nodes) {
+            self.aborted = true;
+        } else if self.nodes.is_multiple_of(CHECK_INTERVAL) {
+            let out_of_time = self
+                .limits
+                .time
+                .is_some_and(|time| self.started.elapsed() >= time);
+            self.aborted = out_of_time || self.stop.load(Ordering::Relaxed);
+        }
+
+        self.aborted
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::chess::moves::MoveKind;
+    use crate::chess::square::Square;
+
+    fn game(fen: &str) -> Game {
+        Game::from_position(Position::from_fen(fen).unwrap())
+    }
+
+    #[test]
+    fn test_finds_mate_in_one() {
+        let game = game("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1");
+        let result = Searcher::new().search(&game, SearchLimits::depth(4), |_| {});
+        assert_eq!(
+            result.best_move,
+            Some(Move::new(Square::A1, Square::new(0, 7), MoveKind::Normal))
+        );
+        assert_eq!(result.info.score, Score::Mate(1));
+    }
+
+    #[test]
+    fn test_takes_hanging_queen() {
+        let game = game("4k3/8/8/3q4/8/8/3R4/4K3 w - - 0 1");
+        let result = Searcher::new().search(&game, SearchLimits::depth(3), |_| {});
+        assert_eq!(
+            result.best_move,
+            Some(Move::new(
+                Square::new(3, 1),
+                Square::new(3, 4),
+                MoveKind::Normal
+            ))
+        );
+    }
+
+    #[test]
+    fn test_avoids_back_rank_mate() {
+        let game = game("r5k1/5ppp/8/8/8/8/5PPP/6K1 w - - 0 1");
+        let result = Searcher::new().search(&game, SearchLimits::depth(3), |_| {});
+        assert!(matches!(result.info.score, Score::Centipawns(_)));
+
+        let mut next = game.clone();
+        next.play(result.best_move.unwrap()).unwrap();
+        let reply = Searcher::new().search(&next, SearchLimits::depth(2), |_| {});
+        assert!(matches!(reply.info.score, Score::Centipawns(_)));
+    }
+
+    #[test]
+    fn test_limits_are_respected() {
+        let game = Game::new();
+        let mut searcher = Searcher::new();
+
+        let mut depths = Vec::new();
+        let result = searcher.search(&game, SearchLimits::depth(3), |info| {
+            depths.push(info.depth)
+        });
+        assert_eq!(depths, [1, 2, 3]);
+        assert!(result.best_move.is_some());
+        assert_eq!(result.info.pv.first(), result.best_move.as_ref());
+
+        let result = searcher.search(&game, SearchLimits::nodes(5000), |_| {});
+        assert!(result.info.nodes <= 5000);
+        assert!(result.best_move.is_some());
+
+        let limit = Duration::from_millis(200);
+        let result = searcher.search(&game, SearchLimits::time(limit), |_| {});
+        assert!(result.info.elapsed < limit + Duration::from_millis(100));
+    }
+
+    #[test]
+    fn test_engine_can_be_stopped() {
+        let mut engine = Engine::new();
+        engine.start(&Game::new(), SearchLimits::infinite(), |_| {});
+        assert!(engine.is_thinking());
+
+        engine.stop();
+        let result = engine.wait().unwrap();
+        assert!(result.best_move.is_some());
+        assert!(!engine.is_thinking());
+    }
+
+    #[test]
+    fn test_repetition_is_scored_as_draw() {
+        // Black is a queen down, so going back to the initial position is the best it can do
+        let mut game = game("rnb1kbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+        for san in ["Nf3", "Nf6", "Ng1"] {
+            let mv = game.position().parse_san(san).unwrap();
+            game.play(mv).unwrap();
+        }
+
+        let result = Searcher::new().search(&game, SearchLimits::depth(3), |_| {});
+        assert_eq!(result.info.score, Score::Centipawns(0));
+        assert_eq!(
+            result.best_move,
+            Some(game.position().parse_san("Ng8").unwrap())
+        );
+    }
+
+    #[test]
+    fn test_evaluation_is_symmetric() {
+        assert_eq!(evaluate(&Position::starting()), 0);
+
+        let white = Position::from_fen("4k3/8/8/8/8/8/8/3QK3 w - - 0 1").unwrap();
+        let black = Position::from_fen("3qk3/8/8/8/8/8/8/4K3 b - - 0 1").unwrap();
+        assert_eq!(evaluate(&white), evaluate(&black));
+        assert!(evaluate(&white) > 800);
+    }
+}
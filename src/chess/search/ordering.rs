@@ -0,0 +1,75 @@
+use std::cmp::Reverse;
+
+use crate::chess::moves::Move;
+use crate::chess::piece::PieceKind;
+use crate::chess::position::Position;
+use crate::chess::search::eval::piece_value;
+use crate::chess::search::MAX_PLY;
+
+const TT_MOVE_SCORE: i32 = 1_000_000;
+const CAPTURE_SCORE: i32 = 100_000;
+const KILLER_SCORES: [i32; 2] = [90_000, 80_000];
+
+/// Two quiet moves per ply that recently caused a beta cutoff.
+pub(super) struct Killers([[Option<Move>; 2]; MAX_PLY]);
+
+impl Killers {
+    pub fn new() -> Self {
+        Self([[None; 2]; MAX_PLY])
+    }
+
+    pub fn clear(&mut self) {
+        *self = Self::new();
+    }
+
+    pub fn store(&mut self, ply: usize, mv: Move) {
+        let slot = &mut self.0[ply];
+        if slot[0] != Some(mv) {
+            slot[1] = slot[0];
+            slot[0] = Some(mv);
+        }
+    }
+
+    fn score(&self, ply: usize, mv: Move) -> Option<i32> {
+        self.0[ply]
+            .iter()
+            .position(|&killer| killer == Some(mv))
+            .map(|slot| KILLER_SCORES[slot])
+    }
+}
+
+/// Most valuable victim, least valuable attacker. Promotions count as capturing the new piece.
+pub(super) fn mvv_lva(position: &Position, mv: Move) -> Option<i32> {
+    let victim = position.captured_piece(mv).map(|p| piece_value(p.kind));
+    let promotion = mv.promotion().map(piece_value);
+    if victim.is_none() && promotion.is_none() {
+        return None;
+    }
+
+    let attacker = position
+        .piece_at(mv.from)
+        .map(|p| p.kind)
+        .unwrap_or(PieceKind::Pawn);
+    Some(10 * (victim.unwrap_or(0) + promotion.unwrap_or(0)) - piece_value(attacker))
+}
+
+/// Sorts `moves` so the most promising are searched first: the transposition table move, then
+/// captures and promotions by MVV-LVA, then killers, then the remaining quiet moves.
+pub(super) fn order_moves(
+    position: &Position,
+    moves: &mut [Move],
+    tt_move: Option<Move>,
+    killers: &Killers,
+    ply: usize,
+) {
+    moves.sort_by_cached_key(|&mv| {
+        let score = if Some(mv) == tt_move {
+            TT_MOVE_SCORE
+        } else if let Some(score) = mvv_lva(position, mv) {
+            CAPTURE_SCORE + score
+        } else {
+            killers.score(ply, mv).unwrap_or(0)
+        };
+        Reverse(score)
+    });
+}
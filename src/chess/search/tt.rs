@@ -0,0 +1,104 @@
+use std::mem;
+
+use crate::chess::moves::Move;
+use crate::chess::search::MATE_BOUND;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(super) enum Bound {
+    Exact,
+    Lower,
+    Upper,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub(super) struct Entry {
+    key: u64,
+    pub depth: i32,
+    pub score: i32,
+    pub bound: Bound,
+    pub best_move: Option<Move>,
+}
+
+/// Fixed size hash table of search results keyed by position hash.
+pub(super) struct TranspositionTable {
+    entries: Vec<Option<Entry>>,
+}
+
+impl TranspositionTable {
+    /// Creates a table using at most `megabytes` of memory, rounded down to a power of two
+    /// number of entries.
+    pub fn new(megabytes: usize) -> Self {
+        let capacity = (megabytes.max(1) << 20) / mem::size_of::<Option<Entry>>();
+        let len = 1 << capacity.ilog2();
+        Self {
+            entries: vec![None; len],
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.fill(None);
+    }
+
+    /// Looks up `key`, returning the entry with its score adjusted to be relative to `ply`.
+    pub fn probe(&self, key: u64, ply: usize) -> Option<Entry> {
+        self.entries[self.index(key)]
+            .filter(|entry| entry.key == key)
+            .map(|entry| Entry {
+                score: score_from_tt(entry.score, ply),
+                ..entry
+            })
+    }
+
+    /// Stores a result, replacing the current occupant unless it is a deeper search of the
+    /// same position.
+    pub fn store(
+        &mut self,
+        key: u64,
+        ply: usize,
+        depth: i32,
+        score: i32,
+        bound: Bound,
+        best_move: Option<Move>,
+    ) {
+        let index = self.index(key);
+        if let Some(existing) = &self.entries[index] {
+            if existing.key == key && existing.depth > depth {
+                return;
+            }
+        }
+
+        self.entries[index] = Some(Entry {
+            key,
+            depth,
+            score: score_to_tt(score, ply),
+            bound,
+            best_move,
+        });
+    }
+
+    fn index(&self, key: u64) -> usize {
+        key as usize & (self.entries.len() - 1)
+    }
+}
+
+// Mate scores are stored as distance from the stored node rather than from the root, so they stay
+// correct when the position is reached at a different ply.
+fn score_to_tt(score: i32, ply: usize) -> i32 {
+    if score >= MATE_BOUND {
+        score + ply as i32
+    } else if score <= -MATE_BOUND {
+        score - ply as i32
+    } else {
+        score
+    }
+}
+
+fn score_from_tt(score: i32, ply: usize) -> i32 {
+    if score >= MATE_BOUND {
+        score - ply as i32
+    } else if score <= -MATE_BOUND {
+        score + ply as i32
+    } else {
+        score
+    }
+}
@@ -0,0 +1,77 @@
+use std::fmt;
+
+/// Square on the board, indexed from a1 = 0 to h8 = 63.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Square(u8);
+
+impl Square {
+    pub const A1: Square = Square::new(0, 0);
+    pub const C1: Square = Square::new(2, 0);
+    pub const D1: Square = Square::new(3, 0);
+    pub const E1: Square = Square::new(4, 0);
+    pub const F1: Square = Square::new(5, 0);
+    pub const G1: Square = Square::new(6, 0);
+    pub const H1: Square = Square::new(7, 0);
+    pub const A8: Square = Square::new(0, 7);
+    pub const C8: Square = Square::new(2, 7);
+    pub const D8: Square = Square::new(3, 7);
+    pub const E8: Square = Square::new(4, 7);
+    pub const F8: Square = Square::new(5, 7);
+    pub const G8: Square = Square::new(6, 7);
+    pub const H8: Square = Square::new(7, 7);
+
+    pub const fn new(file: u8, rank: u8) -> Self {
+        assert!(file < 8 && rank < 8);
+        Self(rank * 8 + file)
+    }
+
+    /// Parses a square name such as `e4`.
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.as_bytes() {
+            &[file @ b'a'..=b'h', rank @ b'1'..=b'8'] => {
+                Some(Square::new(file - b'a', rank - b'1'))
+            }
+            _ => None,
+        }
+    }
+
+    pub fn from_index(index: usize) -> Self {
+        assert!(index < 64);
+        Self(index as u8)
+    }
+
+    pub fn index(self) -> usize {
+        self.0 as usize
+    }
+
+    pub fn file(self) -> u8 {
+        self.0 % 8
+    }
+
+    pub fn rank(self) -> u8 {
+        self.0 / 8
+    }
+
+    /// Returns the square `file_delta` files and `rank_delta` ranks away, if it is on the board.
+    pub fn offset(self, file_delta: i8, rank_delta: i8) -> Option<Square> {
+        let file = self.file() as i8 + file_delta;
+        let rank = self.rank() as i8 + rank_delta;
+        if (0..8).contains(&file) && (0..8).contains(&rank) {
+            Some(Square::new(file as u8, rank as u8))
+        } else {
+            None
+        }
+    }
+
+    pub fn all() -> impl Iterator<Item = Square> {
+        (0..64).map(Square)
+    }
+}
+
+impl fmt::Display for Square {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let file = (b'a' + self.file()) as char;
+        let rank = (b'1' + self.rank()) as char;
+        write!(f, "{file}{rank}")
+    }
+}
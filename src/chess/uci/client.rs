@@ -0,0 +1,355 @@
+use std::collections::VecDeque;
+use std::ffi::OsStr;
+use std::io::{self, BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, TryRecvError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use thiserror::Error;
+
+use crate::chess::game::Game;
+use crate::chess::moves::Move;
+use crate::chess::position::Position;
+use crate::chess::search::SearchLimits;
+use crate::chess::uci::protocol::{parse_move, position_command, GoParams, UciInfo};
+
+/// How long the engine gets to answer `uci` and `isready`.
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+/// How long the engine gets to exit after `quit` before it is killed.
+const QUIT_TIMEOUT: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Error)]
+pub enum UciError {
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    #[error("engine did not answer \"{0}\" in time")]
+    Timeout(&'static str),
+    #[error("engine exited")]
+    Disconnected,
+    #[error("engine sent illegal move \"{0}\"")]
+    IllegalMove(String),
+}
+
+/// An option the engine advertised during the handshake.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UciOption {
+    pub name: String,
+    /// `check`, `spin`, `combo`, `button` or `string`.
+    pub kind: String,
+    pub default: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum UciEvent {
+    Info(UciInfo),
+    /// `mv` is `None` when the engine had no legal move to play.
+    BestMove {
+        mv: Option<Move>,
+        ponder: Option<Move>,
+    },
+}
+
+/// An external UCI engine running as a child process.
+pub struct UciEngine {
+    child: Child,
+    stdin: ChildStdin,
+    lines: Receiver<String>,
+    /// Lines received while waiting for another answer, read before new ones.
+    pending: VecDeque<String>,
+    name: Option<String>,
+    author: Option<String>,
+    options: Vec<UciOption>,
+    position: Position,
+    chess960: bool,
+}
+
+impl UciEngine {
+    /// Starts the engine and performs the `uci` handshake.
+    pub fn spawn<I, S>(program: impl AsRef<OsStr>, args: I) -> Result<Self, UciError>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<OsStr>,
+    {
+        let mut child = Command::new(program)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()?;
+        let stdin = child.stdin.take().expect("stdin is piped");
+        let stdout = child.stdout.take().expect("stdout is piped");
+
+        // Reading on a separate thread lets `poll` check for output without blocking
+        let (sender, lines) = mpsc::channel();
+        thread::spawn(move || {
+            for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+                if sender.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+
+        let mut engine = Self {
+            child,
+            stdin,
+            lines,
+            pending: VecDeque::new(),
+            name: None,
+            author: None,
+            options: Vec::new(),
+            position: Position::starting(),
+            chess960: false,
+        };
+        engine.handshake()?;
+
+        Ok(engine)
+    }
+
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    pub fn author(&self) -> Option<&str> {
+        self.author.as_deref()
+    }
+
+    pub fn options(&self) -> &[UciOption] {
+        &self.options
+    }
+
+    pub fn set_option(&mut self, name: &str, value: &str) -> Result<(), UciError> {
+        self.send(&format!("setoption name {name} value {value}"))
+    }
+
+    /// Switches the engine in and out of Chess960 mode, which changes how castling moves are
+    /// written.
+    pub fn set_chess960(&mut self, enabled: bool) -> Result<(), UciError> {
+        self.set_option("UCI_Chess960", if enabled { "true" } else { "false" })?;
+        self.chess960 = enabled;
+        Ok(())
+    }
+
+    /// Sends `isready` and waits for `readyok`. Output of a running search is kept for
+    /// [`Self::poll`] and [`Self::best_move`].
+    pub fn is_ready(&mut self) -> Result<(), UciError> {
+        self.send("isready")?;
+        self.wait_for("isready", |line| line == "readyok")?;
+        Ok(())
+    }
+
+    pub fn new_game(&mut self) -> Result<(), UciError> {
+        self.send("ucinewgame")?;
+        self.is_ready()
+    }
+
+    /// Sets up the current position of `game`, which is also used to interpret the moves the
+    /// engine sends back.
+    pub fn set_position(&mut self, game: &Game) -> Result<(), UciError> {
+        self.position = game.position().clone();
+        self.send(&position_command(game, self.chess960))
+    }
+
+    /// Starts searching the position last set. The search runs until `limits` are reached, or
+    /// until `stop` when there are none.
+    pub fn go(&mut self, limits: SearchLimits) -> Result<(), UciError> {
+        self.go_with(&GoParams::from(limits))
+    }
+
+    /// Starts searching with the full set of `go` arguments, e.g. to play on a clock.
+    pub fn go_with(&mut self, params: &GoParams) -> Result<(), UciError> {
+        self.send(&params.to_uci())
+    }
+
+    pub fn stop(&mut self) -> Result<(), UciError> {
+        self.send("stop")
+    }
+
+    /// Returns the next event if the engine has sent one, without blocking.
+    pub fn poll(&mut self) -> Result<Option<UciEvent>, UciError> {
+        while let Some(line) = self.pending.pop_front() {
+            if let Some(event) = self.parse_event(&line)? {
+                return Ok(Some(event));
+            }
+        }
+
+        loop {
+            match self.lines.try_recv() {
+                Ok(line) => {
+                    if let Some(event) = self.parse_event(&line)? {
+                        return Ok(Some(event));
+                    }
+                }
+                Err(TryRecvError::Empty) => return Ok(None),
+                Err(TryRecvError::Disconnected) => return Err(UciError::Disconnected),
+            }
+        }
+    }
+
+    /// Blocks until the engine sends its best move, passing any info lines before it to
+    /// `on_info`.
+    pub fn best_move(
+        &mut self,
+        mut on_info: impl FnMut(&UciInfo),
+    ) -> Result<Option<Move>, UciError> {
+        loop {
+            let line = match self.pending.pop_front() {
+                Some(line) => line,
+                None => self.lines.recv().map_err(|_| UciError::Disconnected)?,
+            };
+            match self.parse_event(&line)? {
+                Some(UciEvent::Info(info)) => on_info(&info),
+                Some(UciEvent::BestMove { mv, .. }) => return Ok(mv),
+                None => {}
+            }
+        }
+    }
+
+    fn handshake(&mut self) -> Result<(), UciError> {
+        self.send("uci")?;
+        let deadline = Instant::now() + HANDSHAKE_TIMEOUT;
+        loop {
+            let line = self.recv_until("uci", deadline)?;
+            let (command, args) = split_command(&line);
+            match command {
+                "uciok" => break,
+                "id" => match split_command(args) {
+                    ("name", name) => self.name = Some(name.to_string()),
+                    ("author", author) => self.author = Some(author.to_string()),
+                    _ => {}
+                },
+                "option" => self.options.extend(parse_option(args)),
+                _ => {}
+            }
+        }
+
+        self.is_ready()
+    }
+
+    fn wait_for(
+        &mut self,
+        command: &'static str,
+        matches: impl Fn(&str) -> bool,
+    ) -> Result<String, UciError> {
+        let deadline = Instant::now() + HANDSHAKE_TIMEOUT;
+        loop {
+            let line = self.recv_until(command, deadline)?;
+            if matches(line.trim()) {
+                return Ok(line);
+            }
+            self.pending.push_back(line);
+        }
+    }
+
+    fn recv_until(&mut self, command: &'static str, deadline: Instant) -> Result<String, UciError> {
+        let timeout = deadline.saturating_duration_since(Instant::now());
+        self.lines.recv_timeout(timeout).map_err(|e| match e {
+            RecvTimeoutError::Timeout => UciError::Timeout(command),
+            RecvTimeoutError::Disconnected => UciError::Disconnected,
+        })
+    }
+
+    fn parse_event(&self, line: &str) -> Result<Option<UciEvent>, UciError> {
+        let (command, args) = split_command(line);
+        match command {
+            "info" => Ok(Some(UciEvent::Info(UciInfo::parse(args, &self.position)))),
+            "bestmove" => {
+                let mut tokens = args.split_whitespace();
+                let mv = match tokens.next() {
+                    None | Some("(none)") | Some("0000") => None,
+                    Some(text) => Some(
+                        parse_move(&self.position, text)
+                            .ok_or_else(|| UciError::IllegalMove(text.to_string()))?,
+                    ),
+                };
+                let ponder = match (mv, tokens.next(), tokens.next()) {
+                    (Some(mv), Some("ponder"), Some(text)) => {
+                        let mut next = self.position.clone();
+                        next.make_move(mv);
+                        parse_move(&next, text)
+                    }
+                    _ => None,
+                };
+                Ok(Some(UciEvent::BestMove { mv, ponder }))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    fn send(&mut self, command: &str) -> Result<(), UciError> {
+        writeln!(self.stdin, "{command}")?;
+        self.stdin.flush()?;
+        Ok(())
+    }
+}
+
+impl Drop for UciEngine {
+    fn drop(&mut self) {
+        let _ = self.send("quit");
+        let deadline = Instant::now() + QUIT_TIMEOUT;
+        while Instant::now() < deadline {
+            match self.child.try_wait() {
+                Ok(Some(_)) | Err(_) => return,
+                Ok(None) => thread::sleep(Duration::from_millis(10)),
+            }
+        }
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+/// Splits a line into its first word and the trimmed rest.
+fn split_command(line: &str) -> (&str, &str) {
+    let line = line.trim();
+    match line.split_once(char::is_whitespace) {
+        Some((command, args)) => (command, args.trim()),
+        None => (line, ""),
+    }
+}
+
+/// Parses `name <name> type <type> [default <value>] ...`. Names may contain spaces.
+fn parse_option(args: &str) -> Option<UciOption> {
+    let args = args.strip_prefix("name ")?;
+    let (name, rest) = args.split_once(" type ")?;
+    let (kind, rest) = split_command(rest);
+    let default = rest.strip_prefix("default").map(|rest| {
+        let value = rest.trim_start();
+        let end = [" min ", " max ", " var "]
+            .iter()
+            .filter_map(|keyword| value.find(keyword))
+            .min()
+            .unwrap_or(value.len());
+        value[..end].trim().to_string()
+    });
+
+    Some(UciOption {
+        name: name.trim().to_string(),
+        kind: kind.to_string(),
+        default,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_option() {
+        assert_eq!(
+            parse_option("name Hash type spin default 16 min 1 max 1024"),
+            Some(UciOption {
+                name: "Hash".to_string(),
+                kind: "spin".to_string(),
+                default: Some("16".to_string()),
+            })
+        );
+        assert_eq!(
+            parse_option("name Clear Hash type button"),
+            Some(UciOption {
+                name: "Clear Hash".to_string(),
+                kind: "button".to_string(),
+                default: None,
+            })
+        );
+    }
+}
@@ -0,0 +1,5 @@
+mod client;
+mod protocol;
+
+pub use client::{UciEngine, UciError, UciEvent, UciOption};
+pub use protocol::{format_move, parse_move, parse_position, position_command, GoParams, UciInfo};
@@ -0,0 +1,450 @@
+use std::time::Duration;
+
+use crate::chess::fen::STARTING_FEN;
+use crate::chess::game::Game;
+use crate::chess::moves::{Move, MoveKind};
+use crate::chess::piece::Color;
+use crate::chess::position::Position;
+use crate::chess::search::{Score, SearchInfo, SearchLimits};
+use crate::chess::square::Square;
+
+/// Moves left until the next time control assumed when the GUI does not say.
+const DEFAULT_MOVES_TO_GO: u32 = 30;
+/// Time kept in reserve for communication delays when playing on a clock.
+const MOVE_OVERHEAD: Duration = Duration::from_millis(50);
+
+/// Fields of a UCI `info` line. Engines send whichever subset they like, so all are optional.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct UciInfo {
+    pub depth: Option<u32>,
+    pub seldepth: Option<u32>,
+    pub multipv: Option<u32>,
+    pub score: Option<Score>,
+    pub nodes: Option<u64>,
+    pub nps: Option<u64>,
+    pub time: Option<Duration>,
+    pub pv: Vec<Move>,
+    pub string: Option<String>,
+}
+
+impl UciInfo {
+    /// Parses the arguments of an `info` line, with moves in the PV interpreted from `position`.
+    /// Unknown fields are skipped, and the PV is cut short at the first move that is not legal.
+    pub fn parse(args: &str, position: &Position) -> Self {
+        let mut info = UciInfo::default();
+        let mut tokens = args.split_whitespace();
+        while let Some(token) = tokens.next() {
+            match token {
+                "depth" => info.depth = tokens.next().and_then(|t| t.parse().ok()),
+                "seldepth" => info.seldepth = tokens.next().and_then(|t| t.parse().ok()),
+                "multipv" => info.multipv = tokens.next().and_then(|t| t.parse().ok()),
+                "nodes" => info.nodes = tokens.next().and_then(|t| t.parse().ok()),
+                "nps" => info.nps = tokens.next().and_then(|t| t.parse().ok()),
+                "time" => {
+                    info.time = tokens
+                        .next()
+                        .and_then(|t| t.parse().ok())
+                        .map(Duration::from_millis)
+                }
+                "score" => {
+                    let kind = tokens.next();
+                    let value = tokens.next().and_then(|t| t.parse().ok());
+                    info.score = match (kind, value) {
+                        (Some("cp"), Some(cp)) => Some(Score::Centipawns(cp)),
+                        (Some("mate"), Some(moves)) => Some(Score::Mate(moves)),
+                        _ => None,
+                    };
+                }
+                "pv" => {
+                    let mut position = position.clone();
+                    for text in tokens.by_ref() {
+                        match parse_move(&position, text) {
+                            Some(mv) => {
+                                position.make_move(mv);
+                                info.pv.push(mv);
+                            }
+                            None => break,
+                        }
+                    }
+                }
+                "string" => {
+                    info.string = Some(tokens.by_ref().collect::<Vec<_>>().join(" "));
+                }
+                "currmove" | "currmovenumber" | "hashfull" | "tbhits" | "sbhits" | "cpuload" => {
+                    tokens.next();
+                }
+                _ => {}
+            }
+        }
+
+        info
+    }
+
+    /// Formats the info line sent by a UCI engine, starting with `info`. The PV is written from
+    /// `position`, see `format_move` for `chess960`.
+    pub fn to_uci(&self, position: &Position, chess960: bool) -> String {
+        let mut line = String::from("info");
+        let mut field = |name: &str, value: Option<String>| {
+            if let Some(value) = value {
+                line.push_str(&format!(" {name} {value}"));
+            }
+        };
+        field("depth", self.depth.map(|d| d.to_string()));
+        field("seldepth", self.seldepth.map(|d| d.to_string()));
+        field("multipv", self.multipv.map(|n| n.to_string()));
+        field("score", self.score.map(|s| s.to_string()));
+        field("nodes", self.nodes.map(|n| n.to_string()));
+        field("nps", self.nps.map(|n| n.to_string()));
+        field("time", self.time.map(|t| t.as_millis().to_string()));
+        if !self.pv.is_empty() {
+            let mut position = position.clone();
+            let pv: Vec<String> = self
+                .pv
+                .iter()
+                .map(|&mv| {
+                    let text = format_move(&position, mv, chess960);
+                    position.make_move(mv);
+                    text
+                })
+                .collect();
+            field("pv", Some(pv.join(" ")));
+        }
+        field("string", self.string.clone());
+
+        line
+    }
+}
+
+impl From<&SearchInfo> for UciInfo {
+    fn from(info: &SearchInfo) -> Self {
+        let millis = info.elapsed.as_millis() as u64;
+        Self {
+            depth: Some(info.depth),
+            score: Some(info.score),
+            nodes: Some(info.nodes),
+            nps: Some(info.nodes * 1000 / millis.max(1)),
+            time: Some(info.elapsed),
+            pv: info.pv.clone(),
+            ..Self::default()
+        }
+    }
+}
+
+/// Arguments of a `go` command.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct GoParams {
+    pub depth: Option<u32>,
+    pub nodes: Option<u64>,
+    pub movetime: Option<Duration>,
+    pub wtime: Option<Duration>,
+    pub btime: Option<Duration>,
+    pub winc: Option<Duration>,
+    pub binc: Option<Duration>,
+    pub movestogo: Option<u32>,
+    pub infinite: bool,
+}
+
+impl GoParams {
+    pub fn parse(args: &str) -> Self {
+        let mut params = GoParams::default();
+        let mut tokens = args.split_whitespace();
+        while let Some(token) = tokens.next() {
+            let mut millis = || {
+                tokens
+                    .next()
+                    .and_then(|t| t.parse::<i64>().ok())
+                    .map(|ms| Duration::from_millis(ms.max(0) as u64))
+            };
+            match token {
+                "movetime" => params.movetime = millis(),
+                "wtime" => params.wtime = millis(),
+                "btime" => params.btime = millis(),
+                "winc" => params.winc = millis(),
+                "binc" => params.binc = millis(),
+                "depth" => params.depth = tokens.next().and_then(|t| t.parse().ok()),
+                "nodes" => params.nodes = tokens.next().and_then(|t| t.parse().ok()),
+                "movestogo" => params.movestogo = tokens.next().and_then(|t| t.parse().ok()),
+                "infinite" => params.infinite = true,
+                _ => {}
+            }
+        }
+
+        params
+    }
+
+    /// Formats the `go` command.
+    pub fn to_uci(&self) -> String {
+        let mut command = String::from("go");
+        let mut field = |name: &str, value: Option<String>| {
+            if let Some(value) = value {
+                command.push_str(&format!(" {name} {value}"));
+            }
+        };
+        let millis = |time: Option<Duration>| time.map(|t| t.as_millis().to_string());
+        field("wtime", millis(self.wtime));
+        field("btime", millis(self.btime));
+        field("winc", millis(self.winc));
+        field("binc", millis(self.binc));
+        field("movestogo", self.movestogo.map(|n| n.to_string()));
+        field("depth", self.depth.map(|d| d.to_string()));
+        field("nodes", self.nodes.map(|n| n.to_string()));
+        field("movetime", millis(self.movetime));
+        if self.infinite {
+            command.push_str(" infinite");
+        }
+
+        command
+    }
+
+    /// Search limits for `side` to move. On a clock the engine spends an even share of its
+    /// remaining time until the next time control, plus most of the increment.
+    pub fn limits(&self, side: Color) -> SearchLimits {
+        let (remaining, increment) = match side {
+            Color::White => (self.wtime, self.winc),
+            Color::Black => (self.btime, self.binc),
+        };
+        let clock_time = remaining.map(|remaining| {
+            let moves_to_go = self.movestogo.unwrap_or(DEFAULT_MOVES_TO_GO).max(1);
+            let budget = remaining / moves_to_go + increment.unwrap_or_default() * 3 / 4;
+            budget.min(remaining.saturating_sub(MOVE_OVERHEAD))
+        });
+
+        if self.infinite {
+            return SearchLimits::infinite();
+        }
+        SearchLimits {
+            depth: self.depth,
+            nodes: self.nodes,
+            time: match (self.movetime, clock_time) {
+                (Some(a), Some(b)) => Some(a.min(b)),
+                (a, b) => a.or(b),
+            },
+        }
+    }
+}
+
+impl From<SearchLimits> for GoParams {
+    fn from(limits: SearchLimits) -> Self {
+        Self {
+            depth: limits.depth,
+            nodes: limits.nodes,
+            movetime: limits.time,
+            infinite: limits == SearchLimits::infinite(),
+            ..Self::default()
+        }
+    }
+}
+
+/// Sets up the game described by the arguments of a `position` command, e.g.
+/// `startpos moves e2e4` or `fen <fen> moves e7e5`. Returns `None` if the FEN or any of the
+/// moves is invalid.
+pub fn parse_position(args: &str) -> Option<Game> {
+    let tokens: Vec<&str> = args.split_whitespace().collect();
+    let moves_at = tokens
+        .iter()
+        .position(|&t| t == "moves")
+        .unwrap_or(tokens.len());
+    let fen = match tokens.first() {
+        Some(&"startpos") => STARTING_FEN.to_string(),
+        Some(&"fen") => tokens[1..moves_at].join(" "),
+        _ => return None,
+    };
+
+    let mut game = Game::from_position(Position::from_fen(&fen).ok()?);
+    for text in tokens.iter().skip(moves_at + 1) {
+        let mv = parse_move(game.position(), text)?;
+        game.play(mv).ok()?;
+    }
+
+    Some(game)
+}
+
+/// Finds the legal move written in UCI's long algebraic notation, e.g. `e2e4` or `e7e8q`.
+/// Castling is accepted both as the king's move and, as in Chess960 mode, as the king taking its
+/// own rook. In Chess960 a king move to the same square as castling is taken to be the former.
+pub fn parse_move(position: &Position, text: &str) -> Option<Move> {
+    let moves = position.legal_moves();
+    let is_castle = |mv: &&Move| matches!(mv.kind, MoveKind::Castle(_));
+    moves
+        .iter()
+        .filter(|mv| !is_castle(mv))
+        .find(|mv| mv.to_string() == text)
+        .or_else(|| {
+            moves
+                .iter()
+                .filter(is_castle)
+                .find(|mv| mv.to_string() == text || format_move(position, **mv, true) == text)
+        })
+        .copied()
+}
+
+/// Writes `mv` in UCI's long algebraic notation. With `chess960` castling is written as the king
+/// taking its own rook, as the UCI_Chess960 option asks for.
+pub fn format_move(position: &Position, mv: Move, chess960: bool) -> String {
+    match mv.kind {
+        MoveKind::Castle(side) if chess960 => {
+            let color = position.side_to_move();
+            let rook_file = position
+                .castling_rights()
+                .rook_file(color, side)
+                .expect("castling requires the right to castle");
+            format!("{}{}", mv.from, Square::new(rook_file, color.back_rank()))
+        }
+        _ => mv.to_string(),
+    }
+}
+
+/// The `position` command that sets up the current position of `game` including its moves, so
+/// the engine can detect repetitions. See `format_move` for `chess960`.
+pub fn position_command(game: &Game, chess960: bool) -> String {
+    let mut command = if *game.initial_position() == Position::starting() {
+        String::from("position startpos")
+    } else {
+        format!("position fen {}", game.initial_position().to_fen())
+    };
+
+    let mut moves = game.moves().zip(game.positions()).peekable();
+    if moves.peek().is_some() {
+        command.push_str(" moves");
+        for (mv, position) in moves {
+            command.push(' ');
+            command.push_str(&format_move(position, mv, chess960));
+        }
+    }
+
+    command
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::chess::moves::CastleSide;
+
+    #[test]
+    fn test_info_round_trips() {
+        let line = "depth 3 seldepth 5 score cp -25 nodes 1234 nps 5000 time 250 pv e2e4 e7e5 g1f3";
+        let info = UciInfo::parse(line, &Position::starting());
+        assert_eq!(info.depth, Some(3));
+        assert_eq!(info.score, Some(Score::Centipawns(-25)));
+        assert_eq!(info.time, Some(Duration::from_millis(250)));
+        assert_eq!(info.pv.len(), 3);
+        assert_eq!(
+            info.to_uci(&Position::starting(), false),
+            format!("info {line}")
+        );
+
+        let info = UciInfo::parse(
+            "currmove e2e4 currmovenumber 1 score mate -2 pv e2e4 e2e4",
+            &Position::starting(),
+        );
+        assert_eq!(info.score, Some(Score::Mate(-2)));
+        assert_eq!(info.pv.len(), 1);
+    }
+
+    #[test]
+    fn test_go_limits() {
+        let params = GoParams::parse("wtime 60000 btime 30000 winc 1000 binc 1000");
+        assert_eq!(
+            params.limits(Color::White).time,
+            Some(Duration::from_millis(2750))
+        );
+        assert_eq!(
+            params.limits(Color::Black).time,
+            Some(Duration::from_millis(1750))
+        );
+
+        let params = GoParams::parse("wtime 100 btime 100 movestogo 1");
+        assert_eq!(
+            params.limits(Color::White).time,
+            Some(Duration::from_millis(50))
+        );
+
+        assert_eq!(
+            GoParams::parse("depth 6 movetime 500").limits(Color::White),
+            SearchLimits {
+                depth: Some(6),
+                nodes: None,
+                time: Some(Duration::from_millis(500)),
+            }
+        );
+        assert_eq!(
+            GoParams::parse("infinite").limits(Color::White),
+            SearchLimits::infinite()
+        );
+
+        let command = "go wtime 1000 btime 2000 winc 10 binc 20 movestogo 5 depth 4";
+        assert_eq!(GoParams::parse(&command[3..]).to_uci(), command);
+        assert_eq!(
+            GoParams::from(SearchLimits::infinite()).to_uci(),
+            "go infinite"
+        );
+    }
+
+    #[test]
+    fn test_position_command() {
+        let mut game = Game::new();
+        assert_eq!(position_command(&game, false), "position startpos");
+
+        for text in ["e2e4", "e7e5"] {
+            let mv = parse_move(game.position(), text).unwrap();
+            game.play(mv).unwrap();
+        }
+        assert_eq!(
+            position_command(&game, false),
+            "position startpos moves e2e4 e7e5"
+        );
+
+        let game =
+            Game::from_position(Position::from_fen("4k3/8/8/8/8/8/8/4K3 w - - 0 1").unwrap());
+        assert_eq!(
+            position_command(&game, false),
+            "position fen 4k3/8/8/8/8/8/8/4K3 w - - 0 1"
+        );
+    }
+
+    #[test]
+    fn test_parse_position() {
+        let game = parse_position("startpos moves e2e4 e7e5 g1f3").unwrap();
+        assert_eq!(game.moves().count(), 3);
+        let command = position_command(&game, false);
+        let parsed = parse_position(command.strip_prefix("position ").unwrap()).unwrap();
+        assert_eq!(parsed.position(), game.position());
+
+        let game = parse_position("fen 4k3/8/8/8/8/8/8/R3K2R w KQ - 0 1 moves e1h1").unwrap();
+        assert_eq!(
+            game.position()
+                .piece_at(Square::new(5, 0))
+                .unwrap()
+                .to_char(),
+            'R'
+        );
+        assert_eq!(
+            position_command(&game, true),
+            "position fen 4k3/8/8/8/8/8/8/R3K2R w KQ - 0 1 moves e1h1"
+        );
+        assert_eq!(
+            position_command(&game, false),
+            "position fen 4k3/8/8/8/8/8/8/R3K2R w KQ - 0 1 moves e1g1"
+        );
+
+        assert!(parse_position("fen 4k3/8/8/8/8/8/8/4K3 w - - 0 1 moves e1e3").is_none());
+        assert!(parse_position("moves e2e4").is_none());
+    }
+
+    #[test]
+    fn test_chess960_castling_notation() {
+        // Castling king side takes the king from f1 to g1, just like Kg1
+        let position = Position::from_fen("4k3/8/8/8/8/8/8/1R3K1R w HB - 0 1").unwrap();
+        let king_move = parse_move(&position, "f1g1").unwrap();
+        assert_eq!(king_move.kind, MoveKind::Normal);
+
+        let castle = parse_move(&position, "f1h1").unwrap();
+        assert_eq!(castle.kind, MoveKind::Castle(CastleSide::KingSide));
+        assert_eq!(format_move(&position, castle, true), "f1h1");
+        assert_eq!(format_move(&position, castle, false), "f1g1");
+
+        let long_castle = parse_move(&position, "f1b1").unwrap();
+        assert_eq!(long_castle.kind, MoveKind::Castle(CastleSide::QueenSide));
+        assert_eq!(parse_move(&position, "f1c1"), Some(long_castle));
+    }
+}
@@ -0,0 +1,126 @@
+//! Zobrist keys for hashing positions.
+//!
+//! The keys are laid out and combined the way the Polyglot opening book format does it, so a
+//! position hash is the XOR of:
+//!
+//! - `KEYS[64 * kind + 8 * rank + file]` for every piece, where `kind` counts black pawn = 0,
+//!   white pawn = 1, black knight = 2, ... white king = 11,
+//! - `KEYS[768]`, `KEYS[769]`, `KEYS[770]`, `KEYS[771]` for white king side, white queen side,
+//!   black king side and black queen side castling rights,
+//! - `KEYS[772 + file]` for the en passant file, only when a pawn of the side to move stands
+//!   next to the pawn that just made a double push,
+//! - `KEYS[780]` when White is to move.
+//!
+//! The values come from SplitMix64 seeded with `SEED` rather than from Polyglot's published
+//! Random64 array, so they are stable across builds and platforms but do not match Polyglot book
+//! hashes. Replacing `KEYS` with that array is all it takes to make them match.
+
+use crate::chess::moves::CastleSide;
+use crate::chess::piece::{Color, Piece};
+use crate::chess::position::CastlingRights;
+use crate::chess::square::Square;
+
+const SEED: u64 = 0x0C4E_5500_2B1D_F00D;
+
+const CASTLING_OFFSET: usize = 768;
+const EN_PASSANT_OFFSET: usize = 772;
+const TURN_OFFSET: usize = 780;
+
+static KEYS: [u64; 781] = generate_keys();
+
+const fn generate_keys() -> [u64; 781] {
+    let mut keys = [0; 781];
+    let mut state = SEED;
+    let mut i = 0;
+    while i < keys.len() {
+        state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        keys[i] = z ^ (z >> 31);
+        i += 1;
+    }
+    keys
+}
+
+pub(crate) fn piece_key(piece: Piece, square: Square) -> u64 {
+    let kind = 2 * piece.kind.index() + (piece.color == Color::White) as usize;
+    KEYS[64 * kind + square.index()]
+}
+
+pub(crate) fn castling_key(rights: CastlingRights) -> u64 {
+    [
+        (Color::White, CastleSide::KingSide),
+        (Color::White, CastleSide::QueenSide),
+        (Color::Black, CastleSide::KingSide),
+        (Color::Black, CastleSide::QueenSide),
+    ]
+    .iter()
+    .enumerate()
+    .filter(|(_, &(color, side))| rights.has(color, side))
+    .fold(0, |key, (i, _)| key ^ KEYS[CASTLING_OFFSET + i])
+}
+
+pub(crate) fn en_passant_key(square: Square) -> u64 {
+    KEYS[EN_PASSANT_OFFSET + square.file() as usize]
+}
+
+pub(crate) fn turn_key(color: Color) -> u64 {
+    match color {
+        Color::White => KEYS[TURN_OFFSET],
+        Color::Black => 0,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::chess::game::Game;
+    use crate::chess::position::Position;
+
+    fn play(moves: &[&str]) -> Game {
+        let mut game = Game::new();
+        for san in moves {
+            let mv = game.position().parse_san(san).unwrap();
+            game.play(mv).unwrap();
+        }
+        game
+    }
+
+    #[test]
+    fn test_incremental_hash_matches_fen() {
+        let game = play(&[
+            "e4", "Nf6", "e5", "d5", "exd6", "exd6", "Nf3", "Be7", "Bc4", "O-O", "Ke2", "Re8",
+        ]);
+        for position in game.positions() {
+            let parsed = Position::from_fen(&position.to_fen()).unwrap();
+            assert_eq!(
+                position.zobrist(),
+                parsed.zobrist(),
+                "{}",
+                position.to_fen()
+            );
+        }
+        assert_ne!(game.position().zobrist(), Position::starting().zobrist());
+    }
+
+    #[test]
+    fn test_transpositions_share_hash() {
+        let a = play(&["e4", "e5", "Nf3"]);
+        let b = play(&["Nf3", "e5", "e4"]);
+        assert_eq!(a.position().zobrist(), b.position().zobrist());
+
+        let c = play(&["Nf3", "Nf6", "Ng1", "Ng8"]);
+        assert_eq!(c.position().zobrist(), Position::starting().zobrist());
+    }
+
+    #[test]
+    fn test_en_passant_only_hashed_when_capturable() {
+        let without = Position::from_fen("4k3/8/8/8/4P3/8/8/4K3 b - - 0 1").unwrap();
+        let with_square = Position::from_fen("4k3/8/8/8/4P3/8/8/4K3 b - e3 0 1").unwrap();
+        assert_eq!(without.zobrist(), with_square.zobrist());
+
+        let without = Position::from_fen("4k3/8/8/8/3pP3/8/8/4K3 b - - 0 1").unwrap();
+        let with_square = Position::from_fen("4k3/8/8/8/3pP3/8/8/4K3 b - e3 0 1").unwrap();
+        assert_ne!(without.zobrist(), with_square.zobrist());
+    }
+}
@@ -0,0 +1,411 @@
+use std::{str::FromStr, time::Duration};
+
+use thiserror::Error;
+
+use crate::{
+    chess::Color,
+    timer::{RealTime, TimeSource, Timer},
+};
+
+/// What a player gets back for each move they complete.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Bonus {
+    None,
+    /// Fischer increment, added after every move.
+    Increment(Duration),
+    /// Bronstein delay, the time used for a move is given back up to the delay.
+    Bronstein(Duration),
+    /// Simple (US) delay, the clock only starts counting down once the delay has passed.
+    Delay(Duration),
+}
+
+impl Bonus {
+    fn duration(self) -> Duration {
+        match self {
+            Bonus::None => Duration::ZERO,
+            Bonus::Increment(d) | Bonus::Bronstein(d) | Bonus::Delay(d) => d,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Stage {
+    /// Moves to be made within the stage, `None` for the rest of the game.
+    pub moves: Option<u32>,
+    pub time: Duration,
+    pub bonus: Bonus,
+}
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum TimeControlError {
+    #[error("\"{0}\" is not a valid time control")]
+    Invalid(String),
+}
+
+/// Time control made of one or more stages. A player who completes the moves of the last stage
+/// starts it over, as in `40/120` meaning 120 minutes for every 40 moves.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TimeControl {
+    stages: Vec<Stage>,
+}
+
+impl TimeControl {
+    pub fn sudden_death(time: Duration) -> Self {
+        Self::single(time, Bonus::None)
+    }
+
+    pub fn fischer(time: Duration, increment: Duration) -> Self {
+        Self::single(time, Bonus::Increment(increment))
+    }
+
+    pub fn bronstein(time: Duration, delay: Duration) -> Self {
+        Self::single(time, Bonus::Bronstein(delay))
+    }
+
+    pub fn simple_delay(time: Duration, delay: Duration) -> Self {
+        Self::single(time, Bonus::Delay(delay))
+    }
+
+    /// Creates a multi-stage control, e.g. 40 moves in 90 minutes followed by 30 minutes for
+    /// the rest of the game. Panics if `stages` is empty.
+    pub fn staged(stages: Vec<Stage>) -> Self {
+        assert!(
+            !stages.is_empty(),
+            "a time control needs at least one stage"
+        );
+        Self { stages }
+    }
+
+    fn single(time: Duration, bonus: Bonus) -> Self {
+        Self::staged(vec![Stage {
+            moves: None,
+            time,
+            bonus,
+        }])
+    }
+
+    pub fn stages(&self) -> &[Stage] {
+        &self.stages
+    }
+}
+
+impl FromStr for TimeControl {
+    type Err = TimeControlError;
+
+    /// Parses the format of the PGN `TimeControl` tag with times in seconds, e.g. `300+2` or
+    /// `40/5400+30:1800+30`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || TimeControlError::Invalid(s.to_string());
+        let seconds = |text: &str| text.parse().map(Duration::from_secs).map_err(|_| invalid());
+
+        let stages = s
+            .split(':')
+            .map(|field| {
+                let (moves, field) = match field.split_once('/') {
+                    Some((moves, rest)) => (Some(moves.parse().map_err(|_| invalid())?), rest),
+                    None => (None, field),
+                };
+                let (time, bonus) = match field.split_once('+') {
+                    Some((time, increment)) => (time, Bonus::Increment(seconds(increment)?)),
+                    None => (field, Bonus::None),
+                };
+
+                Ok(Stage {
+                    moves,
+                    time: seconds(time)?,
+                    bonus,
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Self::staged(stages))
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+struct SideClock {
+    remaining: Duration,
+    stage: usize,
+    stage_moves: u32,
+}
+
+/// Two-player game clock. Only the side to move has its time running; pressing the clock after
+/// a move charges the time used, applies the stage bonus and starts the opponent's time.
+pub struct Clock<S: TimeSource = RealTime> {
+    control: TimeControl,
+    sides: [SideClock; 2],
+    running: Option<Color>,
+    paused: bool,
+    turn: Timer<S>,
+    /// Time used in the current turn before the clock was last paused.
+    turn_elapsed: Duration,
+    flagged: Option<Color>,
+}
+
+impl Clock {
+    pub fn new(control: TimeControl) -> Self {
+        Self::with_source(control, RealTime)
+    }
+}
+
+impl<S: TimeSource> Clock<S> {
+    pub fn with_source(control: TimeControl, source: S) -> Self {
+        let side = SideClock {
+            remaining: control.stages[0].time,
+            stage: 0,
+            stage_moves: 0,
+        };
+        Self {
+            control,
+            sides: [side; 2],
+            running: None,
+            paused: false,
+            turn: Timer::with_source(source),
+            turn_elapsed: Duration::ZERO,
+            flagged: None,
+        }
+    }
+
+    pub fn control(&self) -> &TimeControl {
+        &self.control
+    }
+
+    /// Starts the time of `color`, e.g. White at the beginning of the game.
+    pub fn start(&mut self, color: Color) {
+        self.running = Some(color);
+        self.paused = false;
+        self.turn.reset();
+        self.turn_elapsed = Duration::ZERO;
+    }
+
+    /// Ends the turn of the side whose time is running and starts the opponent's.
+    pub fn press(&mut self) {
+        let Some(color) = self.running else {
+            return;
+        };
+        if self.flagged.is_some() {
+            return;
+        }
+
+        let used = self.turn_time();
+        let stages = &self.control.stages;
+        let side = &mut self.sides[color.index()];
+        let stage = stages[side.stage];
+
+        let charged = charged(used, stage.bonus);
+        if charged >= side.remaining {
+            self.flagged = Some(color);
+            return;
+        }
+        side.remaining -= charged;
+        match stage.bonus {
+            Bonus::Increment(increment) => side.remaining += increment,
+            Bonus::Bronstein(delay) => side.remaining += used.min(delay),
+            Bonus::None | Bonus::Delay(_) => {}
+        }
+
+        side.stage_moves += 1;
+        if stage.moves == Some(side.stage_moves) {
+            side.stage = (side.stage + 1).min(stages.len() - 1);
+            side.stage_moves = 0;
+            side.remaining += stages[side.stage].time;
+        }
+
+        self.start(color.opposite());
+    }
+
+    /// Stops the clock for good, e.g. when the game is over.
+    pub fn stop(&mut self) {
+        if let Some(color) = self.running {
+            self.flagged = self.flagged();
+            self.sides[color.index()].remaining = self.remaining(color);
+        }
+        self.running = None;
+    }
+
+    pub fn pause(&mut self) {
+        if self.running.is_some() && !self.paused {
+            self.turn_elapsed += self.turn.duration();
+            self.paused = true;
+        }
+    }
+
+    pub fn resume(&mut self) {
+        if self.paused {
+            self.paused = false;
+            self.turn.reset();
+        }
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    /// The side whose time is running, also while paused.
+    pub fn running(&self) -> Option<Color> {
+        self.running
+    }
+
+    pub fn remaining(&self, color: Color) -> Duration {
+        let side = &self.sides[color.index()];
+        if self.running == Some(color) {
+            let bonus = self.control.stages[side.stage].bonus;
+            side.remaining
+                .saturating_sub(charged(self.turn_time(), bonus))
+        } else {
+            side.remaining
+        }
+    }
+
+    /// The side that ran out of time, if any.
+    pub fn flagged(&self) -> Option<Color> {
+        self.flagged.or_else(|| {
+            self.running
+                .filter(|&color| self.remaining(color) == Duration::ZERO)
+        })
+    }
+
+    /// Moves `color` has left to make in the current stage, `None` in a sudden death stage.
+    pub fn moves_to_go(&self, color: Color) -> Option<u32> {
+        let side = &self.sides[color.index()];
+        self.control.stages[side.stage]
+            .moves
+            .map(|moves| moves - side.stage_moves)
+    }
+
+    /// Increment or delay `color` gets for each move in the current stage.
+    pub fn increment(&self, color: Color) -> Duration {
+        let side = &self.sides[color.index()];
+        self.control.stages[side.stage].bonus.duration()
+    }
+
+    fn turn_time(&self) -> Duration {
+        if self.running.is_some() && !self.paused {
+            self.turn_elapsed + self.turn.duration()
+        } else {
+            self.turn_elapsed
+        }
+    }
+}
+
+/// Time taken off the clock for a turn that took `used`.
+fn charged(used: Duration, bonus: Bonus) -> Duration {
+    match bonus {
+        Bonus::Delay(delay) => used.saturating_sub(delay),
+        _ => used,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::timer::ManualTime;
+
+    fn secs(secs: u64) -> Duration {
+        Duration::from_secs(secs)
+    }
+
+    fn clock(control: TimeControl) -> (Clock<ManualTime>, ManualTime) {
+        let time = ManualTime::default();
+        let mut clock = Clock::with_source(control, time.clone());
+        clock.start(Color::White);
+        (clock, time)
+    }
+
+    #[test]
+    fn test_sudden_death_flag() {
+        let (mut clock, time) = clock(TimeControl::sudden_death(secs(60)));
+        time.advance(secs(20));
+        clock.press();
+        assert_eq!(clock.remaining(Color::White), secs(40));
+        assert_eq!(clock.running(), Some(Color::Black));
+
+        time.advance(secs(59));
+        assert_eq!(clock.remaining(Color::Black), secs(1));
+        assert_eq!(clock.flagged(), None);
+
+        time.advance(secs(5));
+        assert_eq!(clock.remaining(Color::Black), Duration::ZERO);
+        assert_eq!(clock.flagged(), Some(Color::Black));
+
+        clock.press();
+        assert_eq!(clock.flagged(), Some(Color::Black));
+        assert_eq!(clock.running(), Some(Color::Black));
+    }
+
+    #[test]
+    fn test_increment_and_delays() {
+        let (mut fischer, time) = clock(TimeControl::fischer(secs(60), secs(5)));
+        time.advance(secs(2));
+        fischer.press();
+        assert_eq!(fischer.remaining(Color::White), secs(63));
+
+        let (mut bronstein, time) = clock(TimeControl::bronstein(secs(60), secs(5)));
+        time.advance(secs(2));
+        bronstein.press();
+        assert_eq!(bronstein.remaining(Color::White), secs(60));
+        time.advance(secs(8));
+        bronstein.press();
+        assert_eq!(bronstein.remaining(Color::Black), secs(57));
+
+        let (mut delay, time) = clock(TimeControl::simple_delay(secs(60), secs(5)));
+        time.advance(secs(4));
+        assert_eq!(delay.remaining(Color::White), secs(60));
+        time.advance(secs(4));
+        assert_eq!(delay.remaining(Color::White), secs(57));
+        delay.press();
+        assert_eq!(delay.remaining(Color::White), secs(57));
+    }
+
+    #[test]
+    fn test_multi_stage() {
+        // 40 moves in 90 minutes, then 30 minutes for the rest of the game, 30s increment
+        let control: TimeControl = "40/5400+30:1800+30".parse().unwrap();
+        assert_eq!(control.stages().len(), 2);
+
+        let (mut clock, time) = clock(control);
+        for _ in 0..39 {
+            time.advance(secs(60));
+            clock.press();
+            clock.press();
+        }
+        assert_eq!(clock.moves_to_go(Color::White), Some(1));
+        assert_eq!(clock.remaining(Color::White), secs(5400 - 39 * 30));
+
+        time.advance(secs(60));
+        clock.press();
+        assert_eq!(clock.moves_to_go(Color::White), None);
+        assert_eq!(clock.remaining(Color::White), secs(5400 - 40 * 30 + 1800));
+        assert_eq!(clock.moves_to_go(Color::Black), Some(1));
+    }
+
+    #[test]
+    fn test_pause() {
+        let (mut clock, time) = clock(TimeControl::sudden_death(secs(60)));
+        time.advance(secs(10));
+        clock.pause();
+        time.advance(secs(100));
+        assert_eq!(clock.remaining(Color::White), secs(50));
+        assert_eq!(clock.flagged(), None);
+
+        clock.resume();
+        time.advance(secs(10));
+        clock.press();
+        assert_eq!(clock.remaining(Color::White), secs(40));
+    }
+
+    #[test]
+    fn test_parse_time_control() {
+        assert_eq!(
+            "300+2".parse(),
+            Ok(TimeControl::fischer(secs(300), secs(2)))
+        );
+        assert_eq!("60".parse(), Ok(TimeControl::sudden_death(secs(60))));
+        for invalid in ["", "5m", "40/", "300+"] {
+            assert_eq!(
+                invalid.parse::<TimeControl>(),
+                Err(TimeControlError::Invalid(invalid.to_string()))
+            );
+        }
+    }
+}
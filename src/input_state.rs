@@ -22,6 +22,8 @@ pub struct InputState {
     held_keys: HashSet<Key>,
     released_keys: HashSet<Key>,
     mouse_offset: Offset,
+    cursor_position: Option<Position>,
+    scroll_delta: f32,
     touches: HashMap<u64, Touch>,
     ended_touch_ids: SmallVec<[u64; 8]>,
 }
@@ -40,6 +42,8 @@ impl InputState {
             held_keys: HashSet::new(),
             released_keys: HashSet::new(),
             mouse_offset: Default::default(),
+            cursor_position: None,
+            scroll_delta: 0.0,
             touches: HashMap::new(),
             ended_touch_ids: SmallVec::new(),
         }
@@ -61,6 +65,16 @@ impl InputState {
         self.mouse_offset
     }
 
+    /// Cursor position in window pixels, `None` while the cursor is outside the window.
+    pub fn cursor_position(&self) -> Option<Position> {
+        self.cursor_position
+    }
+
+    /// Lines scrolled with the mouse wheel this frame, positive when scrolling up.
+    pub fn scroll_delta(&self) -> f32 {
+        self.scroll_delta
+    }
+
     pub fn touches(&self) -> impl Iterator<Item = &Touch> {
         self.touches.values()
     }
@@ -69,6 +83,7 @@ impl InputState {
         self.released_keys.clear();
         self.pressed_keys.clear();
         self.mouse_offset = Point2D::new(0.0, 0.0);
+        self.scroll_delta = 0.0;
         for id in self.ended_touch_ids.drain(..) {
             self.touches.remove(&id);
         }
@@ -78,6 +93,14 @@ impl InputState {
         self.mouse_offset = offset;
     }
 
+    pub fn set_cursor_position(&mut self, position: Option<Position>) {
+        self.cursor_position = position;
+    }
+
+    pub fn add_scroll_delta(&mut self, lines: f32) {
+        self.scroll_delta += lines;
+    }
+
     pub fn set_touch_start_position(&mut self, id: u64, position: Position) {
         self.touches.insert(
             id,
@@ -1,14 +1,23 @@
+use std::{
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+use image::RgbaImage;
 use math::Point2D;
+use rand::Rng;
 use winit::{
-    event::{DeviceEvent, ElementState, Event, TouchPhase, WindowEvent},
+    event::{DeviceEvent, ElementState, Event, MouseScrollDelta, TouchPhase, WindowEvent},
     event_loop::EventLoop,
     window::WindowBuilder,
 };
 
 use crate::{
     assets::Assets,
-    input_state::{InputState, Key},
-    logging::{debug, warn},
+    chess::{search::SearchLimits, uci::UciEngine, Color, FenError, CHESS960_POSITIONS},
+    clock::TimeControl,
+    input_state::{InputState, Key, VirtualKeyCode},
+    logging::{debug, info, warn},
     rendering::renderer::Renderer,
     scenes::{DynamicScene, PlaygroundScene},
     timer::Timer,
@@ -16,19 +25,35 @@ use crate::{
 
 mod assets;
 mod camera;
+pub mod chess;
+pub mod clock;
 mod input_state;
 mod logging;
 mod math;
 mod obj_loader;
 mod object;
 mod path_wrangler;
+mod picking;
 mod platform;
 mod rendering;
 mod scenes;
-mod timer;
+pub mod timer;
 mod transform;
 
 const TITLE: &str = "Chessno";
+const FEN_ENV_VAR: &str = "CHESSNO_FEN";
+const PGN_ENV_VAR: &str = "CHESSNO_PGN";
+const AI_ENV_VAR: &str = "CHESSNO_AI";
+const AI_THINKING_TIME: Duration = Duration::from_secs(1);
+const UCI_ENGINE_ENV_VAR: &str = "CHESSNO_UCI_ENGINE";
+const CHESS960_ENV_VAR: &str = "CHESSNO_960";
+const TIME_CONTROL_ENV_VAR: &str = "CHESSNO_TIME_CONTROL";
+/// Pixels of touchpad scrolling counted as one line of the mouse wheel.
+const SCROLL_LINE_PIXELS: f64 = 40.0;
+const WIREFRAME_KEY: VirtualKeyCode = VirtualKeyCode::F3;
+const SCREENSHOT_KEY: VirtualKeyCode = VirtualKeyCode::F12;
+/// Directory screenshots are saved to, the working directory if unset.
+const SCREENSHOT_DIR_ENV_VAR: &str = "CHESSNO_SCREENSHOT_DIR";
 
 #[cfg_attr(
     target_os = "android",
@@ -51,7 +76,61 @@ pub fn main() {
     let mut timer = Timer::new();
     let mut input_state = InputState::new();
 
-    let mut scene = PlaygroundScene::new(&mut assets);
+    let chess960_index = match std::env::var(CHESS960_ENV_VAR).as_deref() {
+        Ok("random") => Some(rand::thread_rng().gen_range(0..CHESS960_POSITIONS)),
+        Ok(index) => index
+            .parse()
+            .ok()
+            .filter(|&index| index < CHESS960_POSITIONS)
+            .or_else(|| {
+                warn!("Ignoring {CHESS960_ENV_VAR}: expected 0-959 or \"random\", got {index:?}");
+                None
+            }),
+        Err(_) => None,
+    };
+    let mut scene = match (std::env::var(FEN_ENV_VAR), chess960_index) {
+        (Ok(fen), _) => PlaygroundScene::from_fen(&mut assets, &fen).unwrap_or_else(|e| {
+            warn!("Ignoring {FEN_ENV_VAR}: {e}");
+            PlaygroundScene::new(&mut assets)
+        }),
+        (Err(_), Some(index)) => {
+            debug!("Starting Chess960 position {index}");
+            PlaygroundScene::chess960(&mut assets, index).unwrap()
+        }
+        (Err(_), None) => PlaygroundScene::new(&mut assets),
+    };
+    if let Ok(pgn_path) = std::env::var(PGN_ENV_VAR) {
+        scene
+            .load_pgn(assets.asset_locator(), Path::new(&pgn_path))
+            .unwrap_or_else(|e| warn!("Ignoring {PGN_ENV_VAR}: {e}"));
+    }
+    if let Ok(time_control) = std::env::var(TIME_CONTROL_ENV_VAR) {
+        match time_control.parse::<TimeControl>() {
+            Ok(control) => scene.set_clock(control),
+            Err(e) => warn!("Ignoring {TIME_CONTROL_ENV_VAR}: {e}"),
+        }
+    }
+    let ai_color = match std::env::var(AI_ENV_VAR).as_deref() {
+        Ok("white") => Some(Color::White),
+        Ok("black") => Some(Color::Black),
+        Ok(other) => {
+            warn!("Ignoring {AI_ENV_VAR}: expected \"white\" or \"black\", got \"{other}\"");
+            None
+        }
+        Err(_) => None,
+    };
+    if let Some(color) = ai_color {
+        let limits = SearchLimits::time(AI_THINKING_TIME);
+        let uci_engine = std::env::var(UCI_ENGINE_ENV_VAR).ok().and_then(|path| {
+            UciEngine::spawn(&path, &[] as &[&str])
+                .map_err(|e| warn!("Using the built-in engine, {path} failed to start: {e}"))
+                .ok()
+        });
+        match uci_engine {
+            Some(engine) => scene.set_uci_opponent(color, limits, engine),
+            None => scene.set_opponent(color, limits),
+        }
+    }
     let mut active = false;
 
     event_loop.run(move |event, _, control_flow| match event {
@@ -59,6 +138,7 @@ pub fn main() {
             debug!("Resumed");
             active = true;
             timer.reset();
+            scene.resume_clock();
             match &mut renderer {
                 Some(renderer) => {
                     debug!("Invalidating surface after resume");
@@ -74,6 +154,7 @@ pub fn main() {
         }
         Event::Suspended => {
             active = false;
+            scene.pause_clock();
             if let Some(renderer) = &mut renderer {
                 renderer.pause();
             }
@@ -114,6 +195,18 @@ pub fn main() {
                     warn!("{e:?}");
                 }
             },
+            WindowEvent::CursorMoved { position, .. } => {
+                input_state.set_cursor_position(Some(Point2D::new(position.x, position.y)));
+            }
+            WindowEvent::CursorLeft { .. } => {
+                input_state.set_cursor_position(None);
+            }
+            WindowEvent::MouseWheel { delta, .. } => match delta {
+                MouseScrollDelta::LineDelta(_, lines) => input_state.add_scroll_delta(lines),
+                MouseScrollDelta::PixelDelta(pixels) => {
+                    input_state.add_scroll_delta((pixels.y / SCROLL_LINE_PIXELS) as f32);
+                }
+            },
             WindowEvent::MouseInput { state, button, .. } => match state {
                 ElementState::Pressed => input_state.set_pressed(Key::MouseButton(button)),
                 ElementState::Released => input_state.set_released(Key::MouseButton(button)),
@@ -142,6 +235,14 @@ pub fn main() {
                 scene.update(&window, &input_state, delta, &mut assets);
 
                 if let Some(renderer) = &mut renderer {
+                    if input_state.is_pressed(Key::KeyboardKey(WIREFRAME_KEY)) {
+                        renderer.set_wireframe(!renderer.wireframe());
+                    }
+                    if input_state.is_pressed(Key::KeyboardKey(SCREENSHOT_KEY)) {
+                        if let Some(image) = renderer.screenshot(&mut scene, &assets) {
+                            save_screenshot(&image);
+                        }
+                    }
                     renderer.draw(&mut scene, &mut assets);
                 }
 
@@ -151,3 +252,27 @@ pub fn main() {
         _ => {}
     })
 }
+
+/// Renders the board in the position described by `fen` as seen from white's side into a
+/// `width` by `height` image, without opening a window.
+pub fn render_position(fen: &str, width: u32, height: u32) -> Result<RgbaImage, FenError> {
+    let mut assets = Assets::new();
+    let mut scene = PlaygroundScene::from_fen(&mut assets, fen)?;
+    let mut renderer = Renderer::headless(TITLE);
+    renderer.load_assets(&assets);
+
+    Ok(renderer.render_to_image(&mut scene, &assets, width, height))
+}
+
+fn save_screenshot(image: &RgbaImage) {
+    let seconds = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let dir = std::env::var(SCREENSHOT_DIR_ENV_VAR).unwrap_or_else(|_| ".".to_string());
+    let path = PathBuf::from(dir).join(format!("chessno-{seconds}.png"));
+    match image.save(&path) {
+        Ok(()) => info!("Saved screenshot to {}", path.display()),
+        Err(e) => warn!("Failed to save screenshot to {}: {e}", path.display()),
+    }
+}
@@ -44,3 +44,28 @@ impl Rectangle {
         self.height
     }
 }
+
+/// Cubic ease-in-out of `t` in `[0, 1]`: starts and ends slowly.
+pub fn ease_in_out(t: f32) -> f32 {
+    let t = t.clamp(0.0, 1.0);
+    if t < 0.5 {
+        4.0 * t * t * t
+    } else {
+        1.0 - (-2.0 * t + 2.0).powi(3) / 2.0
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_ease_in_out() {
+        assert_eq!(ease_in_out(0.0), 0.0);
+        assert_eq!(ease_in_out(0.5), 0.5);
+        assert_eq!(ease_in_out(1.0), 1.0);
+        assert!(ease_in_out(0.1) < 0.1);
+        assert!(ease_in_out(0.9) > 0.9);
+        assert_eq!(ease_in_out(2.0), 1.0);
+    }
+}
@@ -0,0 +1,69 @@
+//! Scripted stand-in for a UCI engine, used by the UCI client tests.
+//!
+//! It plays the first legal move after a single fake info line, waits for `stop` when told to
+//! `go infinite`, and reports every option it is given as an `info string`.
+
+use std::io::{self, BufRead, Write};
+
+use chessno::chess::{uci::parse_position, Game};
+
+fn main() {
+    let stdin = io::stdin();
+    let mut stdout = io::stdout();
+    let mut game = Game::new();
+    let mut pending_stop = false;
+
+    for line in stdin.lock().lines() {
+        let line = line.unwrap();
+        let mut tokens = line.split_whitespace();
+        let replies = match tokens.next() {
+            Some("uci") => vec![
+                "id name Mock Engine".to_string(),
+                "id author chessno".to_string(),
+                "option name Hash type spin default 16 min 1 max 64".to_string(),
+                "option name Skill Level type spin default 20 min 0 max 20".to_string(),
+                "uciok".to_string(),
+            ],
+            Some("isready") => vec!["readyok".to_string()],
+            Some("setoption") => {
+                let rest = tokens.collect::<Vec<_>>().join(" ");
+                let rest = rest.strip_prefix("name ").unwrap_or(&rest);
+                let (name, value) = rest.split_once(" value ").unwrap_or((rest, ""));
+                vec![format!("info string set {name} to {value}")]
+            }
+            Some("position") => {
+                game = parse_position(&tokens.collect::<Vec<_>>().join(" ")).unwrap();
+                Vec::new()
+            }
+            Some("go") => {
+                if tokens.any(|t| t == "infinite") {
+                    pending_stop = true;
+                    Vec::new()
+                } else {
+                    best_move(&game)
+                }
+            }
+            Some("stop") if pending_stop => {
+                pending_stop = false;
+                best_move(&game)
+            }
+            Some("quit") => break,
+            _ => Vec::new(),
+        };
+
+        for reply in replies {
+            writeln!(stdout, "{reply}").unwrap();
+        }
+        stdout.flush().unwrap();
+    }
+}
+
+fn best_move(game: &Game) -> Vec<String> {
+    match game.legal_moves().first() {
+        Some(mv) => vec![
+            format!("info depth 1 score cp 13 nodes 20 time 0 pv {mv}"),
+            format!("bestmove {mv}"),
+        ],
+        None => vec!["bestmove (none)".to_string()],
+    }
+}
@@ -1,9 +1,9 @@
 use std::borrow::Cow;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
 
-use log::{trace, warn};
-use obj::{Group, Obj, ObjData, ObjMaterial, SimplePolygon};
+use log::warn;
+use obj::{Group, IndexTuple, Obj, ObjData, ObjMaterial};
 use smallvec::{smallvec, SmallVec};
 
 use crate::assets::MaterialId;
@@ -11,7 +11,7 @@ use crate::{
     assets::{Assets, MeshId, TextureId, FALLBACK_TEXTURE},
     path_wrangler::PathWrangler,
     rendering::{
-        mesh::{BBox, Mesh, Submesh},
+        mesh::{BBox, Indices, Mesh, Submesh},
         texture::Texture,
         vertex::Vertex,
     },
@@ -19,18 +19,20 @@ use crate::{
 
 pub struct ObjLoader<'a> {
     vertices: Vec<Vertex>,
-    indices: Vec<u16>,
+    indices: Vec<u32>,
+    /// Index of the vertex made for each position, UV and normal index combination, so
+    /// corners sharing all three share a vertex.
+    welded: HashMap<IndexTuple, u32>,
     textures: HashSet<TextureId>,
     assets: &'a mut Assets,
 }
 
-struct IndexedVertex(usize, Vertex);
-
 impl<'a> ObjLoader<'a> {
     pub fn new(assets: &'a mut Assets) -> Self {
         Self {
             vertices: Vec::new(),
             indices: Vec::new(),
+            welded: HashMap::new(),
             textures: HashSet::new(),
             assets,
         }
@@ -46,7 +48,7 @@ impl<'a> ObjLoader<'a> {
         };
 
         let vertex_count = obj.data.position.len();
-        self.vertices = vec![Vertex::zeroed(); vertex_count];
+        self.vertices = Vec::with_capacity(vertex_count);
         self.indices = Vec::with_capacity(vertex_count);
 
         obj.load_mtls_fn(|path, mat_name| {
@@ -60,7 +62,9 @@ impl<'a> ObjLoader<'a> {
         let vertices = std::mem::replace(&mut self.vertices, Vec::new());
         let indices = std::mem::replace(&mut self.indices, Vec::new());
         let textures = std::mem::replace(&mut self.textures, HashSet::new());
-        let mesh = Mesh {
+        self.welded.clear();
+        let indices = Indices::new(indices, vertices.len());
+        let mut mesh = Mesh {
             id: 0,
             vertices,
             indices,
@@ -68,6 +72,7 @@ impl<'a> ObjLoader<'a> {
             submeshes,
             bbox: BBox::default(),
         };
+        mesh.compute_missing_normals();
 
         self.assets.insert_mesh(name, mesh)
     }
@@ -76,24 +81,18 @@ impl<'a> ObjLoader<'a> {
         let mut submesh_start;
         let mut submesh_end = 0;
         let mut submeshes = Vec::with_capacity(1);
-        let zero_vertex = Vertex::zeroed();
         for object in &obj.data.objects {
             for group in &object.groups {
                 submesh_start = submesh_end;
                 for poly in &group.polys {
-                    let poly_indices = self.indices(&poly);
+                    let corners: SmallVec<[u32; 4]> = poly
+                        .0
+                        .iter()
+                        .map(|&tuple| self.weld(obj, tuple, poly.0.len()))
+                        .collect();
+                    let poly_indices = triangulate(&corners);
                     submesh_end += poly_indices.len();
-                    let poly_vertices = self.vertices(obj, &poly);
-
-                    // Update mesh vertices and indices
-                    self.indices.extend(poly_indices.iter());
-                    for iv in poly_vertices {
-                        if self.vertices[iv.0] != zero_vertex {
-                            trace!("Vertex with position index {} is already written", iv.0);
-                        } else {
-                            self.vertices[iv.0] = iv.1;
-                        }
-                    }
+                    self.indices.extend(poly_indices);
                 }
 
                 let texture_id = self.texture(obj, &group);
@@ -134,51 +133,41 @@ impl<'a> ObjLoader<'a> {
         fallback_texture_id
     }
 
-    /// Creates vertices for a polygon, looking up actual data within `obj` by indices in `poly`.
-    fn vertices(&self, obj: &Obj, poly: &SimplePolygon) -> SmallVec<[IndexedVertex; 4]> {
-        let tuples = &poly.0;
-        let positions = &obj.data.position;
-        let uvs = &obj.data.texture;
-        tuples
-            .iter()
-            .map(|t| {
-                let uv = {
-                    if let Some(uv_ix) = t.1 {
-                        let uv = uvs[uv_ix];
-                        [uv[0], uv[1], 0.0]
-                    } else {
-                        warn!("Missing UV index for vertex index {}", t.0);
-                        [0.0; 3]
-                    }
-                };
-                let pos = positions[t.0];
-
-                let color = if tuples.len() > 3 {
-                    [1.0, 0.0, 0.0]
-                } else {
-                    [1.0; 3]
-                };
-
-                IndexedVertex(t.0, Vertex { pos, uv, color })
-            })
-            .collect()
-    }
+    /// Index of the vertex for a polygon corner, creating the vertex from the data within `obj`
+    /// the first time its indices are seen.
+    fn weld(&mut self, obj: &Obj, tuple: IndexTuple, corner_count: usize) -> u32 {
+        if let Some(&ix) = self.welded.get(&tuple) {
+            return ix;
+        }
+        let IndexTuple(pos_ix, uv_ix, normal_ix) = tuple;
+
+        let uv = if let Some(uv_ix) = uv_ix {
+            let uv = obj.data.texture[uv_ix];
+            [uv[0], uv[1], 0.0]
+        } else {
+            warn!("Missing UV index for vertex index {}", pos_ix);
+            [0.0; 3]
+        };
 
-    fn indices(&self, poly: &SimplePolygon) -> SmallVec<[u16; 8]> {
-        let tuples = &poly.0;
-        let indices: SmallVec<[usize; 8]> = match tuples.len() {
-            3 => {
-                let (a, b, c) = (tuples[0], tuples[1], tuples[2]);
-                smallvec![a.0, b.0, c.0]
-            }
-            4 => {
-                let (a, b, c, d) = (tuples[0], tuples[1], tuples[2], tuples[3]);
-                smallvec![a.0, b.0, c.0, a.0, c.0, d.0]
-            }
-            _ => panic!("unsupported polygon vertex count ({})", tuples.len()),
+        let color = if corner_count > 3 {
+            [1.0, 0.0, 0.0]
+        } else {
+            [1.0; 3]
         };
 
-        indices.into_iter().map(|ix| ix as u16).collect()
+        // Missing normals are computed once the whole mesh is assembled
+        let normal = normal_ix.map_or([0.0; 3], |normal_ix| obj.data.normal[normal_ix]);
+
+        let ix = self.vertices.len() as u32;
+        self.vertices.push(Vertex {
+            pos: obj.data.position[pos_ix],
+            uv,
+            color,
+            normal,
+        });
+        self.welded.insert(tuple, ix);
+
+        ix
     }
 
     fn texture_path(&self, base_path: &Path, name: &str) -> PathBuf {
@@ -191,6 +180,15 @@ impl<'a> ObjLoader<'a> {
     }
 }
 
+/// Splits a triangle or a quad into triangles.
+fn triangulate(corners: &[u32]) -> SmallVec<[u32; 6]> {
+    match *corners {
+        [a, b, c] => smallvec![a, b, c],
+        [a, b, c, d] => smallvec![a, b, c, a, c, d],
+        _ => panic!("unsupported polygon vertex count ({})", corners.len()),
+    }
+}
+
 #[cfg(test)]
 mod test {
     use crate::assets::DEFAULT_MATERIAL;
@@ -203,6 +201,15 @@ mod test {
         let mut assets = Assets::new();
         let default_material_id = assets.id_of(DEFAULT_MATERIAL).unwrap();
         let mut loader = ObjLoader::new(&mut assets);
-        let _mesh = loader.load(path, "plant", default_material_id);
+        let mesh_id = loader.load(path, "plant", default_material_id);
+
+        // Corners sharing position, UV and normal share a vertex
+        let mesh = assets.mesh(mesh_id).unwrap();
+        assert!(mesh.vertices.len() < mesh.indices.len());
+        assert!(mesh
+            .indices
+            .triangles()
+            .flatten()
+            .all(|i| i < mesh.vertices.len()));
     }
 }
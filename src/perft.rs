@@ -0,0 +1,46 @@
+//! Runs perft on a position and prints the node count per root move.
+//!
+//! Usage: `cargo run --release --bin perft -- <depth> [fen]`
+
+use std::{env, process, time::Instant};
+
+use chessno::chess::{divide, Position, STARTING_FEN};
+
+fn main() {
+    let mut args = env::args().skip(1);
+    let depth = match args.next().map(|arg| arg.parse::<u32>()) {
+        Some(Ok(depth)) if depth > 0 => depth,
+        _ => {
+            eprintln!("usage: perft <depth> [fen]");
+            process::exit(2);
+        }
+    };
+    let fen = args.collect::<Vec<_>>().join(" ");
+    let fen = if fen.is_empty() { STARTING_FEN } else { &fen };
+
+    let position = match Position::from_fen(fen) {
+        Ok(position) => position,
+        Err(e) => {
+            eprintln!("invalid FEN: {e}");
+            process::exit(2);
+        }
+    };
+
+    let start = Instant::now();
+    let mut divided = divide(&position, depth);
+    let elapsed = start.elapsed();
+    divided.sort_by_key(|(mv, _)| mv.to_string());
+
+    let mut total = 0;
+    for (mv, nodes) in divided {
+        println!("{mv}: {nodes}");
+        total += nodes;
+    }
+    println!();
+    println!("Nodes searched: {total}");
+    println!(
+        "Time: {:.3}s ({:.0} nodes/s)",
+        elapsed.as_secs_f64(),
+        total as f64 / elapsed.as_secs_f64().max(1e-9)
+    );
+}
@@ -0,0 +1,127 @@
+use nalgebra::{Matrix4, Point3, Unit, Vector3};
+
+use crate::math::Point2D;
+
+/// Half-line in world space, e.g. from the camera through the cursor.
+#[derive(Clone, Debug)]
+pub struct Ray {
+    pub origin: Point3<f32>,
+    pub direction: Unit<Vector3<f32>>,
+}
+
+/// Axis-aligned bounding box in world space.
+#[derive(Clone, Debug)]
+pub struct Aabb {
+    pub min: Point3<f32>,
+    pub max: Point3<f32>,
+}
+
+impl Ray {
+    pub fn new(origin: Point3<f32>, direction: Vector3<f32>) -> Self {
+        Self {
+            origin,
+            direction: Unit::new_normalize(direction),
+        }
+    }
+
+    /// Unprojects a position in window pixels, origin at the top left, into the ray of world
+    /// points it covers. `view_projection` is the camera matrix the scene was drawn with.
+    /// Returns `None` if the matrix can't be inverted.
+    pub fn from_screen(
+        view_projection: &Matrix4<f32>,
+        position: Point2D,
+        width: f32,
+        height: f32,
+    ) -> Option<Self> {
+        let inverse = view_projection.try_inverse()?;
+
+        // The projection flips y, so NDC y grows downwards like window coordinates
+        let x = 2.0 * position.x as f32 / width - 1.0;
+        let y = 2.0 * position.y as f32 / height - 1.0;
+        let near = inverse.transform_point(&Point3::new(x, y, -1.0));
+        let far = inverse.transform_point(&Point3::new(x, y, 1.0));
+
+        Some(Self::new(near, far - near))
+    }
+
+    pub fn at(&self, distance: f32) -> Point3<f32> {
+        self.origin + self.direction.as_ref() * distance
+    }
+
+    /// Distance along the ray to the horizontal plane at `height`.
+    pub fn intersect_horizontal_plane(&self, height: f32) -> Option<f32> {
+        if self.direction.y.abs() < f32::EPSILON {
+            return None;
+        }
+
+        let distance = (height - self.origin.y) / self.direction.y;
+        (distance >= 0.0).then_some(distance)
+    }
+
+    /// Distance along the ray to the nearest face of `aabb`, 0 if the ray starts inside it.
+    pub fn intersect_aabb(&self, aabb: &Aabb) -> Option<f32> {
+        let mut near = 0.0f32;
+        let mut far = f32::INFINITY;
+        for axis in 0..3 {
+            let origin = self.origin[axis];
+            let direction = self.direction[axis];
+            if direction.abs() < f32::EPSILON {
+                if origin < aabb.min[axis] || origin > aabb.max[axis] {
+                    return None;
+                }
+                continue;
+            }
+
+            let t1 = (aabb.min[axis] - origin) / direction;
+            let t2 = (aabb.max[axis] - origin) / direction;
+            near = near.max(t1.min(t2));
+            far = far.min(t1.max(t2));
+            if near > far {
+                return None;
+            }
+        }
+
+        Some(near)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_ray_from_screen() {
+        let mut projection = Matrix4::new_perspective(1.0, 45f32.to_radians(), 0.1, 100.0);
+        projection.m22 *= -1.0;
+        let eye = Point3::new(0.0, 2.0, 2.0);
+        let view = Matrix4::look_at_rh(&eye, &Point3::origin(), &Vector3::y());
+
+        let ray =
+            Ray::from_screen(&(projection * view), Point2D::new(50.0, 50.0), 100.0, 100.0).unwrap();
+        let distance = ray.intersect_horizontal_plane(0.0).unwrap();
+        assert!((ray.at(distance) - Point3::origin()).norm() < 1e-3);
+
+        // Upper half of the window looks further away from the camera
+        let ray =
+            Ray::from_screen(&(projection * view), Point2D::new(50.0, 40.0), 100.0, 100.0).unwrap();
+        let distance = ray.intersect_horizontal_plane(0.0).unwrap();
+        assert!(ray.at(distance).z < 0.0);
+    }
+
+    #[test]
+    fn test_ray_aabb() {
+        let aabb = Aabb {
+            min: Point3::new(-1.0, 0.0, -1.0),
+            max: Point3::new(1.0, 2.0, 1.0),
+        };
+
+        let ray = Ray::new(Point3::new(-5.0, 1.0, 0.0), Vector3::x());
+        assert_eq!(ray.intersect_aabb(&aabb), Some(4.0));
+
+        let miss = Ray::new(Point3::new(-5.0, 3.0, 0.0), Vector3::x());
+        assert_eq!(miss.intersect_aabb(&aabb), None);
+
+        let behind = Ray::new(Point3::new(-5.0, 1.0, 0.0), -Vector3::x());
+        assert_eq!(behind.intersect_aabb(&aabb), None);
+    }
+}
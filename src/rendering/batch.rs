@@ -0,0 +1,90 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use crate::rendering::vertex::Instance;
+
+/// Instances drawn together with a single instanced draw call.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Batch<K> {
+    pub key: K,
+    /// Index of the first instance of the batch in the instance buffer.
+    pub first_instance: u32,
+    pub instance_count: u32,
+}
+
+/// Groups instances drawn with the same key, keeping the batches in the order their keys first
+/// appear in. Returns the batches and the instances laid out batch by batch.
+pub fn batch_instances<K: Copy + Eq + Hash>(
+    items: impl IntoIterator<Item = (K, Instance)>,
+) -> (Vec<Batch<K>>, Vec<Instance>) {
+    let mut group_ixs = HashMap::new();
+    let mut groups: Vec<(K, Vec<Instance>)> = Vec::new();
+    for (key, instance) in items {
+        let ix = *group_ixs.entry(key).or_insert_with(|| {
+            groups.push((key, Vec::new()));
+            groups.len() - 1
+        });
+        groups[ix].1.push(instance);
+    }
+
+    let mut batches = Vec::with_capacity(groups.len());
+    let mut instances = Vec::new();
+    for (key, group) in groups {
+        batches.push(Batch {
+            key,
+            first_instance: instances.len() as u32,
+            instance_count: group.len() as u32,
+        });
+        instances.extend(group);
+    }
+
+    (batches, instances)
+}
+
+#[cfg(test)]
+mod test {
+    use nalgebra::{Matrix4, Vector3};
+
+    use super::*;
+
+    fn instance(x: f32) -> Instance {
+        Instance {
+            model: Matrix4::new_translation(&Vector3::new(x, 0.0, 0.0)),
+        }
+    }
+
+    #[test]
+    fn test_batch_instances() {
+        let items = [
+            ('a', instance(0.0)),
+            ('b', instance(1.0)),
+            ('a', instance(2.0)),
+            ('c', instance(3.0)),
+            ('a', instance(4.0)),
+        ];
+        let (batches, instances) = batch_instances(items);
+
+        assert_eq!(
+            batches,
+            [
+                Batch {
+                    key: 'a',
+                    first_instance: 0,
+                    instance_count: 3
+                },
+                Batch {
+                    key: 'b',
+                    first_instance: 3,
+                    instance_count: 1
+                },
+                Batch {
+                    key: 'c',
+                    first_instance: 4,
+                    instance_count: 1
+                },
+            ]
+        );
+        let xs: Vec<f32> = instances.iter().map(|i| i.model[(0, 3)]).collect();
+        assert_eq!(xs, [0.0, 2.0, 4.0, 1.0, 3.0]);
+    }
+}
@@ -0,0 +1,220 @@
+//! Golden image tests, rendering fixed scenes offscreen and comparing them against the reference
+//! images in `tests/golden`.
+//!
+//! They need a Vulkan driver so they are ignored by default, and are run by hand on lavapipe as
+//! described in the readme. Run them with `cargo test golden -- --ignored` and set
+//! `CHESSNO_BLESS=1` to write the rendered images as the new references after an intended
+//! change. On a mismatch the rendered image and a diff
+//! marking the differing pixels in red are written to `target/golden`.
+
+use std::path::Path;
+use std::{env, fs};
+
+use image::{Rgba, RgbaImage};
+use nalgebra::{Point3, Vector4};
+
+use crate::{
+    assets::{Assets, DEFAULT_MATERIAL},
+    camera::Camera,
+    object::Object,
+    rendering::{
+        light::Lighting, material::Material, mesh::Mesh, projection::Projection,
+        renderer::Renderer, texture::Texture, PrimitiveType,
+    },
+    scenes::{PlaygroundScene, Scene},
+    transform::Transform,
+};
+
+const WIDTH: u32 = 320;
+const HEIGHT: u32 = 240;
+/// Largest difference in a channel for pixels to count as equal, absorbing rounding that
+/// differs between drivers.
+const TOLERANCE: u8 = 8;
+/// Fraction of the pixels allowed to differ, for edges rasterized slightly differently.
+const MAX_DIFFERING: f64 = 0.002;
+const BLESS_ENV_VAR: &str = "CHESSNO_BLESS";
+
+/// The board of a playground scene without the pieces.
+struct EmptyBoard(PlaygroundScene);
+
+impl Scene for EmptyBoard {
+    fn objects(&self) -> &[Object] {
+        self.0.board_objects()
+    }
+
+    fn active_camera(&self) -> &Camera {
+        self.0.active_camera()
+    }
+
+    fn active_camera_mut(&mut self) -> &mut Camera {
+        self.0.active_camera_mut()
+    }
+
+    fn lighting(&self) -> &Lighting {
+        self.0.lighting()
+    }
+}
+
+struct MeshScene {
+    objects: Vec<Object>,
+    camera: Camera,
+    lighting: Lighting,
+}
+
+impl Scene for MeshScene {
+    fn objects(&self) -> &[Object] {
+        &self.objects
+    }
+
+    fn active_camera(&self) -> &Camera {
+        &self.camera
+    }
+
+    fn active_camera_mut(&mut self) -> &mut Camera {
+        &mut self.camera
+    }
+
+    fn lighting(&self) -> &Lighting {
+        &self.lighting
+    }
+}
+
+struct Comparison {
+    differing: usize,
+    /// The expected image darkened, with the differing pixels in red.
+    diff: RgbaImage,
+}
+
+fn compare(actual: &RgbaImage, expected: &RgbaImage, tolerance: u8) -> Comparison {
+    let mut differing = 0;
+    let diff = RgbaImage::from_fn(expected.width(), expected.height(), |x, y| {
+        let expected = expected.get_pixel(x, y);
+        let equal = actual
+            .get_pixel(x, y)
+            .0
+            .iter()
+            .zip(expected.0)
+            .all(|(&a, e)| a.abs_diff(e) <= tolerance);
+        if equal {
+            let [r, g, b, _] = expected.0.map(|c| c / 3);
+            Rgba([r, g, b, 255])
+        } else {
+            differing += 1;
+            Rgba([255, 0, 0, 255])
+        }
+    });
+
+    Comparison { differing, diff }
+}
+
+/// Checks `actual` against the reference image `name`, or makes it the reference when
+/// blessing.
+fn assert_golden(name: &str, actual: &RgbaImage) {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let reference = root.join("tests/golden").join(format!("{name}.png"));
+    if env::var_os(BLESS_ENV_VAR).is_some() {
+        fs::create_dir_all(reference.parent().unwrap()).unwrap();
+        actual.save(&reference).unwrap();
+        return;
+    }
+
+    let output_dir = root.join("target/golden");
+    fs::create_dir_all(&output_dir).unwrap();
+    let actual_path = output_dir.join(format!("{name}-actual.png"));
+    actual.save(&actual_path).unwrap();
+
+    let expected = match image::open(&reference) {
+        Ok(image) => image.to_rgba8(),
+        Err(e) => panic!(
+            "failed to open {}: {e}, run with {BLESS_ENV_VAR}=1 to create it",
+            reference.display()
+        ),
+    };
+    assert_eq!(
+        actual.dimensions(),
+        expected.dimensions(),
+        "{name} was rendered at a different size than the reference"
+    );
+
+    let comparison = compare(actual, &expected, TOLERANCE);
+    let allowed = (MAX_DIFFERING * (expected.width() * expected.height()) as f64) as usize;
+    if comparison.differing > allowed {
+        let diff_path = output_dir.join(format!("{name}-diff.png"));
+        comparison.diff.save(&diff_path).unwrap();
+        panic!(
+            "{name}: {} pixels differ from {}, {allowed} allowed, see {} and {}",
+            comparison.differing,
+            reference.display(),
+            actual_path.display(),
+            diff_path.display()
+        );
+    }
+}
+
+fn render(scene: &mut impl Scene, assets: &Assets) -> RgbaImage {
+    let mut renderer = Renderer::headless("chessno-golden");
+    renderer.load_assets(assets);
+    renderer.render_to_image(scene, assets, WIDTH, HEIGHT)
+}
+
+#[test]
+fn test_compare() {
+    let expected = RgbaImage::from_pixel(4, 4, Rgba([90, 120, 150, 255]));
+    let mut actual = expected.clone();
+    actual.put_pixel(0, 0, Rgba([95, 120, 150, 255]));
+    actual.put_pixel(3, 3, Rgba([90, 120, 200, 255]));
+
+    let comparison = compare(&actual, &expected, 5);
+    assert_eq!(comparison.differing, 1);
+    assert_eq!(*comparison.diff.get_pixel(3, 3), Rgba([255, 0, 0, 255]));
+    assert_eq!(*comparison.diff.get_pixel(0, 0), Rgba([30, 40, 50, 255]));
+    assert_eq!(compare(&actual, &expected, 50).differing, 0);
+}
+
+#[test]
+#[ignore]
+fn test_golden_empty_board() {
+    let mut assets = Assets::new();
+    let mut scene = EmptyBoard(PlaygroundScene::new(&mut assets));
+    assert_golden("empty_board", &render(&mut scene, &assets));
+}
+
+#[test]
+#[ignore]
+fn test_golden_start_position() {
+    let mut assets = Assets::new();
+    let mut scene = PlaygroundScene::new(&mut assets);
+    assert_golden("start_position", &render(&mut scene, &assets));
+}
+
+#[test]
+#[ignore]
+fn test_golden_textured_mesh() {
+    let mut assets = Assets::new();
+    let texture =
+        Texture::from_asset(assets.asset_locator(), Path::new("textures/shrek.jpg")).unwrap();
+    let texture_id = assets.insert_texture("shrek", texture);
+    let default_material_id = assets.id_of(DEFAULT_MATERIAL).unwrap();
+    let material = Material {
+        id: 0,
+        texture_id,
+        ..*assets.material(default_material_id).unwrap()
+    };
+    let material_id = assets.insert_material("shrek_material", material);
+    let mesh_id = assets.insert_mesh("plane", Mesh::new_plane(texture_id, material_id));
+
+    // Lying flat like a board cell, seen from above at an angle
+    let plane = Object {
+        mesh_id,
+        primitive_type: PrimitiveType::Triangles,
+        transform: Transform::new(Point3::origin(), Vector4::new(1.0, 0.0, 0.0, 90.0), 1.0),
+    };
+    let eye = Point3::new(0.0, 1.2, 0.8);
+    let projection = Projection::perspective(45.0, 0.1, 100.0);
+    let mut scene = MeshScene {
+        objects: vec![plane],
+        camera: Camera::new(&eye, &-eye.coords, projection),
+        lighting: Lighting::default(),
+    };
+    assert_golden("textured_mesh", &render(&mut scene, &assets));
+}
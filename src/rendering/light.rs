@@ -0,0 +1,130 @@
+use nalgebra::{Point3, Vector3};
+
+/// Number of point lights the lit shaders take, the rest are ignored.
+pub const MAX_POINT_LIGHTS: usize = 4;
+
+/// Light from far away shining the same way everywhere, like the sun.
+#[derive(Clone, Debug)]
+pub struct DirectionalLight {
+    /// Direction the light travels in.
+    pub direction: Vector3<f32>,
+    pub color: [f32; 3],
+    pub intensity: f32,
+}
+
+/// Light shining from a point, fading out to nothing at `range`.
+#[derive(Clone, Debug)]
+pub struct PointLight {
+    pub position: Point3<f32>,
+    pub color: [f32; 3],
+    pub intensity: f32,
+    pub range: f32,
+}
+
+/// Lights of a scene, as used by the lit materials.
+#[derive(Clone, Debug)]
+pub struct Lighting {
+    pub ambient: [f32; 3],
+    pub directional: DirectionalLight,
+    pub point_lights: Vec<PointLight>,
+}
+
+impl Default for Lighting {
+    /// Dim ambient light and white light from above and in front.
+    fn default() -> Self {
+        Self {
+            ambient: [0.15; 3],
+            directional: DirectionalLight {
+                direction: Vector3::new(-0.3, -1.0, -0.5),
+                color: [1.0; 3],
+                intensity: 0.9,
+            },
+            point_lights: Vec::new(),
+        }
+    }
+}
+
+/// The lights uniform buffer of the lit shaders, in std140 layout. Colours are premultiplied
+/// by intensity.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct LightsUniform {
+    ambient: [f32; 4],
+    camera_position: [f32; 4],
+    directional_direction: [f32; 4],
+    directional_color: [f32; 4],
+    /// Position with the range in `w` followed by the colour.
+    point_lights: [[[f32; 4]; 2]; MAX_POINT_LIGHTS],
+    point_light_count: u32,
+    _padding: [u32; 3],
+}
+
+impl LightsUniform {
+    pub fn new(lighting: &Lighting, camera_position: &Point3<f32>) -> Self {
+        let mut uniform = Self {
+            ambient: extend(lighting.ambient, 0.0),
+            camera_position: extend(camera_position.coords.into(), 1.0),
+            directional_direction: extend(lighting.directional.direction.normalize().into(), 0.0),
+            directional_color: premultiplied(
+                lighting.directional.color,
+                lighting.directional.intensity,
+            ),
+            ..Default::default()
+        };
+
+        for (slot, light) in uniform.point_lights.iter_mut().zip(&lighting.point_lights) {
+            *slot = [
+                extend(light.position.coords.into(), light.range),
+                premultiplied(light.color, light.intensity),
+            ];
+            uniform.point_light_count += 1;
+        }
+
+        uniform
+    }
+}
+
+fn extend([x, y, z]: [f32; 3], w: f32) -> [f32; 4] {
+    [x, y, z, w]
+}
+
+fn premultiplied(color: [f32; 3], intensity: f32) -> [f32; 4] {
+    extend(color.map(|c| c * intensity), 0.0)
+}
+
+#[cfg(test)]
+mod test {
+    use std::mem::size_of;
+
+    use super::*;
+
+    #[test]
+    fn test_lights_uniform() {
+        // Four vec4s, the point light array and the count rounded up to a vec4
+        assert_eq!(
+            size_of::<LightsUniform>(),
+            16 * 4 + 32 * MAX_POINT_LIGHTS + 16
+        );
+
+        let light = PointLight {
+            position: Point3::new(1.0, 2.0, 3.0),
+            color: [1.0, 0.5, 0.0],
+            intensity: 2.0,
+            range: 5.0,
+        };
+        let lighting = Lighting {
+            point_lights: vec![light; MAX_POINT_LIGHTS + 2],
+            ..Default::default()
+        };
+        let uniform = LightsUniform::new(&lighting, &Point3::new(0.0, 1.0, 0.0));
+
+        assert_eq!(uniform.point_light_count, MAX_POINT_LIGHTS as u32);
+        assert_eq!(
+            uniform.point_lights[0],
+            [[1.0, 2.0, 3.0, 5.0], [2.0, 1.0, 0.0, 0.0]]
+        );
+        assert_eq!(uniform.camera_position, [0.0, 1.0, 0.0, 1.0]);
+        let [x, y, z, _] = uniform.directional_direction;
+        assert!((Vector3::new(x, y, z).norm() - 1.0).abs() < 1e-6);
+    }
+}
@@ -5,6 +5,7 @@ pub struct Material {
     pub vertex_shader_id: ShaderId,
     pub fragment_shader_id: ShaderId,
     pub texture_id: TextureId,
+    pub sampler: SamplerSettings,
 }
 
 impl Asset for Material {
@@ -12,3 +13,39 @@ impl Asset for Material {
         self.id
     }
 }
+
+/// How the texture of a material is sampled.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct SamplerSettings {
+    /// Filtering within and between mip levels.
+    pub filter: Filter,
+    pub wrap: Wrap,
+    /// Samples taken along the direction a texture is stretched in, 1 turning anisotropic
+    /// filtering off. Limited to what the device supports.
+    pub max_anisotropy: u8,
+}
+
+impl Default for SamplerSettings {
+    /// Trilinear filtering with 16 anisotropic samples, repeating the texture.
+    fn default() -> Self {
+        Self {
+            filter: Filter::Linear,
+            wrap: Wrap::Repeat,
+            max_anisotropy: 16,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Filter {
+    Nearest,
+    Linear,
+}
+
+/// What texture coordinates outside of `[0, 1]` sample.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Wrap {
+    Repeat,
+    MirroredRepeat,
+    ClampToEdge,
+}
@@ -1,20 +1,32 @@
 use std::collections::HashSet;
 
+use nalgebra::Vector3;
+
 use crate::assets::{Asset, MaterialId, MeshId, TextureId};
 use crate::rendering::vertex::Vertex;
+use crate::rendering::vulkan::allocator::Allocator;
 use crate::rendering::vulkan::memory::{IndexBuffer, VertexBuffer};
-use crate::rendering::vulkan::resource::DeviceResource;
+
+/// Normal of the side of the plane mesh its triangles face.
+const PLANE_NORMAL: [f32; 3] = [0.0, 0.0, -1.0];
 
 #[derive(Clone, Debug)]
 pub struct Mesh {
     pub id: MeshId,
     pub vertices: Vec<Vertex>,
-    pub indices: Vec<u16>,
+    pub indices: Indices,
     pub textures: HashSet<TextureId>,
     pub submeshes: Vec<Submesh>,
     pub bbox: BBox,
 }
 
+/// Vertex indices of a triangle list, 16 bit unless there are too many vertices for them.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Indices {
+    U16(Vec<u16>),
+    U32(Vec<u32>),
+}
+
 #[derive(Clone, Debug)]
 pub struct Submesh {
     pub id: MeshId,
@@ -23,19 +35,26 @@ pub struct Submesh {
     pub material_id: MaterialId,
 }
 
-/// Submesh loaded to the GPU (current implementation uses one index and vertex buffer per submesh).
+/// Mesh loaded to the GPU, its submeshes sharing one vertex and one index buffer.
 #[derive(Debug)]
-pub struct LoadedSubmesh {
-    pub id: MeshId,
-    pub material_id: MaterialId,
+pub struct LoadedMesh {
     pub vertex_buf: VertexBuffer,
     pub index_buf: IndexBuffer,
+    pub submeshes: Vec<LoadedSubmesh>,
 }
 
-impl DeviceResource for LoadedSubmesh {
-    fn destroy(&self, device: &erupt::DeviceLoader) {
-        self.vertex_buf.destroy(device);
-        self.index_buf.destroy(device);
+/// Range of the index buffer of a loaded mesh drawn with a material.
+#[derive(Debug)]
+pub struct LoadedSubmesh {
+    pub material_id: MaterialId,
+    pub first_index: u32,
+    pub index_count: u32,
+}
+
+impl LoadedMesh {
+    pub unsafe fn destroy(&self, device: &erupt::DeviceLoader, allocator: &mut Allocator) {
+        self.vertex_buf.destroy(device, allocator);
+        self.index_buf.destroy(device, allocator);
     }
 }
 
@@ -58,27 +77,31 @@ impl Mesh {
             Vertex {
                 pos: [-0.5, -0.5, 0.0],
                 uv: [0.0, 0.0, 0.0],
-                color: [0.0; 3],
+                color: [1.0; 3],
+                normal: PLANE_NORMAL,
             },
             Vertex {
                 pos: [-0.5, 0.5, 0.0],
                 uv: [0.0, 1.0, 0.0],
-                color: [0.0; 3],
+                color: [1.0; 3],
+                normal: PLANE_NORMAL,
             },
             Vertex {
                 pos: [0.5, 0.5, 0.0],
                 uv: [1.0, 1.0, 0.0],
-                color: [0.0; 3],
+                color: [1.0; 3],
+                normal: PLANE_NORMAL,
             },
             Vertex {
                 pos: [0.5, -0.5, 0.0],
                 uv: [1.0, 0.0, 0.0],
-                color: [0.0; 3],
+                color: [1.0; 3],
+                normal: PLANE_NORMAL,
             },
         ]
         .to_vec();
 
-        let indices = [1, 2, 0, 2, 3, 0].to_vec();
+        let indices = Indices::U16([1, 2, 0, 2, 3, 0].to_vec());
 
         let bbox = BBox {
             width: 1.0,
@@ -101,4 +124,229 @@ impl Mesh {
             }],
         }
     }
+
+    /// Creates a solid of revolution by sweeping `profile`, a list of `[radius, height]` points
+    /// from bottom to top, around the y axis. Start and end the profile at radius 0 for a closed
+    /// mesh.
+    pub fn new_lathe(
+        profile: &[[f32; 2]],
+        segments: u16,
+        color: [f32; 3],
+        material_id: MaterialId,
+    ) -> Mesh {
+        assert!(
+            profile.len() >= 2,
+            "a lathe profile needs at least two points"
+        );
+        assert!(segments >= 3, "a lathe needs at least three segments");
+
+        let ring_len = segments + 1;
+        let mut vertices = Vec::with_capacity(profile.len() * ring_len as usize);
+        for (i, &[radius, height]) in profile.iter().enumerate() {
+            let v = i as f32 / (profile.len() - 1) as f32;
+            let [normal_radius, normal_height] = lathe_normal(profile, i);
+            for j in 0..ring_len {
+                let u = j as f32 / segments as f32;
+                let angle = u * std::f32::consts::TAU;
+                let (sin, cos) = angle.sin_cos();
+                vertices.push(Vertex {
+                    pos: [radius * cos, height, radius * sin],
+                    uv: [u, v, 0.0],
+                    color,
+                    normal: [normal_radius * cos, normal_height, normal_radius * sin],
+                });
+            }
+        }
+
+        // Counter-clockwise when seen from outside, like the plane from its front
+        let mut indices = Vec::with_capacity((profile.len() - 1) * segments as usize * 6);
+        for i in 0..profile.len() as u16 - 1 {
+            for j in 0..segments {
+                let a = i * ring_len + j;
+                let b = a + 1;
+                let c = b + ring_len;
+                let d = a + ring_len;
+                indices.extend_from_slice(&[a, d, b, b, d, c]);
+            }
+        }
+
+        let max_radius = profile.iter().map(|p| p[0]).fold(0.0, f32::max);
+        let min_height = profile.iter().map(|p| p[1]).fold(f32::INFINITY, f32::min);
+        let max_height = profile
+            .iter()
+            .map(|p| p[1])
+            .fold(f32::NEG_INFINITY, f32::max);
+        let bbox = BBox {
+            width: max_radius * 2.0,
+            length: max_radius * 2.0,
+            height: max_height - min_height,
+        };
+
+        let n_indices = indices.len();
+        Mesh {
+            id: 0,
+            vertices,
+            indices: Indices::U16(indices),
+            bbox,
+            textures: HashSet::new(),
+            submeshes: vec![Submesh {
+                id: 0,
+                start_index: 0,
+                end_index: n_indices,
+                material_id,
+            }],
+        }
+    }
+
+    /// Gives the vertices without a normal the average normal of the triangles around them,
+    /// weighted by area. Triangles are expected to be counter-clockwise seen from their front.
+    pub fn compute_missing_normals(&mut self) {
+        let missing: Vec<bool> = self
+            .vertices
+            .iter()
+            .map(|vertex| vertex.normal == [0.0; 3])
+            .collect();
+        if !missing.contains(&true) {
+            return;
+        }
+
+        let mut normals = vec![Vector3::zeros(); self.vertices.len()];
+        for triangle in self.indices.triangles() {
+            let [a, b, c] = triangle.map(|i| Vector3::from(self.vertices[i].pos));
+            // Twice the area long
+            let face_normal = (b - a).cross(&(c - a));
+            for i in triangle {
+                normals[i] += face_normal;
+            }
+        }
+
+        for ((vertex, normal), missing) in self.vertices.iter_mut().zip(normals).zip(missing) {
+            if missing {
+                if let Some(normal) = normal.try_normalize(f32::EPSILON) {
+                    vertex.normal = normal.into();
+                }
+            }
+        }
+    }
+}
+
+impl Indices {
+    /// Indices of a mesh with `vertex_count` vertices, 32 bit only when 16 bits are not enough.
+    /// The largest 16 bit index is left out as it restarts strips.
+    pub fn new(indices: Vec<u32>, vertex_count: usize) -> Self {
+        if vertex_count > u16::MAX as usize {
+            Self::U32(indices)
+        } else {
+            Self::U16(indices.into_iter().map(|i| i as u16).collect())
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        match self {
+            Self::U16(indices) => indices.len(),
+            Self::U32(indices) => indices.len(),
+        }
+    }
+
+    pub fn get(&self, ix: usize) -> usize {
+        match self {
+            Self::U16(indices) => indices[ix] as usize,
+            Self::U32(indices) => indices[ix] as usize,
+        }
+    }
+
+    /// Vertex indices of each triangle.
+    pub fn triangles(&self) -> impl Iterator<Item = [usize; 3]> + '_ {
+        (0..self.len() / 3).map(move |t| [0, 1, 2].map(|corner| self.get(t * 3 + corner)))
+    }
+}
+
+/// Unit `[radius, height]` normal of the lathe surface at the point `i` of `profile`, averaging
+/// the segments on either side.
+fn lathe_normal(profile: &[[f32; 2]], i: usize) -> [f32; 2] {
+    let prev = profile[i.saturating_sub(1)];
+    let next = profile[(i + 1).min(profile.len() - 1)];
+    let (d_radius, d_height) = (next[0] - prev[0], next[1] - prev[1]);
+    let length = d_radius.hypot(d_height);
+    if length > 0.0 {
+        [d_height / length, -d_radius / length]
+    } else {
+        [0.0, 1.0]
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn face_normals(mesh: &Mesh) -> impl Iterator<Item = (Vector3<f32>, [usize; 3])> + '_ {
+        mesh.indices.triangles().map(|triangle| {
+            let [a, b, c] = triangle.map(|i| Vector3::from(mesh.vertices[i].pos));
+            ((b - a).cross(&(c - a)), triangle)
+        })
+    }
+
+    #[test]
+    fn test_lathe_normals_face_outward() {
+        let profile = [[0.0, 0.0], [0.5, 0.0], [0.5, 1.0], [0.0, 1.0]];
+        let mesh = Mesh::new_lathe(&profile, 8, [1.0; 3], 0);
+
+        for (face_normal, triangle) in face_normals(&mesh) {
+            for i in triangle {
+                let vertex = &mesh.vertices[i];
+                assert!((Vector3::from(vertex.normal).norm() - 1.0).abs() < 1e-5);
+                if face_normal.norm() > 1e-6 {
+                    assert!(face_normal.dot(&Vector3::from(vertex.normal)) > 0.0);
+                }
+            }
+
+            // Away from the axis
+            let center = triangle
+                .iter()
+                .map(|&i| Vector3::from(mesh.vertices[i].pos))
+                .sum::<Vector3<f32>>()
+                / 3.0;
+            let outward = center - Vector3::new(0.0, 0.5, 0.0);
+            if face_normal.norm() > 1e-6 {
+                assert!(face_normal.dot(&outward) > 0.0);
+            }
+        }
+    }
+
+    #[test]
+    fn test_compute_missing_normals() {
+        let mut plane = Mesh::new_plane(0, 0);
+        for vertex in &mut plane.vertices {
+            vertex.normal = [0.0; 3];
+        }
+        plane.vertices[0].normal = [1.0, 0.0, 0.0];
+
+        plane.compute_missing_normals();
+        assert_eq!(plane.vertices[0].normal, [1.0, 0.0, 0.0]);
+        for vertex in &plane.vertices[1..] {
+            assert_eq!(vertex.normal, PLANE_NORMAL);
+        }
+        for (face_normal, _) in face_normals(&plane) {
+            assert!(face_normal.dot(&Vector3::from(PLANE_NORMAL)) > 0.0);
+        }
+    }
+
+    #[test]
+    fn test_indices_width() {
+        let indices = vec![0, 1, 2, 2, 1, 3];
+        let short = Indices::new(indices.clone(), 4);
+        assert_eq!(short, Indices::U16(vec![0, 1, 2, 2, 1, 3]));
+        assert_eq!(
+            short.triangles().collect::<Vec<_>>(),
+            [[0, 1, 2], [2, 1, 3]]
+        );
+
+        let long = Indices::new(vec![70_000, 1, 2], 70_001);
+        assert_eq!(long, Indices::U32(vec![70_000, 1, 2]));
+        assert_eq!(long.get(0), 70_000);
+        assert_eq!(
+            Indices::new(indices, 65_536),
+            Indices::U32(vec![0, 1, 2, 2, 1, 3])
+        );
+    }
 }
@@ -1,3 +1,7 @@
+mod batch;
+#[cfg(test)]
+mod golden;
+pub mod light;
 pub mod material;
 pub mod mesh;
 pub mod projection;
@@ -8,6 +12,7 @@ pub mod texture;
 pub mod vertex;
 mod vulkan;
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub enum PrimitiveType {
     Points,
     Lines,
@@ -15,3 +20,10 @@ pub enum PrimitiveType {
     Triangles,
     TriangleStrip,
 }
+
+impl PrimitiveType {
+    /// Whether the primitives have an area that can be drawn as a wireframe.
+    pub fn is_polygon(self) -> bool {
+        matches!(self, Self::Triangles | Self::TriangleStrip)
+    }
+}
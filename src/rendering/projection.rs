@@ -1,4 +1,4 @@
-use nalgebra::{Matrix4, Perspective3};
+use nalgebra::{Matrix4, Orthographic3, Perspective3};
 
 #[derive(Debug)]
 pub enum Projection {
@@ -11,17 +11,23 @@ impl Projection {
         Self::Perspective(PerspectiveProjection::new(fov_deg, near, far))
     }
 
+    /// Orthographic projection showing at least a `width` by `height` region around the view
+    /// direction, whatever the aspect ratio of the viewport.
+    pub fn orthographic(width: f32, height: f32, near: f32, far: f32) -> Self {
+        Self::Orthographic(OrthographicProjection::new(width, height, near, far))
+    }
+
     pub fn matrix(&self) -> Matrix4<f32> {
         match self {
+            Self::Orthographic(p) => p.matrix(),
             Self::Perspective(p) => p.matrix(),
-            _ => unimplemented!(),
         }
     }
 
     pub fn set_viewport_dimensions(&mut self, width: f32, height: f32) {
         match self {
+            Self::Orthographic(p) => p.set_aspect_ratio(width / height),
             Self::Perspective(p) => p.set_aspect_ratio(width / height),
-            _ => unimplemented!(),
         }
     }
 }
@@ -32,6 +38,49 @@ pub struct OrthographicProjection {
     height: f32,
     near: f32,
     far: f32,
+    aspect: f32,
+}
+
+impl OrthographicProjection {
+    fn new(width: f32, height: f32, near: f32, far: f32) -> Self {
+        Self {
+            width,
+            height,
+            near,
+            far,
+            aspect: width / height,
+        }
+    }
+
+    pub fn set_aspect_ratio(&mut self, aspect: f32) {
+        self.aspect = aspect;
+    }
+
+    /// Width and height of the visible region, the fitted region widened along one axis to
+    /// match the viewport.
+    pub fn visible_size(&self) -> (f32, f32) {
+        if self.aspect > self.width / self.height {
+            (self.height * self.aspect, self.height)
+        } else {
+            (self.width, self.width / self.aspect)
+        }
+    }
+
+    pub fn matrix(&self) -> Matrix4<f32> {
+        let (width, height) = self.visible_size();
+        let mut p = Orthographic3::new(
+            -width / 2.0,
+            width / 2.0,
+            -height / 2.0,
+            height / 2.0,
+            self.near,
+            self.far,
+        )
+        .to_homogeneous();
+        p.m22 *= -1.0;
+
+        p
+    }
 }
 
 #[derive(Debug)]
@@ -64,3 +113,26 @@ impl PerspectiveProjection {
         p
     }
 }
+
+#[cfg(test)]
+mod test {
+    use nalgebra::Point3;
+
+    use super::*;
+
+    #[test]
+    fn test_orthographic_fit() {
+        let mut projection = OrthographicProjection::new(8.0, 4.0, 0.1, 10.0);
+        projection.set_aspect_ratio(1.0);
+        assert_eq!(projection.visible_size(), (8.0, 8.0));
+        projection.set_aspect_ratio(4.0);
+        assert_eq!(projection.visible_size(), (16.0, 4.0));
+
+        // The corners of the region stay on screen, the y axis points down like in Vulkan
+        let corner = projection
+            .matrix()
+            .transform_point(&Point3::new(4.0, 2.0, -5.0));
+        assert!((corner.x - 0.5).abs() < 1e-6);
+        assert!((corner.y + 1.0).abs() < 1e-6);
+    }
+}
@@ -4,6 +4,7 @@ use std::{collections::HashMap, ffi::c_void};
 
 use erupt::utils::surface;
 use erupt::{vk, DeviceLoader};
+use image::RgbaImage;
 use smallvec::SmallVec;
 use winit::dpi::PhysicalSize;
 use winit::window::Window;
@@ -11,24 +12,28 @@ use winit::window::Window;
 use crate::assets::{MaterialId, ShaderId};
 use crate::{
     assets::{Asset, Assets, MeshId, TextureId},
-    logging::{debug, error},
+    logging::{debug, error, warn},
+    object::Object,
     rendering::{
-        mesh::LoadedSubmesh,
+        batch::{self, Batch},
+        light::LightsUniform,
+        material::SamplerSettings,
+        mesh::{LoadedMesh, LoadedSubmesh},
         spatial::Spatial,
         texture::{self, LoadedTexture, Texture},
-        vertex::Vertex,
+        vertex::{Instance, Vertex},
         vulkan::{
-            context::Context,
-            descriptor as ds, g,
-            memory::{self, IndexBuffer, VertexBuffer},
-            resource::DeviceResource,
-            swapchain::Swapchain,
+            allocator::Allocation, context::Context, descriptor as ds, g, memory,
+            offscreen::OffscreenTarget, resource::DeviceResource, swapchain::Swapchain,
         },
+        PrimitiveType,
     },
     scenes::Scene,
 };
 
 const FRAMES_IN_FLIGHT: usize = 2;
+/// Instances the instance buffers have room for at first, enough for the board and pieces.
+const INITIAL_INSTANCE_CAPACITY: usize = 128;
 
 pub struct Renderer {
     ctx: Context,
@@ -37,64 +42,156 @@ pub struct Renderer {
     frame_number: usize,
 
     textures: HashMap<TextureId, LoadedTexture>,
-    texture_descriptor_sets: HashMap<TextureId, vk::DescriptorSet>,
-    sampler: vk::Sampler,
+    /// Sets binding a texture with a sampler, by the texture and the sampler settings.
+    texture_descriptor_sets: HashMap<(TextureId, SamplerSettings), vk::DescriptorSet>,
+    samplers: HashMap<SamplerSettings, vk::Sampler>,
 
-    meshes: HashMap<MeshId, LoadedSubmesh>,
+    meshes: HashMap<MeshId, LoadedMesh>,
 
     surface_size: vk::Extent2D,
     new_surface_size: Option<vk::Extent2D>,
     new_surface: Option<vk::SurfaceKHR>,
 
-    descriptor_pool: vk::DescriptorPool,
+    /// Pools the descriptor sets are allocated from, the last one taking new texture sets.
+    descriptor_pools: Vec<vk::DescriptorPool>,
+    /// Texture sets allocated from the last pool. Vulkan 1.0 doesn't report a full pool, so
+    /// the next one is created once this reaches `ds::TEXTURE_SETS_PER_POOL`.
+    pool_texture_sets: u32,
     texture_descriptor_set_layout: vk::DescriptorSetLayout,
+    lights_descriptor_set_layout: vk::DescriptorSetLayout,
 
     render_pass: vk::RenderPass,
-    pipelines: HashMap<MaterialId, Pipeline>,
+    /// Render pass leaving the image ready to be copied out, for rendering offscreen.
+    capture_render_pass: vk::RenderPass,
+    offscreen: Option<OffscreenTarget>,
+    pipelines: HashMap<PipelineKey, Pipeline>,
+    wireframe: bool,
+}
+
+/// What a pipeline is created for. Pipelines are created the first time a key is drawn with.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+struct PipelineKey {
+    material_id: MaterialId,
+    primitive_type: PrimitiveType,
+    wireframe: bool,
+}
+
+/// What objects are batched by, drawing each batch with a single instanced draw call.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+struct DrawKey {
+    pipeline: PipelineKey,
+    mesh_id: MeshId,
+    /// Index of the submesh in the mesh.
+    submesh: usize,
 }
 
-#[derive(Clone)]
 struct Frame {
     image_available_semaphore: vk::Semaphore,
     render_finished_semaphore: vk::Semaphore,
     in_flight_fence: vk::Fence,
     cmd_buf: vk::CommandBuffer,
+    lights_buf: vk::Buffer,
+    lights_alloc: Allocation,
+    lights_descriptor_set: vk::DescriptorSet,
+    /// Model matrices of the objects drawn in the frame.
+    instance_buf: vk::Buffer,
+    instance_alloc: Allocation,
+    instance_capacity: usize,
 }
 
 impl Renderer {
     pub fn new(app_name: &str, window: &Window) -> Self {
-        let mut ctx = Context::new(window, &app_name, "No Engine");
+        Self::with_context(Context::new(window, app_name, "No Engine"))
+    }
+
+    /// Creates a renderer without a window that can only render with
+    /// [`Renderer::render_to_image`].
+    pub fn headless(app_name: &str) -> Self {
+        Self::with_context(Context::headless(app_name, "No Engine"))
+    }
+
+    fn with_context(mut ctx: Context) -> Self {
         unsafe {
+            let descriptor_pool = ds::create_descriptor_pool(
+                &ctx.device,
+                FRAMES_IN_FLIGHT as u32,
+                ds::TEXTURE_SETS_PER_POOL,
+            );
+
+            let texture_descriptor_set_layout = ds::descriptor_set_layout_1_texture(&ctx.device, 1);
+            let lights_descriptor_set_layout =
+                ds::descriptor_set_layout_1_uniform(&ctx.device, 0, vk::ShaderStageFlags::FRAGMENT);
+
             let cmd_bufs =
                 memory::create_command_buffers(&ctx.device, ctx.cmd_pool, FRAMES_IN_FLIGHT);
-
-            let frames_in_flight = (0..FRAMES_IN_FLIGHT)
-                .map(|n| Frame {
-                    image_available_semaphore: ctx.sync_pool.semaphore(&ctx.device),
-                    render_finished_semaphore: ctx.sync_pool.semaphore(&ctx.device),
-                    in_flight_fence: ctx.sync_pool.fence(&ctx.device, true),
-                    cmd_buf: cmd_bufs[n],
+            let lights_bufs: SmallVec<[_; FRAMES_IN_FLIGHT]> = (0..FRAMES_IN_FLIGHT)
+                .map(|_| {
+                    create_frame_buffer(
+                        &mut ctx,
+                        size_of::<LightsUniform>(),
+                        vk::BufferUsageFlags::UNIFORM_BUFFER,
+                    )
                 })
                 .collect();
+            let buffers: SmallVec<[vk::Buffer; FRAMES_IN_FLIGHT]> =
+                lights_bufs.iter().map(|(buf, _)| *buf).collect();
+            let lights_descriptor_sets = ds::uniform_descriptor_sets(
+                &ctx.device,
+                descriptor_pool,
+                lights_descriptor_set_layout,
+                0,
+                &buffers,
+                size_of::<LightsUniform>(),
+            );
 
-            let render_pass = create_render_pass(&ctx);
-
-            let descriptor_pool = ds::create_descriptor_pool(&ctx.device, FRAMES_IN_FLIGHT);
+            let frames_in_flight = lights_bufs
+                .into_iter()
+                .enumerate()
+                .map(|(n, (lights_buf, lights_alloc))| {
+                    let (instance_buf, instance_alloc) = create_frame_buffer(
+                        &mut ctx,
+                        size_of::<Instance>() * INITIAL_INSTANCE_CAPACITY,
+                        vk::BufferUsageFlags::VERTEX_BUFFER,
+                    );
+                    Frame {
+                        image_available_semaphore: ctx.sync_pool.semaphore(&ctx.device),
+                        render_finished_semaphore: ctx.sync_pool.semaphore(&ctx.device),
+                        in_flight_fence: ctx.sync_pool.fence(&ctx.device, true),
+                        cmd_buf: cmd_bufs[n],
+                        lights_buf,
+                        lights_alloc,
+                        lights_descriptor_set: lights_descriptor_sets[n],
+                        instance_buf,
+                        instance_alloc,
+                        instance_capacity: INITIAL_INSTANCE_CAPACITY,
+                    }
+                })
+                .collect();
 
-            let texture_descriptor_set_layout = ds::descriptor_set_layout_1_texture(&ctx.device, 1);
-            let sampler = texture::create_sampler(&ctx);
+            let final_layout = match ctx.swapchain {
+                Some(_) => vk::ImageLayout::PRESENT_SRC_KHR,
+                None => vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+            };
+            let render_pass = create_render_pass(&ctx, final_layout);
+            let capture_render_pass =
+                create_render_pass(&ctx, vk::ImageLayout::TRANSFER_SRC_OPTIMAL);
 
             Self {
                 ctx,
                 frames_in_flight,
                 frame_number: 0,
-                descriptor_pool,
+                descriptor_pools: vec![descriptor_pool],
+                pool_texture_sets: 0,
                 texture_descriptor_set_layout,
+                lights_descriptor_set_layout,
                 texture_descriptor_sets: HashMap::new(),
                 meshes: HashMap::new(),
                 pipelines: HashMap::new(),
+                wireframe: false,
                 render_pass,
-                sampler,
+                capture_render_pass,
+                offscreen: None,
+                samplers: HashMap::new(),
                 textures: HashMap::new(),
                 surface_size: vk::Extent2D::default(),
                 new_surface: None,
@@ -113,6 +210,8 @@ impl Renderer {
             self.surface_size.width as f32,
             self.surface_size.height as f32,
         );
+        self.create_pipelines(scene.objects(), assets);
+        let batches = unsafe { self.prepare_frame(scene, assets) };
 
         if let Some(swapchain) = &self.ctx.swapchain {
             let current_frame = self.current_frame();
@@ -125,70 +224,14 @@ impl Renderer {
                     framebuffers[image_index as usize],
                     swapchain.image_dimensions(),
                 );
-            }
-
-            let objects = scene.objects();
-            let camera = scene.active_camera();
-            for o in objects {
-                let mesh = assets
-                    .mesh(o.mesh_id)
-                    .expect("failed to fetch mesh that is supposed to be loaded");
-                unsafe {
-                    for sm in &mesh.submeshes {
-                        let mvp = Spatial(camera.matrix() * o.transform.matrix());
-
-                        let device = &self.ctx.device;
-                        let cmd_buf = self.current_frame().cmd_buf;
-
-                        let pipeline = &self.pipelines[&sm.material_id];
-                        self.ctx.device.cmd_bind_pipeline(
-                            cmd_buf,
-                            vk::PipelineBindPoint::GRAPHICS,
-                            pipeline.handle,
-                        );
-
-                        let mesh = &self.meshes[&sm.id];
-                        device.cmd_bind_vertex_buffers(cmd_buf, 0, &[mesh.vertex_buf.handle], &[0]);
-                        device.cmd_bind_index_buffer(
-                            cmd_buf,
-                            mesh.index_buf.handle,
-                            0,
-                            vk::IndexType::UINT16,
-                        );
-
-                        device.cmd_push_constants(
-                            cmd_buf,
-                            pipeline.layout,
-                            vk::ShaderStageFlags::VERTEX,
-                            0,
-                            size_of::<Spatial>() as _,
-                            &mvp as *const Spatial as *const c_void,
-                        );
-
-                        let material = assets.material(sm.material_id).unwrap();
-                        let texture_descriptor_set =
-                            self.texture_descriptor_sets[&material.texture_id];
-                        device.cmd_bind_descriptor_sets(
-                            cmd_buf,
-                            vk::PipelineBindPoint::GRAPHICS,
-                            pipeline.layout,
-                            0,
-                            &[texture_descriptor_set],
-                            &[],
-                        );
-                        device.cmd_draw_indexed(
-                            cmd_buf,
-                            mesh.index_buf.index_count as _,
-                            1,
-                            0,
-                            0,
-                            0,
-                        );
-                    }
-                }
-            }
+                self.record_objects(
+                    current_frame.cmd_buf,
+                    current_frame,
+                    &batches,
+                    scene,
+                    assets,
+                );
 
-            unsafe {
                 g::end_draw(
                     &self.ctx.device,
                     self.ctx.graphics_queue,
@@ -210,6 +253,209 @@ impl Renderer {
         self.finish_frame()
     }
 
+    /// Renders `scene` into a `width` by `height` image and reads it back.
+    pub fn render_to_image(
+        &mut self,
+        scene: &mut impl Scene,
+        assets: &Assets,
+        width: u32,
+        height: u32,
+    ) -> RgbaImage {
+        let extent = vk::Extent2D { width, height };
+        self.ensure_offscreen_target(extent);
+
+        scene
+            .active_camera_mut()
+            .set_viewport_dimensions(width as f32, height as f32);
+        self.create_pipelines(scene.objects(), assets);
+        let batches = unsafe {
+            // The frame's buffers may still be read by a frame in flight
+            self.ctx
+                .device
+                .queue_wait_idle(self.ctx.graphics_queue)
+                .unwrap();
+            self.prepare_frame(scene, assets)
+        };
+
+        let device = &self.ctx.device;
+        let target = self.offscreen.as_ref().unwrap();
+        let frame = self.current_frame();
+        unsafe {
+            let (cmd_buf, cmd_pool) =
+                g::begin_once_commands(device, self.ctx.physical_device.graphics_queue_family);
+            g::begin_render_pass(
+                device,
+                cmd_buf,
+                self.capture_render_pass,
+                target.framebuffer(),
+                &extent,
+            );
+            self.record_objects(cmd_buf, frame, &batches, scene, assets);
+            device.cmd_end_render_pass(cmd_buf);
+            target.cmd_copy_to_readback(device, cmd_buf);
+            g::end_once_commands(device, cmd_pool, cmd_buf, self.ctx.graphics_queue);
+
+            target.read()
+        }
+    }
+
+    /// Renders `scene` the size of the window, `None` while there is no swapchain.
+    pub fn screenshot(&mut self, scene: &mut impl Scene, assets: &Assets) -> Option<RgbaImage> {
+        let extent = *self.ctx.swapchain.as_ref()?.image_dimensions();
+        Some(self.render_to_image(scene, assets, extent.width, extent.height))
+    }
+
+    fn ensure_offscreen_target(&mut self, extent: vk::Extent2D) {
+        if let Some(target) = &self.offscreen {
+            if target.extent().width == extent.width && target.extent().height == extent.height {
+                return;
+            }
+            unsafe {
+                self.ctx.device.device_wait_idle().unwrap();
+                target.destroy(&self.ctx.device, &mut self.ctx.allocator);
+            }
+        }
+
+        self.offscreen = Some(unsafe {
+            OffscreenTarget::new(
+                &self.ctx.device,
+                &self.ctx.physical_device,
+                &mut self.ctx.allocator,
+                self.capture_render_pass,
+                extent,
+            )
+        });
+    }
+
+    /// Uploads the lights and object transforms of `scene` to the current frame and returns the
+    /// batches to draw them in.
+    unsafe fn prepare_frame(&mut self, scene: &impl Scene, assets: &Assets) -> Vec<Batch<DrawKey>> {
+        let mut items = Vec::with_capacity(scene.objects().len());
+        for o in scene.objects() {
+            let mesh = assets
+                .mesh(o.mesh_id)
+                .expect("failed to fetch mesh that is supposed to be loaded");
+            let model = o.transform.matrix();
+            for (ix, sm) in mesh.submeshes.iter().enumerate() {
+                let key = DrawKey {
+                    pipeline: self.pipeline_key(o, sm.material_id),
+                    mesh_id: o.mesh_id,
+                    submesh: ix,
+                };
+                items.push((key, Instance { model }));
+            }
+        }
+        let (batches, instances) = batch::batch_instances(items);
+
+        self.ensure_instance_capacity(instances.len());
+        let frame = self.current_frame();
+        frame.instance_alloc.write(
+            instances.as_ptr() as *const c_void,
+            size_of::<Instance>() * instances.len(),
+        );
+
+        let lights = LightsUniform::new(scene.lighting(), &scene.active_camera().position);
+        frame.lights_alloc.write(
+            &lights as *const LightsUniform as *const c_void,
+            size_of::<LightsUniform>(),
+        );
+
+        batches
+    }
+
+    /// Grows the instance buffer of the current frame to fit `count` instances.
+    unsafe fn ensure_instance_capacity(&mut self, count: usize) {
+        let frame = &mut self.frames_in_flight[self.frame_number];
+        if count <= frame.instance_capacity {
+            return;
+        }
+
+        self.ctx.device.destroy_buffer(frame.instance_buf, None);
+        self.ctx.allocator.free(&frame.instance_alloc);
+
+        let capacity = count.next_power_of_two();
+        let (instance_buf, instance_alloc) = create_frame_buffer(
+            &mut self.ctx,
+            size_of::<Instance>() * capacity,
+            vk::BufferUsageFlags::VERTEX_BUFFER,
+        );
+        frame.instance_buf = instance_buf;
+        frame.instance_alloc = instance_alloc;
+        frame.instance_capacity = capacity;
+    }
+
+    /// Records drawing `batches` of the objects of `scene`, prepared for `frame`, into the
+    /// render pass begun on `cmd_buf`.
+    unsafe fn record_objects(
+        &self,
+        cmd_buf: vk::CommandBuffer,
+        frame: &Frame,
+        batches: &[Batch<DrawKey>],
+        scene: &impl Scene,
+        assets: &Assets,
+    ) {
+        let device = &self.ctx.device;
+        let spatial = Spatial {
+            view_projection: scene.active_camera().matrix(),
+        };
+
+        let mut bound_pipeline = None;
+        let mut bound_mesh = None;
+        for batch in batches {
+            let pipeline = &self.pipelines[&batch.key.pipeline];
+            if bound_pipeline != Some(pipeline.handle) {
+                device.cmd_bind_pipeline(cmd_buf, vk::PipelineBindPoint::GRAPHICS, pipeline.handle);
+                device.cmd_push_constants(
+                    cmd_buf,
+                    pipeline.layout,
+                    vk::ShaderStageFlags::VERTEX,
+                    0,
+                    size_of::<Spatial>() as _,
+                    &spatial as *const Spatial as *const c_void,
+                );
+                bound_pipeline = Some(pipeline.handle);
+            }
+
+            let mesh = &self.meshes[&batch.key.mesh_id];
+            if bound_mesh != Some(batch.key.mesh_id) {
+                device.cmd_bind_vertex_buffers(
+                    cmd_buf,
+                    0,
+                    &[mesh.vertex_buf.handle, frame.instance_buf],
+                    &[0, 0],
+                );
+                device.cmd_bind_index_buffer(
+                    cmd_buf,
+                    mesh.index_buf.handle,
+                    0,
+                    mesh.index_buf.index_type,
+                );
+                bound_mesh = Some(batch.key.mesh_id);
+            }
+
+            let material = assets.material(batch.key.pipeline.material_id).unwrap();
+            let texture_descriptor_set =
+                self.texture_descriptor_sets[&(material.texture_id, material.sampler)];
+            device.cmd_bind_descriptor_sets(
+                cmd_buf,
+                vk::PipelineBindPoint::GRAPHICS,
+                pipeline.layout,
+                0,
+                &[texture_descriptor_set, frame.lights_descriptor_set],
+                &[],
+            );
+            let submesh = &mesh.submeshes[batch.key.submesh];
+            device.cmd_draw_indexed(
+                cmd_buf,
+                submesh.index_count,
+                batch.instance_count,
+                submesh.first_index,
+                0,
+                batch.first_instance,
+            );
+        }
+    }
+
     pub fn invalidate_surface_size(&mut self, new_size: PhysicalSize<u32>) {
         let PhysicalSize { width, height } = new_size;
         self.new_surface_size = Some(vk::Extent2D { width, height });
@@ -224,12 +470,13 @@ impl Renderer {
     }
 
     fn next_swapchain_image(&mut self) -> Option<u32> {
-        let current_frame = self.current_frame().clone();
+        let in_flight_fence = self.current_frame().in_flight_fence;
+        let image_available_semaphore = self.current_frame().image_available_semaphore;
         if let Some(swapchain) = &mut self.ctx.swapchain {
             let maybe_image = swapchain.acquire_image(
                 &self.ctx.device,
-                current_frame.in_flight_fence,
-                current_frame.image_available_semaphore,
+                in_flight_fence,
+                image_available_semaphore,
             );
 
             if maybe_image.is_none() || self.new_surface_size.is_some() {
@@ -251,6 +498,7 @@ impl Renderer {
                         &self.ctx.instance,
                         &self.ctx.device,
                         &self.ctx.physical_device,
+                        &mut self.ctx.allocator,
                         self.new_surface,
                         &self.surface_size,
                     );
@@ -261,8 +509,8 @@ impl Renderer {
 
                 swapchain.acquire_image(
                     &self.ctx.device,
-                    current_frame.in_flight_fence,
-                    current_frame.image_available_semaphore,
+                    in_flight_fence,
+                    image_available_semaphore,
                 )
             } else {
                 maybe_image
@@ -272,6 +520,87 @@ impl Renderer {
         }
     }
 
+    pub fn wireframe(&self) -> bool {
+        self.wireframe
+    }
+
+    /// Draws polygons as their outlines, if the device supports it.
+    pub fn set_wireframe(&mut self, enabled: bool) {
+        if enabled && self.ctx.physical_device.features.fill_mode_non_solid == 0 {
+            warn!("Wireframe rendering is not supported by the device");
+            return;
+        }
+        self.wireframe = enabled;
+    }
+
+    fn pipeline_key(&self, object: &Object, material_id: MaterialId) -> PipelineKey {
+        PipelineKey {
+            material_id,
+            primitive_type: object.primitive_type,
+            wireframe: self.wireframe && object.primitive_type.is_polygon(),
+        }
+    }
+
+    fn create_pipelines(&mut self, objects: &[Object], assets: &Assets) {
+        for o in objects {
+            let mesh = assets
+                .mesh(o.mesh_id)
+                .expect("failed to fetch mesh that is supposed to be loaded");
+            for sm in &mesh.submeshes {
+                let key = self.pipeline_key(o, sm.material_id);
+                self.ensure_pipeline(key, assets);
+            }
+        }
+    }
+
+    fn ensure_pipeline(&mut self, key: PipelineKey, assets: &Assets) {
+        let Entry::Vacant(e) = self.pipelines.entry(key) else {
+            return;
+        };
+
+        let material = assets.material(key.material_id).unwrap();
+        let vertex_shader = assets
+            .shader(material.vertex_shader_id)
+            .unwrap()
+            .initialize(&self.ctx.device)
+            .map_err(|e| {
+                error!("{e}");
+            })
+            .expect("fix shader compilation errors");
+        let fragment_shader = assets
+            .shader(material.fragment_shader_id)
+            .unwrap()
+            .initialize(&self.ctx.device)
+            .map_err(|e| {
+                error!("{e}");
+            })
+            .expect("fix shader compilation errors");
+        let shader_stages = [vertex_shader.stage_info(), fragment_shader.stage_info()];
+
+        let polygon_mode = if key.wireframe {
+            vk::PolygonMode::LINE
+        } else {
+            vk::PolygonMode::FILL
+        };
+        unsafe {
+            let pipeline = create_pipeline(
+                &self.ctx.device,
+                self.render_pass,
+                &shader_stages,
+                topology(key.primitive_type),
+                polygon_mode,
+                &[
+                    self.texture_descriptor_set_layout,
+                    self.lights_descriptor_set_layout,
+                ],
+            );
+            e.insert(pipeline);
+
+            vertex_shader.destroy(&self.ctx.device);
+            fragment_shader.destroy(&self.ctx.device);
+        }
+    }
+
     fn finish_frame(&mut self) {
         self.frame_number = (self.frame_number + 1) % FRAMES_IN_FLIGHT;
     }
@@ -280,125 +609,95 @@ impl Renderer {
         &self.frames_in_flight[self.frame_number]
     }
 
+    /// Uploads the textures and meshes of `assets` not uploaded yet, all in one submission that
+    /// later draws wait for.
     pub fn load_assets(&mut self, assets: &Assets) {
         self.use_textures(assets.textures());
         self.use_meshes(assets);
+        unsafe {
+            self.ctx.uploader.submit(&self.ctx.device);
+        }
     }
 
     fn use_textures<'a>(&mut self, textures: impl Iterator<Item = &'a Texture>) {
         unsafe {
             for t in textures {
                 if !self.textures.contains_key(&t.id()) {
-                    let gpu_texture = t.init(&self.ctx);
+                    let gpu_texture = t.init(&mut self.ctx);
                     self.textures.insert(t.id(), gpu_texture);
                 }
             }
-
-            let textures: SmallVec<[&LoadedTexture; 16]> = self.textures.values().collect();
-            self.texture_descriptor_sets = ds::texture_descriptor_sets(
-                &self.ctx.device,
-                self.descriptor_pool,
-                self.texture_descriptor_set_layout,
-                1,
-                &textures,
-                self.sampler,
-            );
         }
     }
 
     fn use_meshes<'a>(&mut self, assets: &Assets) {
-        let copy_queue = self.ctx.graphics_queue;
-        let copy_queue_family = self.ctx.physical_device.graphics_queue_family;
-
-        let vertex_binding_descs = [Vertex::binding_desc()];
-        let vertex_attribute_descs = Vertex::attribute_descs();
-
         unsafe {
             for mesh in assets.meshes() {
-                for submesh in &mesh.submeshes {
-                    let (vertex_buf, vertex_mem) = memory::create_vertex_buffer(
-                        &self.ctx,
-                        &mesh.vertices,
-                        copy_queue_family,
-                        copy_queue,
-                    );
-                    let indices = &mesh.indices[submesh.start_index..submesh.end_index];
-                    let (index_buf, index_mem) = memory::create_index_buffer(
-                        &self.ctx,
-                        indices,
-                        copy_queue_family,
-                        copy_queue,
-                    );
-
-                    let vertex_buf = VertexBuffer {
-                        handle: vertex_buf,
-                        memory: vertex_mem,
-                    };
-
-                    let index_buf = IndexBuffer {
-                        handle: index_buf,
-                        memory: index_mem,
-                        index_count: indices.len(),
-                    };
-
-                    let gpu_mesh = LoadedSubmesh {
-                        id: submesh.id,
-                        material_id: submesh.material_id,
-                        index_buf,
+                if !self.meshes.contains_key(&mesh.id) {
+                    let vertex_buf = memory::create_vertex_buffer(&mut self.ctx, &mesh.vertices);
+                    let index_buf = memory::create_index_buffer(&mut self.ctx, &mesh.indices);
+                    let submeshes = mesh
+                        .submeshes
+                        .iter()
+                        .map(|sm| LoadedSubmesh {
+                            material_id: sm.material_id,
+                            first_index: sm.start_index as u32,
+                            index_count: (sm.end_index - sm.start_index) as u32,
+                        })
+                        .collect();
+
+                    let gpu_mesh = LoadedMesh {
                         vertex_buf,
+                        index_buf,
+                        submeshes,
                     };
+                    self.meshes.insert(mesh.id, gpu_mesh);
+                }
 
-                    match self.meshes.entry(submesh.id) {
-                        Entry::Vacant(e) => {
-                            e.insert(gpu_mesh);
-                        }
-                        _ => {}
+                for submesh in &mesh.submeshes {
+                    // Most objects are drawn filled, so have those pipelines ready up front
+                    let key = PipelineKey {
+                        material_id: submesh.material_id,
+                        primitive_type: PrimitiveType::Triangles,
+                        wireframe: false,
                     };
-
-                    match self.pipelines.entry(submesh.material_id) {
-                        Entry::Vacant(e) => {
-                            let material = assets.material(submesh.material_id).unwrap();
-                            let vertex_shader = assets
-                                .shader(material.vertex_shader_id)
-                                .unwrap()
-                                .initialize(&self.ctx.device)
-                                .map_err(|e| {
-                                    error!("{e}");
-                                })
-                                .expect("fix shader compilation errors");
-                            let fragment_shader = assets
-                                .shader(material.fragment_shader_id)
-                                .unwrap()
-                                .initialize(&self.ctx.device)
-                                .map_err(|e| {
-                                    error!("{e}");
-                                })
-                                .expect("fix shader compilation errors");
-                            let shader_stages =
-                                [vertex_shader.stage_info(), fragment_shader.stage_info()];
-
-                            let pipeline = create_pipeline(
-                                &self.ctx.device,
-                                self.render_pass,
-                                &shader_stages,
-                                &vertex_binding_descs,
-                                &vertex_attribute_descs,
-                                vk::PrimitiveTopology::TRIANGLE_LIST,
-                                &[self.texture_descriptor_set_layout],
-                            );
-
-                            e.insert(pipeline);
-
-                            vertex_shader.destroy(&self.ctx.device);
-                            fragment_shader.destroy(&self.ctx.device);
-                        }
-                        _ => {}
-                    }
+                    self.ensure_pipeline(key, assets);
+                    self.ensure_texture_descriptor_set(submesh.material_id, assets);
                 }
             }
         }
     }
 
+    /// Creates the set binding the texture of a material with a sampler of its settings, unless
+    /// there is one for the same texture and settings.
+    unsafe fn ensure_texture_descriptor_set(&mut self, material_id: MaterialId, assets: &Assets) {
+        let material = assets.material(material_id).unwrap();
+        let key = (material.texture_id, material.sampler);
+        let Entry::Vacant(e) = self.texture_descriptor_sets.entry(key) else {
+            return;
+        };
+
+        let sampler = *self
+            .samplers
+            .entry(material.sampler)
+            .or_insert_with(|| texture::create_sampler(&self.ctx, &material.sampler));
+        if self.pool_texture_sets == ds::TEXTURE_SETS_PER_POOL {
+            let pool = ds::create_descriptor_pool(&self.ctx.device, 0, ds::TEXTURE_SETS_PER_POOL);
+            self.descriptor_pools.push(pool);
+            self.pool_texture_sets = 0;
+        }
+        let set = ds::texture_descriptor_set(
+            &self.ctx.device,
+            *self.descriptor_pools.last().unwrap(),
+            self.texture_descriptor_set_layout,
+            1,
+            &self.textures[&material.texture_id],
+            sampler,
+        );
+        self.pool_texture_sets += 1;
+        e.insert(set);
+    }
+
     pub fn resume(&mut self) {
         debug!("Recreating swapchain after start");
         self.surface_size = self
@@ -411,6 +710,7 @@ impl Renderer {
         self.ctx.swapchain = Some(Swapchain::new(
             &self.ctx.device,
             &self.ctx.physical_device,
+            &mut self.ctx.allocator,
             self.ctx.graphics_queue,
             surface,
             &self.surface_size,
@@ -422,7 +722,11 @@ impl Renderer {
         let mut swapchain = self.ctx.swapchain.take();
         if let Some(swapchain) = &mut swapchain {
             unsafe {
-                swapchain.destroy(&self.ctx.device, &self.ctx.instance);
+                swapchain.destroy(
+                    &self.ctx.device,
+                    &self.ctx.instance,
+                    &mut self.ctx.allocator,
+                );
             }
         }
     }
@@ -436,26 +740,44 @@ impl Drop for Renderer {
             self.ctx.device.device_wait_idle().unwrap();
 
             for m in self.meshes.values() {
-                m.destroy(&self.ctx.device)
+                m.destroy(&self.ctx.device, &mut self.ctx.allocator);
             }
 
             for t in self.textures.values() {
-                t.destroy(&self.ctx.device);
+                t.destroy(&self.ctx.device, &mut self.ctx.allocator);
+            }
+            for &sampler in self.samplers.values() {
+                self.ctx.device.destroy_sampler(sampler, None);
+            }
+
+            for f in &self.frames_in_flight {
+                self.ctx.device.destroy_buffer(f.lights_buf, None);
+                self.ctx.allocator.free(&f.lights_alloc);
+                self.ctx.device.destroy_buffer(f.instance_buf, None);
+                self.ctx.allocator.free(&f.instance_alloc);
             }
-            self.ctx.device.destroy_sampler(self.sampler, None);
 
             self.ctx
                 .device
                 .destroy_descriptor_set_layout(self.texture_descriptor_set_layout, None);
             self.ctx
                 .device
-                .destroy_descriptor_pool(self.descriptor_pool, None);
+                .destroy_descriptor_set_layout(self.lights_descriptor_set_layout, None);
+            for &pool in &self.descriptor_pools {
+                self.ctx.device.destroy_descriptor_pool(pool, None);
+            }
 
             for (_, p) in &self.pipelines {
                 p.destroy(&self.ctx.device);
             }
 
+            if let Some(target) = &self.offscreen {
+                target.destroy(&self.ctx.device, &mut self.ctx.allocator);
+            }
             self.ctx.device.destroy_render_pass(self.render_pass, None);
+            self.ctx
+                .device
+                .destroy_render_pass(self.capture_render_pass, None);
         }
     }
 }
@@ -483,7 +805,7 @@ impl DeviceResource for Pipeline {
     }
 }
 
-unsafe fn create_render_pass(ctx: &Context) -> vk::RenderPass {
+unsafe fn create_render_pass(ctx: &Context, final_layout: vk::ImageLayout) -> vk::RenderPass {
     let color_attachment = vk::AttachmentDescriptionBuilder::new()
         .format(ctx.physical_device.surface_format.format)
         .samples(vk::SampleCountFlagBits::_1)
@@ -492,7 +814,7 @@ unsafe fn create_render_pass(ctx: &Context) -> vk::RenderPass {
         .stencil_load_op(vk::AttachmentLoadOp::DONT_CARE)
         .stencil_store_op(vk::AttachmentStoreOp::DONT_CARE)
         .initial_layout(vk::ImageLayout::UNDEFINED)
-        .final_layout(vk::ImageLayout::PRESENT_SRC_KHR);
+        .final_layout(final_layout);
 
     let depth_attachment = vk::AttachmentDescriptionBuilder::new()
         .format(ctx.physical_device.depth_format)
@@ -517,7 +839,7 @@ unsafe fn create_render_pass(ctx: &Context) -> vk::RenderPass {
         .color_attachments(&color_attachment_refs)
         .depth_stencil_attachment(&depth_attachment_ref)];
 
-    let dependencies = vec![vk::SubpassDependencyBuilder::new()
+    let mut dependencies = vec![vk::SubpassDependencyBuilder::new()
         .src_subpass(vk::SUBPASS_EXTERNAL)
         .dst_subpass(0)
         .src_stage_mask(
@@ -533,6 +855,18 @@ unsafe fn create_render_pass(ctx: &Context) -> vk::RenderPass {
             vk::AccessFlags::COLOR_ATTACHMENT_WRITE
                 | vk::AccessFlags::DEPTH_STENCIL_ATTACHMENT_WRITE,
         )];
+    if final_layout == vk::ImageLayout::TRANSFER_SRC_OPTIMAL {
+        // The image is copied out right after the pass
+        dependencies.push(
+            vk::SubpassDependencyBuilder::new()
+                .src_subpass(0)
+                .dst_subpass(vk::SUBPASS_EXTERNAL)
+                .src_stage_mask(vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT)
+                .src_access_mask(vk::AccessFlags::COLOR_ATTACHMENT_WRITE)
+                .dst_stage_mask(vk::PipelineStageFlags::TRANSFER)
+                .dst_access_mask(vk::AccessFlags::TRANSFER_READ),
+        );
+    }
 
     let render_pass_info = vk::RenderPassCreateInfoBuilder::new()
         .attachments(&attachments)
@@ -544,15 +878,41 @@ unsafe fn create_render_pass(ctx: &Context) -> vk::RenderPass {
         .unwrap()
 }
 
+/// Creates a buffer written by the host every frame.
+unsafe fn create_frame_buffer(
+    ctx: &mut Context,
+    size: usize,
+    usage: vk::BufferUsageFlags,
+) -> (vk::Buffer, Allocation) {
+    ctx.allocator.allocate_buffer(
+        &ctx.device,
+        size,
+        usage,
+        vk::MemoryPropertyFlags::HOST_VISIBLE | vk::MemoryPropertyFlags::HOST_COHERENT,
+    )
+}
+
+fn topology(primitive_type: PrimitiveType) -> vk::PrimitiveTopology {
+    match primitive_type {
+        PrimitiveType::Points => vk::PrimitiveTopology::POINT_LIST,
+        PrimitiveType::Lines => vk::PrimitiveTopology::LINE_LIST,
+        PrimitiveType::LineStrip => vk::PrimitiveTopology::LINE_STRIP,
+        PrimitiveType::Triangles => vk::PrimitiveTopology::TRIANGLE_LIST,
+        PrimitiveType::TriangleStrip => vk::PrimitiveTopology::TRIANGLE_STRIP,
+    }
+}
+
 unsafe fn create_pipeline(
     device: &DeviceLoader,
     render_pass: vk::RenderPass,
     shader_stages: &[vk::PipelineShaderStageCreateInfoBuilder],
-    vertex_binding_descs: &[vk::VertexInputBindingDescriptionBuilder],
-    vertex_attribute_descs: &[vk::VertexInputAttributeDescriptionBuilder],
     primitive_topology: vk::PrimitiveTopology,
+    polygon_mode: vk::PolygonMode,
     descriptor_set_layouts: &[vk::DescriptorSetLayout],
 ) -> Pipeline {
+    let vertex_binding_descs = [Vertex::binding_desc(), Instance::binding_desc()];
+    let mut vertex_attribute_descs = Vertex::attribute_descs();
+    vertex_attribute_descs.extend(Instance::attribute_descs());
     let vertex_input = vk::PipelineVertexInputStateCreateInfoBuilder::new()
         .vertex_binding_descriptions(&vertex_binding_descs)
         .vertex_attribute_descriptions(&vertex_attribute_descs);
@@ -577,7 +937,7 @@ unsafe fn create_pipeline(
     let rasterizer = vk::PipelineRasterizationStateCreateInfoBuilder::new()
         .depth_clamp_enable(false)
         .rasterizer_discard_enable(false)
-        .polygon_mode(vk::PolygonMode::FILL)
+        .polygon_mode(polygon_mode)
         .line_width(1.0)
         .cull_mode(vk::CullModeFlags::BACK)
         .front_face(vk::FrontFace::COUNTER_CLOCKWISE);
@@ -1,4 +1,7 @@
 use nalgebra::Matrix4;
 
+/// Push constants of the vertex shaders, shared by all the instances of a draw.
 #[repr(C)]
-pub struct Spatial(pub Matrix4<f32>);
+pub struct Spatial {
+    pub view_projection: Matrix4<f32>,
+}
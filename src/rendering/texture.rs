@@ -1,20 +1,29 @@
-use std::ffi::c_void;
 use std::io;
+use std::iter;
 use std::path::Path;
 
 use erupt::{vk, DeviceLoader};
+use image::imageops::{self, FilterType};
 use image::io::Reader as ImageReader;
-use image::EncodableLayout;
 use image::RgbaImage;
+use smallvec::SmallVec;
 
 use crate::assets::AssetLocator;
 use crate::{
     assets::{Asset, TextureId},
-    rendering::vulkan::{
-        context::Context, g, memory, physical_device::PhysicalDevice, resource::DeviceResource,
+    rendering::{
+        material::{Filter, SamplerSettings, Wrap},
+        vulkan::{
+            allocator::{Allocation, Allocator},
+            context::Context,
+            memory, upload,
+        },
     },
 };
 
+/// Format textures are uploaded in, the colours of images being sRGB encoded.
+const TEXTURE_FORMAT: vk::Format = vk::Format::R8G8B8A8_SRGB;
+
 #[derive(Clone, Debug)]
 pub struct Texture {
     pub id: TextureId,
@@ -29,25 +38,16 @@ impl Asset for Texture {
 
 /// Texture loaded to the GPU.
 pub struct LoadedTexture {
-    pub id: TextureId,
-    pub memory: vk::DeviceMemory,
+    pub allocation: Allocation,
     pub image: vk::Image,
     pub image_view: vk::ImageView,
 }
 
 impl LoadedTexture {
-    pub fn id(&self) -> TextureId {
-        self.id
-    }
-}
-
-impl DeviceResource for LoadedTexture {
-    fn destroy(&self, device: &DeviceLoader) {
-        unsafe {
-            device.destroy_image_view(self.image_view, None);
-            device.destroy_image(self.image, None);
-            device.free_memory(self.memory, None);
-        }
+    pub unsafe fn destroy(&self, device: &DeviceLoader, allocator: &mut Allocator) {
+        device.destroy_image_view(self.image_view, None);
+        device.destroy_image(self.image, None);
+        allocator.free(&self.allocation);
     }
 }
 
@@ -64,187 +64,108 @@ impl Texture {
         })
     }
 
-    pub unsafe fn init(&self, ctx: &Context) -> LoadedTexture {
-        let (image, memory) = upload_to_gpu(
-            &ctx.device,
-            &ctx.physical_device,
-            &self.image.as_bytes(),
-            self.image.width(),
-            self.image.height(),
-            ctx.graphics_queue,
-            ctx.physical_device.graphics_queue_family,
-        );
-        let image_view = create_texture_view(&ctx.device, image);
+    pub unsafe fn init(&self, ctx: &mut Context) -> LoadedTexture {
+        let blit = ctx
+            .physical_device
+            .supports_linear_blit(&ctx.instance, TEXTURE_FORMAT);
+        let smaller_levels = if blit {
+            Vec::new()
+        } else {
+            mip_chain(&self.image)
+        };
+        let levels: SmallVec<[&RgbaImage; 16]> =
+            iter::once(&self.image).chain(&smaller_levels).collect();
+
+        let (image, allocation) =
+            ctx.uploader
+                .upload_image(&ctx.device, &mut ctx.allocator, &levels, TEXTURE_FORMAT);
+        let mip_levels = upload::mip_level_count(self.image.width(), self.image.height());
+        let image_view = create_texture_view(&ctx.device, image, mip_levels);
 
         LoadedTexture {
-            id: self.id,
             image,
             image_view,
-            memory,
+            allocation,
         }
     }
 }
 
-pub unsafe fn create_sampler(ctx: &Context) -> vk::Sampler {
-    let max_anisotropy = ctx.physical_device.properties.limits.max_sampler_anisotropy;
+/// Mip levels below `image` down to a single pixel, for when they can't be blitted on the GPU.
+fn mip_chain(image: &RgbaImage) -> Vec<RgbaImage> {
+    let (width, height) = image.dimensions();
+    let mut levels: Vec<RgbaImage> = Vec::new();
+    for level in 1..upload::mip_level_count(width, height) {
+        let (level_width, level_height) = upload::mip_size(width, height, level);
+        let larger = levels.last().unwrap_or(image);
+        let smaller = imageops::resize(larger, level_width, level_height, FilterType::Triangle);
+        levels.push(smaller);
+    }
+
+    levels
+}
+
+pub unsafe fn create_sampler(ctx: &Context, settings: &SamplerSettings) -> vk::Sampler {
+    let (filter, mipmap_mode) = match settings.filter {
+        Filter::Nearest => (vk::Filter::NEAREST, vk::SamplerMipmapMode::NEAREST),
+        Filter::Linear => (vk::Filter::LINEAR, vk::SamplerMipmapMode::LINEAR),
+    };
+    let address_mode = match settings.wrap {
+        Wrap::Repeat => vk::SamplerAddressMode::REPEAT,
+        Wrap::MirroredRepeat => vk::SamplerAddressMode::MIRRORED_REPEAT,
+        Wrap::ClampToEdge => vk::SamplerAddressMode::CLAMP_TO_EDGE,
+    };
+    let max_anisotropy = (settings.max_anisotropy as f32)
+        .min(ctx.physical_device.properties.limits.max_sampler_anisotropy);
+
     let info = vk::SamplerCreateInfoBuilder::new()
-        .mag_filter(vk::Filter::LINEAR)
-        .min_filter(vk::Filter::NEAREST)
-        .address_mode_u(vk::SamplerAddressMode::REPEAT)
-        .address_mode_v(vk::SamplerAddressMode::REPEAT)
-        .address_mode_w(vk::SamplerAddressMode::REPEAT)
-        .anisotropy_enable(true)
-        .max_anisotropy(max_anisotropy)
+        .mag_filter(filter)
+        .min_filter(filter)
+        .address_mode_u(address_mode)
+        .address_mode_v(address_mode)
+        .address_mode_w(address_mode)
+        .anisotropy_enable(max_anisotropy > 1.0)
+        .max_anisotropy(max_anisotropy.max(1.0))
         .border_color(vk::BorderColor::INT_OPAQUE_BLACK)
         .unnormalized_coordinates(false)
         .compare_enable(false)
         .compare_op(vk::CompareOp::ALWAYS)
-        .mipmap_mode(vk::SamplerMipmapMode::LINEAR)
+        .mipmap_mode(mipmap_mode)
         .mip_lod_bias(0.0)
         .min_lod(0.0)
-        .max_lod(0.0);
+        .max_lod(vk::LOD_CLAMP_NONE);
 
     ctx.device
         .create_sampler(&info, None)
         .expect("failed to create a texture sampler")
 }
 
-unsafe fn upload_to_gpu(
+pub unsafe fn create_texture_view(
     device: &DeviceLoader,
-    physical_device: &PhysicalDevice,
-    image: &[u8],
-    image_width: u32,
-    image_height: u32,
-    copy_queue: vk::Queue,
-    copy_queue_family: u32,
-) -> (vk::Image, vk::DeviceMemory) {
-    let image_size = image.len();
-    let (staging_buf, staging_mem) = memory::allocate_buffer(
-        device,
-        physical_device,
-        image_size,
-        vk::BufferUsageFlags::TRANSFER_SRC,
-        vk::MemoryPropertyFlags::HOST_VISIBLE | vk::MemoryPropertyFlags::HOST_COHERENT,
-    );
-
-    memory::copy_to_gpu(
-        &device,
-        image.as_ptr() as *const c_void,
-        staging_mem,
-        image_size,
-    );
-
-    let (texture, texture_mem) = memory::create_image(
-        device,
-        physical_device,
-        image_width,
-        image_height,
-        vk::Format::R8G8B8A8_SRGB,
-        vk::ImageTiling::OPTIMAL,
-        vk::ImageUsageFlags::TRANSFER_DST | vk::ImageUsageFlags::SAMPLED,
-        vk::MemoryPropertyFlags::DEVICE_LOCAL,
-    );
-
-    transition_image_layout(
-        device,
-        texture,
-        vk::Format::R8G8B8A8_SRGB,
-        vk::ImageLayout::UNDEFINED,
-        vk::ImageLayout::TRANSFER_DST_OPTIMAL,
-        copy_queue_family,
-        copy_queue,
-    );
-
-    memory::copy_buffer_to_image(
-        device,
-        staging_buf,
-        texture,
-        (image_width, image_height),
-        copy_queue,
-        copy_queue_family,
-    );
-
-    transition_image_layout(
-        device,
-        texture,
-        vk::Format::R8G8B8A8_SNORM,
-        vk::ImageLayout::TRANSFER_DST_OPTIMAL,
-        vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
-        copy_queue_family,
-        copy_queue,
-    );
-
-    device.destroy_buffer(staging_buf, None);
-    device.free_memory(staging_mem, None);
-
-    (texture, texture_mem)
-}
-
-pub unsafe fn create_texture_view(device: &DeviceLoader, texture: vk::Image) -> vk::ImageView {
+    texture: vk::Image,
+    mip_levels: u32,
+) -> vk::ImageView {
     memory::create_image_view(
         device,
         texture,
-        vk::Format::R8G8B8A8_SRGB,
+        TEXTURE_FORMAT,
         vk::ImageAspectFlags::COLOR,
+        mip_levels,
     )
 }
 
-pub unsafe fn transition_image_layout(
-    device: &DeviceLoader,
-    image: vk::Image,
-    format: vk::Format,
-    old_layout: vk::ImageLayout,
-    new_layout: vk::ImageLayout,
-    copy_queue_family: u32,
-    copy_queue: vk::Queue,
-) {
-    let (cmd_buf, cmd_pool) = g::begin_once_commands(device, copy_queue_family);
-
-    let mut barrier = vk::ImageMemoryBarrierBuilder::new()
-        .old_layout(old_layout)
-        .new_layout(new_layout)
-        .src_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
-        .dst_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
-        .image(image)
-        .subresource_range(vk::ImageSubresourceRange {
-            aspect_mask: vk::ImageAspectFlags::COLOR,
-            base_mip_level: 0,
-            level_count: 1,
-            base_array_layer: 0,
-            layer_count: 1,
-        });
-
-    let source_stage;
-    let destination_stage;
-    match (old_layout, new_layout) {
-        (vk::ImageLayout::UNDEFINED, vk::ImageLayout::TRANSFER_DST_OPTIMAL) => {
-            barrier = barrier
-                .src_access_mask(vk::AccessFlags::empty())
-                .dst_access_mask(vk::AccessFlags::TRANSFER_WRITE);
-
-            source_stage = vk::PipelineStageFlags::TOP_OF_PIPE;
-            destination_stage = vk::PipelineStageFlags::TRANSFER;
-        }
-        (vk::ImageLayout::TRANSFER_DST_OPTIMAL, vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL) => {
-            barrier = barrier
-                .src_access_mask(vk::AccessFlags::TRANSFER_WRITE)
-                .dst_access_mask(vk::AccessFlags::SHADER_READ);
+#[cfg(test)]
+mod test {
+    use image::Rgba;
 
-            source_stage = vk::PipelineStageFlags::TRANSFER;
-            destination_stage = vk::PipelineStageFlags::FRAGMENT_SHADER;
-        }
-        _ => panic!("unsupported layout transition"),
-    }
+    use super::*;
+
+    #[test]
+    fn test_mip_chain() {
+        let image = RgbaImage::from_pixel(5, 3, Rgba([200, 100, 50, 255]));
+        let levels = mip_chain(&image);
 
-    device.cmd_pipeline_barrier(
-        cmd_buf,
-        source_stage,
-        destination_stage,
-        vk::DependencyFlags::empty(),
-        &[],
-        &[],
-        &[barrier],
-    );
-
-    g::end_once_commands(device, cmd_pool, cmd_buf, copy_queue);
+        let sizes: Vec<_> = levels.iter().map(|level| level.dimensions()).collect();
+        assert_eq!(sizes, [(2, 1), (1, 1)]);
+        assert_eq!(levels[1].get_pixel(0, 0), &Rgba([200, 100, 50, 255]));
+    }
 }
@@ -2,6 +2,7 @@ use std::mem::size_of;
 
 use erupt::vk;
 use memoffset::offset_of;
+use nalgebra::Matrix4;
 
 use crate::rendering::mesh::Mesh;
 
@@ -11,17 +12,10 @@ pub struct Vertex {
     pub pos: [f32; 3],
     pub uv: [f32; 3],
     pub color: [f32; 3],
+    pub normal: [f32; 3],
 }
 
 impl Vertex {
-    pub fn zeroed() -> Self {
-        Vertex {
-            pos: [0.0; 3],
-            uv: [0.0; 3],
-            color: [0.0; 3],
-        }
-    }
-
     pub fn binding_desc<'a>() -> vk::VertexInputBindingDescriptionBuilder<'a> {
         vk::VertexInputBindingDescriptionBuilder::new()
             .binding(0)
@@ -52,7 +46,49 @@ impl Vertex {
                 offset: offset_of!(Vertex, color) as u32,
             }
             .into_builder(),
+            vk::VertexInputAttributeDescription {
+                binding: 0,
+                location: 3,
+                format: vk::Format::R32G32B32_SFLOAT,
+                offset: offset_of!(Vertex, normal) as u32,
+            }
+            .into_builder(),
         ]
         .into()
     }
 }
+
+/// Per-instance vertex data of instanced draws.
+#[repr(C)]
+#[derive(Clone, Debug, PartialEq)]
+pub struct Instance {
+    /// Object to world transform, a column per attribute location.
+    pub model: Matrix4<f32>,
+}
+
+impl Instance {
+    /// First attribute location of the instance data, following the vertex attributes.
+    const FIRST_LOCATION: u32 = 4;
+
+    pub fn binding_desc<'a>() -> vk::VertexInputBindingDescriptionBuilder<'a> {
+        vk::VertexInputBindingDescriptionBuilder::new()
+            .binding(1)
+            .input_rate(vk::VertexInputRate::INSTANCE)
+            .stride(size_of::<Instance>() as u32)
+    }
+
+    pub fn attribute_descs<'a>() -> Vec<vk::VertexInputAttributeDescriptionBuilder<'a>> {
+        (0..4)
+            .map(|column| {
+                vk::VertexInputAttributeDescription {
+                    binding: 1,
+                    location: Self::FIRST_LOCATION + column,
+                    format: vk::Format::R32G32B32A32_SFLOAT,
+                    offset: offset_of!(Instance, model) as u32
+                        + column * size_of::<[f32; 4]>() as u32,
+                }
+                .into_builder()
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,329 @@
+use std::collections::HashMap;
+use std::ffi::c_void;
+use std::ptr;
+
+use erupt::{vk, DeviceLoader};
+use smallvec::SmallVec;
+
+use crate::rendering::vulkan::{memory, physical_device::PhysicalDevice};
+
+/// Size of the device memory blocks allocations are taken from. Bigger allocations get a block
+/// of their own.
+const BLOCK_SIZE: vk::DeviceSize = 16 * 1024 * 1024;
+
+/// Part of a memory block owned by a buffer or an image.
+#[derive(Debug)]
+pub struct Allocation {
+    pub memory: vk::DeviceMemory,
+    pub offset: vk::DeviceSize,
+    pub size: vk::DeviceSize,
+    block: BlockKey,
+    /// Where the allocation is mapped to, null unless the memory is host visible.
+    mapped: *mut u8,
+}
+
+impl Allocation {
+    /// Copies `size` bytes from `src` to the start of the allocation, which must be host
+    /// visible.
+    pub unsafe fn write(&self, src: *const c_void, size: usize) {
+        self.write_at(0, src, size);
+    }
+
+    /// Copies `size` bytes from `src` to `offset` bytes into the allocation.
+    pub unsafe fn write_at(&self, offset: usize, src: *const c_void, size: usize) {
+        assert!(!self.mapped.is_null(), "allocation is not host visible");
+        assert!((offset + size) as vk::DeviceSize <= self.size);
+        ptr::copy_nonoverlapping(src as *const u8, self.mapped.add(offset), size);
+    }
+
+    /// Copies `size` bytes from the start of the allocation, which must be host visible, to
+    /// `dst`.
+    pub unsafe fn read(&self, dst: *mut c_void, size: usize) {
+        assert!(!self.mapped.is_null(), "allocation is not host visible");
+        assert!(size as vk::DeviceSize <= self.size);
+        ptr::copy_nonoverlapping(self.mapped, dst as *mut u8, size);
+    }
+}
+
+/// Memory type and tiling of the resources in a block. Linear and optimally tiled resources
+/// get separate blocks so they never have to be kept `bufferImageGranularity` apart.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+struct BlockKey {
+    memory_type: u32,
+    linear: bool,
+}
+
+struct MemoryBlock {
+    memory: vk::DeviceMemory,
+    free_list: FreeList,
+    mapped: *mut u8,
+}
+
+/// Hands out parts of large device memory blocks, keeping the number of device allocations
+/// low.
+pub struct Allocator {
+    memory_properties: vk::PhysicalDeviceMemoryProperties,
+    blocks: HashMap<BlockKey, Vec<MemoryBlock>>,
+}
+
+impl Allocator {
+    pub fn new(physical_device: &PhysicalDevice) -> Self {
+        Self {
+            memory_properties: physical_device.memory_properties,
+            blocks: HashMap::new(),
+        }
+    }
+
+    pub unsafe fn allocate(
+        &mut self,
+        device: &DeviceLoader,
+        requirements: &vk::MemoryRequirements,
+        properties: vk::MemoryPropertyFlags,
+        linear: bool,
+    ) -> Allocation {
+        let memory_type = memory::find_memory_type(
+            self.memory_properties,
+            requirements.memory_type_bits,
+            properties,
+        );
+        let key = BlockKey {
+            memory_type,
+            linear,
+        };
+        let host_visible = self.memory_properties.memory_types[memory_type as usize]
+            .property_flags
+            .contains(vk::MemoryPropertyFlags::HOST_VISIBLE);
+
+        let blocks = self.blocks.entry(key).or_default();
+        let found = blocks.iter_mut().enumerate().find_map(|(ix, block)| {
+            let offset = block
+                .free_list
+                .allocate(requirements.size, requirements.alignment)?;
+            Some((ix, offset))
+        });
+        let (block_ix, offset) = match found {
+            Some(found) => found,
+            None => {
+                let size = BLOCK_SIZE.max(requirements.size);
+                let mut block = MemoryBlock::new(device, memory_type, size, host_visible);
+                let offset = block
+                    .free_list
+                    .allocate(requirements.size, requirements.alignment)
+                    .expect("new block fits the allocation");
+                blocks.push(block);
+                (blocks.len() - 1, offset)
+            }
+        };
+        let block = &blocks[block_ix];
+
+        let mapped = if block.mapped.is_null() {
+            ptr::null_mut()
+        } else {
+            block.mapped.add(offset as usize)
+        };
+        Allocation {
+            memory: block.memory,
+            offset,
+            size: requirements.size,
+            block: key,
+            mapped,
+        }
+    }
+
+    /// Returns the memory of `allocation` to its block for reuse.
+    pub fn free(&mut self, allocation: &Allocation) {
+        let block = self
+            .blocks
+            .get_mut(&allocation.block)
+            .and_then(|blocks| blocks.iter_mut().find(|b| b.memory == allocation.memory))
+            .expect("allocation must come from this allocator");
+        block.free_list.free(allocation.offset, allocation.size);
+    }
+
+    pub unsafe fn allocate_buffer(
+        &mut self,
+        device: &DeviceLoader,
+        size: usize,
+        usage: vk::BufferUsageFlags,
+        properties: vk::MemoryPropertyFlags,
+    ) -> (vk::Buffer, Allocation) {
+        let buffer_info = vk::BufferCreateInfoBuilder::new()
+            .size(size as vk::DeviceSize)
+            .usage(usage)
+            .sharing_mode(vk::SharingMode::EXCLUSIVE);
+
+        let buffer = device
+            .create_buffer(&buffer_info, None)
+            .expect("Failed to create buffer");
+
+        let mem_reqs = device.get_buffer_memory_requirements(buffer);
+        let allocation = self.allocate(device, &mem_reqs, properties, true);
+        device
+            .bind_buffer_memory(buffer, allocation.memory, allocation.offset)
+            .expect("Failed to bind memory");
+
+        (buffer, allocation)
+    }
+
+    pub unsafe fn create_image(
+        &mut self,
+        device: &DeviceLoader,
+        width: u32,
+        height: u32,
+        format: vk::Format,
+        tiling: vk::ImageTiling,
+        usage: vk::ImageUsageFlags,
+        properties: vk::MemoryPropertyFlags,
+    ) -> (vk::Image, Allocation) {
+        let image_info = memory::image_info(width, height, 1, format, tiling, usage);
+        let image = device
+            .create_image(&image_info, None)
+            .expect("failed to create texture image");
+
+        let mem_reqs = device.get_image_memory_requirements(image);
+        let linear = tiling == vk::ImageTiling::LINEAR;
+        let allocation = self.allocate(device, &mem_reqs, properties, linear);
+        device
+            .bind_image_memory(image, allocation.memory, allocation.offset)
+            .expect("failed to bind image memory");
+
+        (image, allocation)
+    }
+
+    /// Frees the memory blocks, once nothing uses them anymore.
+    pub unsafe fn destroy_all(&mut self, device: &DeviceLoader) {
+        for block in self.blocks.values().flatten() {
+            device.free_memory(block.memory, None);
+        }
+
+        self.blocks.clear();
+    }
+}
+
+impl MemoryBlock {
+    unsafe fn new(
+        device: &DeviceLoader,
+        memory_type: u32,
+        size: vk::DeviceSize,
+        host_visible: bool,
+    ) -> Self {
+        let alloc_info = vk::MemoryAllocateInfoBuilder::new()
+            .allocation_size(size)
+            .memory_type_index(memory_type);
+        let memory = device
+            .allocate_memory(&alloc_info, None)
+            .expect("failed to allocate memory");
+
+        // Host visible blocks stay mapped, as a memory object can't be mapped twice at once
+        let mapped = if host_visible {
+            device
+                .map_memory(memory, 0, vk::WHOLE_SIZE, vk::MemoryMapFlags::empty())
+                .expect("failed to map memory") as *mut u8
+        } else {
+            ptr::null_mut()
+        };
+
+        Self {
+            memory,
+            free_list: FreeList::new(size),
+            mapped,
+        }
+    }
+}
+
+/// Free ranges of a memory block as offset and size, sorted by offset and never touching.
+#[derive(Debug)]
+struct FreeList {
+    ranges: Vec<(vk::DeviceSize, vk::DeviceSize)>,
+}
+
+impl FreeList {
+    fn new(size: vk::DeviceSize) -> Self {
+        Self {
+            ranges: vec![(0, size)],
+        }
+    }
+
+    /// Takes `size` bytes at `alignment` from the first range they fit in and returns their
+    /// offset. The bytes skipped for alignment stay free.
+    fn allocate(
+        &mut self,
+        size: vk::DeviceSize,
+        alignment: vk::DeviceSize,
+    ) -> Option<vk::DeviceSize> {
+        let alignment = alignment.max(1);
+        for ix in 0..self.ranges.len() {
+            let (offset, range_size) = self.ranges[ix];
+            let aligned = (offset + alignment - 1) / alignment * alignment;
+            let end = offset + range_size;
+            if aligned + size > end {
+                continue;
+            }
+
+            let mut rest = SmallVec::<[_; 2]>::new();
+            if aligned > offset {
+                rest.push((offset, aligned - offset));
+            }
+            if aligned + size < end {
+                rest.push((aligned + size, end - aligned - size));
+            }
+            self.ranges.splice(ix..ix + 1, rest);
+
+            return Some(aligned);
+        }
+
+        None
+    }
+
+    /// Gives back a range taken with [`Self::allocate`], merging it with its neighbours.
+    fn free(&mut self, offset: vk::DeviceSize, size: vk::DeviceSize) {
+        let ix = self.ranges.partition_point(|&(o, _)| o < offset);
+        self.ranges.insert(ix, (offset, size));
+
+        if ix + 1 < self.ranges.len() && offset + size == self.ranges[ix + 1].0 {
+            self.ranges[ix].1 += self.ranges[ix + 1].1;
+            self.ranges.remove(ix + 1);
+        }
+        if ix > 0 {
+            let (previous_offset, previous_size) = self.ranges[ix - 1];
+            if previous_offset + previous_size == offset {
+                self.ranges[ix - 1].1 += self.ranges[ix].1;
+                self.ranges.remove(ix);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_free_list_alignment() {
+        let mut free_list = FreeList::new(1024);
+
+        assert_eq!(free_list.allocate(10, 1), Some(0));
+        assert_eq!(free_list.allocate(100, 256), Some(256));
+        assert_eq!(free_list.ranges, [(10, 246), (356, 668)]);
+        assert_eq!(free_list.allocate(200, 1), Some(10));
+        assert_eq!(free_list.allocate(1000, 1), None);
+    }
+
+    #[test]
+    fn test_free_list_reuse() {
+        let mut free_list = FreeList::new(300);
+        let a = free_list.allocate(100, 1).unwrap();
+        let b = free_list.allocate(100, 1).unwrap();
+        let c = free_list.allocate(100, 1).unwrap();
+        assert_eq!(free_list.allocate(1, 1), None);
+
+        free_list.free(a, 100);
+        free_list.free(c, 100);
+        assert_eq!(free_list.ranges, [(0, 100), (200, 100)]);
+        assert_eq!(free_list.allocate(150, 1), None);
+
+        free_list.free(b, 100);
+        assert_eq!(free_list.ranges, [(0, 300)]);
+        assert_eq!(free_list.allocate(300, 1), Some(0));
+    }
+}
@@ -6,9 +6,11 @@ use erupt::{cstr, utils::surface, vk, DeviceLoader, EntryLoader, InstanceLoader}
 use winit::dpi::PhysicalSize;
 use winit::window::Window;
 
-use crate::logging::{debug, info};
+use crate::logging::{debug, info, warn};
+use crate::rendering::vulkan::allocator::Allocator;
 use crate::rendering::vulkan::physical_device::PhysicalDevice;
 use crate::rendering::vulkan::swapchain::Swapchain;
+use crate::rendering::vulkan::upload::Uploader;
 use crate::rendering::vulkan::{memory, validation};
 
 use super::sync_pool::SyncPool;
@@ -18,6 +20,8 @@ const LAYER_KHRONOS_VALIDATION: *const c_char = cstr!("VK_LAYER_KHRONOS_validati
 pub struct Context {
     pub cmd_pool: vk::CommandPool,
     pub sync_pool: SyncPool,
+    pub allocator: Allocator,
+    pub uploader: Uploader,
     pub swapchain: Option<Swapchain>,
     pub graphics_queue: vk::Queue,
     pub device: Arc<DeviceLoader>,
@@ -29,71 +33,84 @@ pub struct Context {
 impl Context {
     pub fn new(window: &Window, app_name: &str, engine_name: &str) -> Self {
         unsafe {
-            let entry = Arc::new(
-                EntryLoader::new().expect("Vulkan libraries must be present on the device"),
-            );
-            info!(
-                "Initializing Vulkan instance {}.{}.{}",
-                vk::api_version_major(entry.instance_version()),
-                vk::api_version_minor(entry.instance_version()),
-                vk::api_version_patch(entry.instance_version())
-            );
-
-            let mut instance_extensions = surface::enumerate_required_extensions(window)
+            let instance_extensions = surface::enumerate_required_extensions(window)
                 .expect("failed to get required surface extensions");
-
-            let mut layers = Vec::new();
-            #[cfg(all(debug_assertions, not(target_os = "android")))]
-            {
-                instance_extensions.push(vk::EXT_DEBUG_UTILS_EXTENSION_NAME);
-                layers.push(LAYER_KHRONOS_VALIDATION);
-            }
-
-            let instance =
-                create_instance(&entry, app_name, engine_name, &instance_extensions, &layers);
+            let (entry, instance, layers) =
+                load_instance(app_name, engine_name, instance_extensions);
             let surface = surface::create_surface(&instance, &window, None)
                 .expect("failed to create a surface");
 
-            #[cfg(all(debug_assertions, not(target_os = "android")))]
-            validation::init(&instance);
-
-            let device_extensions = [vk::KHR_SWAPCHAIN_EXTENSION_NAME];
-            let physical_device = PhysicalDevice::new(&instance, surface, &device_extensions);
-
-            let device =
-                create_logical_device(&instance, &physical_device, &device_extensions, &layers);
-
-            let graphics_queue = device.get_device_queue(physical_device.graphics_queue_family, 0);
-
-            info!(
-                "Using physical device: {:?}",
-                CStr::from_ptr(physical_device.properties.device_name.as_ptr())
-            );
+            let mut ctx = Self::with_device(entry, instance, Some(surface), &layers);
 
             let PhysicalSize { width, height } = window.inner_size();
             let draw_area_size = vk::Extent2D { width, height };
-            let swapchain = Swapchain::new(
-                &device,
-                &physical_device,
-                graphics_queue,
+            ctx.swapchain = Some(Swapchain::new(
+                &ctx.device,
+                &ctx.physical_device,
+                &mut ctx.allocator,
+                ctx.graphics_queue,
                 surface,
                 &draw_area_size,
-            );
-
-            let sync_pool = SyncPool::new();
-            let cmd_pool =
-                memory::create_command_pool(&device, physical_device.graphics_queue_family);
-
-            Self {
-                cmd_pool,
-                graphics_queue,
-                device,
-                physical_device,
-                instance,
-                entry,
-                swapchain: Some(swapchain),
-                sync_pool,
+            ));
+
+            ctx
+        }
+    }
+
+    /// Creates a context without a window or swapchain, for rendering offscreen only. Works
+    /// with software drivers such as lavapipe.
+    pub fn headless(app_name: &str, engine_name: &str) -> Self {
+        unsafe {
+            let (entry, instance, layers) = load_instance(app_name, engine_name, Vec::new());
+            Self::with_device(entry, instance, None, &layers)
+        }
+    }
+
+    unsafe fn with_device(
+        entry: Arc<EntryLoader>,
+        instance: Arc<InstanceLoader>,
+        surface: Option<vk::SurfaceKHR>,
+        layers: &[*const c_char],
+    ) -> Self {
+        let device_extensions: &[*const c_char] = match surface {
+            Some(_) => &[vk::KHR_SWAPCHAIN_EXTENSION_NAME],
+            None => &[],
+        };
+        let physical_device = PhysicalDevice::new(&instance, surface, device_extensions);
+
+        let device = create_logical_device(&instance, &physical_device, device_extensions, layers);
+
+        let graphics_queue = device.get_device_queue(physical_device.graphics_queue_family, 0);
+
+        info!(
+            "Using physical device: {:?}",
+            CStr::from_ptr(physical_device.properties.device_name.as_ptr())
+        );
+
+        let mut sync_pool = SyncPool::new();
+        let mut allocator = Allocator::new(&physical_device);
+        let graphics = (graphics_queue, physical_device.graphics_queue_family);
+        let transfer = match physical_device.transfer_queue_family {
+            Some(queue_family) => {
+                debug!("Uploading through dedicated transfer queue family {queue_family}");
+                (device.get_device_queue(queue_family, 0), queue_family)
             }
+            None => graphics,
+        };
+        let uploader = Uploader::new(&device, &mut allocator, &mut sync_pool, transfer, graphics);
+        let cmd_pool = memory::create_command_pool(&device, physical_device.graphics_queue_family);
+
+        Self {
+            cmd_pool,
+            graphics_queue,
+            device,
+            physical_device,
+            instance,
+            entry,
+            swapchain: None,
+            sync_pool,
+            allocator,
+            uploader,
         }
     }
 
@@ -105,10 +122,12 @@ impl Drop for Context {
         debug!("Dropping Vulkan context");
         unsafe {
             if let Some(swapchain) = &mut self.swapchain {
-                swapchain.destroy(&self.device, &self.instance);
+                swapchain.destroy(&self.device, &self.instance, &mut self.allocator);
             }
 
+            self.uploader.destroy(&self.device, &mut self.allocator);
             self.sync_pool.destroy_all(&self.device);
+            self.allocator.destroy_all(&self.device);
             self.device.destroy_command_pool(self.cmd_pool, None);
 
             self.device.destroy_device(None);
@@ -120,6 +139,63 @@ impl Drop for Context {
     }
 }
 
+/// Loads Vulkan and creates an instance with `instance_extensions`, adding validation in debug
+/// builds where it is installed. Returns the layers enabled.
+unsafe fn load_instance(
+    app_name: &str,
+    engine_name: &str,
+    mut instance_extensions: Vec<*const c_char>,
+) -> (Arc<EntryLoader>, Arc<InstanceLoader>, Vec<*const c_char>) {
+    let entry =
+        Arc::new(EntryLoader::new().expect("Vulkan libraries must be present on the device"));
+    info!(
+        "Initializing Vulkan instance {}.{}.{}",
+        vk::api_version_major(entry.instance_version()),
+        vk::api_version_minor(entry.instance_version()),
+        vk::api_version_patch(entry.instance_version())
+    );
+
+    let mut layers = Vec::new();
+    #[cfg(all(debug_assertions, not(target_os = "android")))]
+    let validation = validation_available(&entry);
+    #[cfg(all(debug_assertions, not(target_os = "android")))]
+    if validation {
+        instance_extensions.push(vk::EXT_DEBUG_UTILS_EXTENSION_NAME);
+        layers.push(LAYER_KHRONOS_VALIDATION);
+    } else {
+        warn!("Validation layers are not installed, continuing without them");
+    }
+
+    let instance = create_instance(&entry, app_name, engine_name, &instance_extensions, &layers);
+
+    #[cfg(all(debug_assertions, not(target_os = "android")))]
+    if validation {
+        validation::init(&instance);
+    }
+
+    (entry, instance, layers)
+}
+
+/// Whether both the validation layer and the debug utils extension it reports through are
+/// present.
+#[cfg(all(debug_assertions, not(target_os = "android")))]
+unsafe fn validation_available(entry: &EntryLoader) -> bool {
+    let layers = entry.enumerate_instance_layer_properties(None).unwrap();
+    let has_layer = layers.iter().any(|properties| {
+        CStr::from_ptr(properties.layer_name.as_ptr()) == CStr::from_ptr(LAYER_KHRONOS_VALIDATION)
+    });
+
+    let extensions = entry
+        .enumerate_instance_extension_properties(None, None)
+        .unwrap();
+    let has_extension = extensions.iter().any(|properties| {
+        CStr::from_ptr(properties.extension_name.as_ptr())
+            == CStr::from_ptr(vk::EXT_DEBUG_UTILS_EXTENSION_NAME)
+    });
+
+    has_layer && has_extension
+}
+
 unsafe fn create_instance(
     entry: &EntryLoader,
     app_name: &str,
@@ -150,11 +226,22 @@ unsafe fn create_logical_device(
     device_extensions: &[*const c_char],
     device_layers: &[*const c_char],
 ) -> Arc<DeviceLoader> {
-    let queue_infos = vec![vk::DeviceQueueCreateInfoBuilder::new()
+    let queue_priorities = [1.0];
+    let mut queue_infos = vec![vk::DeviceQueueCreateInfoBuilder::new()
         .queue_family_index(physical_device.graphics_queue_family)
-        .queue_priorities(&[1.0])];
+        .queue_priorities(&queue_priorities)];
+    if let Some(transfer_queue_family) = physical_device.transfer_queue_family {
+        queue_infos.push(
+            vk::DeviceQueueCreateInfoBuilder::new()
+                .queue_family_index(transfer_queue_family)
+                .queue_priorities(&queue_priorities),
+        );
+    }
 
-    let features = vk::PhysicalDeviceFeaturesBuilder::new().sampler_anisotropy(true);
+    // Wireframe rendering is a debugging aid, so it is enabled where available but not required
+    let features = vk::PhysicalDeviceFeaturesBuilder::new()
+        .sampler_anisotropy(true)
+        .fill_mode_non_solid(physical_device.features.fill_mode_non_solid != 0);
 
     let device_info = vk::DeviceCreateInfoBuilder::new()
         .queue_create_infos(&queue_infos)
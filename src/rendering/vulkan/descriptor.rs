@@ -1,64 +1,80 @@
-use std::collections::HashMap;
 use std::mem::size_of;
 
 use erupt::{vk, DeviceLoader};
 use smallvec::{smallvec, SmallVec};
 
-use crate::{assets::TextureId, rendering::texture::LoadedTexture, transform::Transform};
+use crate::{rendering::texture::LoadedTexture, transform::Transform};
 
-pub unsafe fn texture_descriptor_sets(
+/// Texture sets each pool has room for, another pool is created once they run out.
+pub const TEXTURE_SETS_PER_POOL: u32 = 16;
+
+/// Allocates a set binding `texture` sampled with `sampler`.
+pub unsafe fn texture_descriptor_set(
     device: &DeviceLoader,
     pool: vk::DescriptorPool,
     layout: vk::DescriptorSetLayout,
     binding: u32,
-    textures: &[&LoadedTexture],
+    texture: &LoadedTexture,
     sampler: vk::Sampler,
-) -> HashMap<TextureId, vk::DescriptorSet> {
-    debug_assert!(textures.len() <= 8);
+) -> vk::DescriptorSet {
+    let layouts = [layout];
+    let alloc_info = vk::DescriptorSetAllocateInfoBuilder::new()
+        .descriptor_pool(pool)
+        .set_layouts(&layouts);
+
+    let set = device
+        .allocate_descriptor_sets(&alloc_info)
+        .expect("failed to allocate texture descriptor set")[0];
+
+    let image_infos = [vk::DescriptorImageInfoBuilder::new()
+        .image_layout(vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL)
+        .image_view(texture.image_view)
+        .sampler(sampler)];
+    let write = vk::WriteDescriptorSetBuilder::new()
+        .descriptor_type(vk::DescriptorType::COMBINED_IMAGE_SAMPLER)
+        .dst_set(set)
+        .dst_binding(binding)
+        .dst_array_element(0)
+        .image_info(&image_infos);
+    device.update_descriptor_sets(&[write], &[]);
+
+    set
+}
 
-    let layouts: SmallVec<[vk::DescriptorSetLayout; 16]> = smallvec![layout; textures.len()];
+/// Allocates a set for each of `buffers`, binding the first `range` bytes of the buffer as a
+/// uniform buffer.
+pub unsafe fn uniform_descriptor_sets(
+    device: &DeviceLoader,
+    pool: vk::DescriptorPool,
+    layout: vk::DescriptorSetLayout,
+    binding: u32,
+    buffers: &[vk::Buffer],
+    range: usize,
+) -> SmallVec<[vk::DescriptorSet; 2]> {
+    let layouts: SmallVec<[vk::DescriptorSetLayout; 2]> = smallvec![layout; buffers.len()];
     let alloc_info = vk::DescriptorSetAllocateInfoBuilder::new()
         .descriptor_pool(pool)
         .set_layouts(&layouts);
 
     let sets = device
         .allocate_descriptor_sets(&alloc_info)
-        .expect("failed to allocate global descriptor set");
-
-    let image_infos: SmallVec<[_; 8]> = textures
-        .iter()
-        .map(|t| {
-            vk::DescriptorImageInfoBuilder::new()
-                .image_layout(vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL)
-                .image_view(t.image_view)
-                .sampler(sampler)
-        })
-        .map(|image_info| {
-            let v: SmallVec<[vk::DescriptorImageInfoBuilder; 1]> = smallvec![image_info];
-            v
-        })
-        .collect();
-
-    let descriptor_writes: SmallVec<[vk::WriteDescriptorSetBuilder; 8]> = sets
-        .iter()
-        .zip(image_infos.iter())
-        .map(|(&set, image_infos)| {
-            vk::WriteDescriptorSetBuilder::new()
-                .descriptor_type(vk::DescriptorType::COMBINED_IMAGE_SAMPLER)
-                .dst_set(set)
-                .dst_binding(binding)
-                .dst_array_element(0)
-                .image_info(&image_infos)
-        })
-        .collect();
-
-    device.update_descriptor_sets(&descriptor_writes, &[]);
-
-    textures
-        .iter()
-        .zip(sets.iter())
-        .map(|(t, s)| (t.id(), *s))
-        .collect()
+        .expect("failed to allocate uniform descriptor sets");
+
+    for (&set, &buffer) in sets.iter().zip(buffers) {
+        let buffer_infos = [vk::DescriptorBufferInfoBuilder::new()
+            .buffer(buffer)
+            .offset(0)
+            .range(range as vk::DeviceSize)];
+        let write = vk::WriteDescriptorSetBuilder::new()
+            .descriptor_type(vk::DescriptorType::UNIFORM_BUFFER)
+            .dst_set(set)
+            .dst_binding(binding)
+            .dst_array_element(0)
+            .buffer_info(&buffer_infos);
+        device.update_descriptor_sets(&[write], &[]);
+    }
+
+    sets.iter().copied().collect()
 }
 
 pub unsafe fn create_spatial_descriptor_sets(
@@ -120,21 +136,29 @@ pub unsafe fn create_descriptor_sets(
     descriptor_sets
 }
 
+/// Creates a pool for `uniform_sets` sets of one uniform buffer and `texture_sets` sets of one
+/// texture.
 pub unsafe fn create_descriptor_pool(
     device: &DeviceLoader,
-    frames_in_flight: usize,
+    uniform_sets: u32,
+    texture_sets: u32,
 ) -> vk::DescriptorPool {
-    let pool_sizes = [
-        vk::DescriptorPoolSizeBuilder::new()
-            ._type(vk::DescriptorType::UNIFORM_BUFFER)
-            .descriptor_count(frames_in_flight as u32),
+    let mut pool_sizes: SmallVec<[vk::DescriptorPoolSizeBuilder; 2]> = SmallVec::new();
+    if uniform_sets > 0 {
+        pool_sizes.push(
+            vk::DescriptorPoolSizeBuilder::new()
+                ._type(vk::DescriptorType::UNIFORM_BUFFER)
+                .descriptor_count(uniform_sets),
+        );
+    }
+    pool_sizes.push(
         vk::DescriptorPoolSizeBuilder::new()
             ._type(vk::DescriptorType::COMBINED_IMAGE_SAMPLER)
-            .descriptor_count(16),
-    ];
+            .descriptor_count(texture_sets),
+    );
     let pool_info = vk::DescriptorPoolCreateInfoBuilder::new()
         .pool_sizes(&pool_sizes)
-        .max_sets(16);
+        .max_sets(uniform_sets + texture_sets);
 
     device
         .create_descriptor_pool(&pool_info, None)
@@ -159,6 +183,25 @@ pub unsafe fn descriptor_set_layout_1_texture(
         .expect("failed to create a descriptor set layout")
 }
 
+pub unsafe fn descriptor_set_layout_1_uniform(
+    device: &DeviceLoader,
+    binding: u32,
+    stage_flags: vk::ShaderStageFlags,
+) -> vk::DescriptorSetLayout {
+    let uniform_binding = vk::DescriptorSetLayoutBindingBuilder::new()
+        .binding(binding)
+        .descriptor_count(1)
+        .descriptor_type(vk::DescriptorType::UNIFORM_BUFFER)
+        .stage_flags(stage_flags);
+
+    let bindings = [uniform_binding];
+    let info = vk::DescriptorSetLayoutCreateInfoBuilder::new().bindings(&bindings);
+
+    device
+        .create_descriptor_set_layout(&info, None)
+        .expect("failed to create a descriptor set layout")
+}
+
 impl Transform {
     fn binding<'a>() -> vk::DescriptorSetLayoutBindingBuilder<'a> {
         vk::DescriptorSetLayoutBindingBuilder::new()
@@ -81,6 +81,17 @@ pub unsafe fn begin_draw(
         .begin_command_buffer(cmd_buf, &cmd_buf_begin_info)
         .unwrap();
 
+    begin_render_pass(device, cmd_buf, render_pass, framebuffer, draw_extent);
+}
+
+/// Begins `render_pass` clearing to black and sets the viewport and scissor to `draw_extent`.
+pub unsafe fn begin_render_pass(
+    device: &DeviceLoader,
+    cmd_buf: vk::CommandBuffer,
+    render_pass: vk::RenderPass,
+    framebuffer: vk::Framebuffer,
+    draw_extent: &vk::Extent2D,
+) {
     let clear_values = [
         vk::ClearValue {
             color: vk::ClearColorValue {
@@ -1,18 +1,16 @@
 use std::ffi::c_void;
 use std::mem::size_of_val;
-use std::ptr;
 
 use erupt::{vk, DeviceLoader};
 use smallvec::SmallVec;
 
+use crate::rendering::mesh::Indices;
 use crate::rendering::vertex::Vertex;
+use crate::rendering::vulkan::allocator::{Allocation, Allocator};
 use crate::rendering::vulkan::context::Context;
-use crate::rendering::vulkan::g;
 use crate::rendering::vulkan::physical_device::PhysicalDevice;
 use crate::transform::Transform;
 
-use super::resource::DeviceResource;
-
 pub struct UniformBuffer {
     memory: vk::DeviceMemory,
     handle: vk::Buffer,
@@ -20,66 +18,48 @@ pub struct UniformBuffer {
 
 #[derive(Debug)]
 pub struct VertexBuffer {
-    pub memory: vk::DeviceMemory,
+    pub allocation: Allocation,
     pub handle: vk::Buffer,
 }
 
-impl DeviceResource for VertexBuffer {
-    fn destroy(&self, device: &DeviceLoader) {
-        unsafe {
-            device.destroy_buffer(self.handle, None);
-            device.free_memory(self.memory, None);
-        }
+impl VertexBuffer {
+    pub unsafe fn destroy(&self, device: &DeviceLoader, allocator: &mut Allocator) {
+        device.destroy_buffer(self.handle, None);
+        allocator.free(&self.allocation);
     }
 }
 
 #[derive(Debug)]
 pub struct IndexBuffer {
-    pub memory: vk::DeviceMemory,
+    pub allocation: Allocation,
     pub handle: vk::Buffer,
-    pub index_count: usize,
+    pub index_type: vk::IndexType,
 }
 
-impl DeviceResource for IndexBuffer {
-    fn destroy(&self, device: &DeviceLoader) {
-        unsafe {
-            device.destroy_buffer(self.handle, None);
-            device.free_memory(self.memory, None);
-        }
+impl IndexBuffer {
+    pub unsafe fn destroy(&self, device: &DeviceLoader, allocator: &mut Allocator) {
+        device.destroy_buffer(self.handle, None);
+        allocator.free(&self.allocation);
     }
 }
 
-pub unsafe fn copy_to_gpu(
-    device: &DeviceLoader,
-    src: *const c_void,
-    dst: vk::DeviceMemory,
-    size: usize,
-) {
-    let data = device
-        .map_memory(dst, 0, size as vk::DeviceSize, vk::MemoryMapFlags::empty())
-        .expect("failed to map memory");
-    ptr::copy_nonoverlapping(src, data, size as usize);
-    device.unmap_memory(dst);
-}
-
-pub unsafe fn create_image(
-    device: &DeviceLoader,
-    physical_device: &PhysicalDevice,
+/// Creation info of a single layer 2D image.
+pub fn image_info<'a>(
     width: u32,
     height: u32,
+    mip_levels: u32,
     format: vk::Format,
     tiling: vk::ImageTiling,
     usage: vk::ImageUsageFlags,
-    properties: vk::MemoryPropertyFlags,
-) -> (vk::Image, vk::DeviceMemory) {
-    let image_info = vk::ImageCreateInfoBuilder::new()
+) -> vk::ImageCreateInfoBuilder<'a> {
+    vk::ImageCreateInfoBuilder::new()
         .image_type(vk::ImageType::_2D)
         .extent(vk::Extent3D {
             width,
             height,
             depth: 1,
         })
-        .mip_levels(1)
+        .mip_levels(mip_levels)
         .array_layers(1)
         .format(format)
         .tiling(tiling)
@@ -87,30 +67,7 @@ pub unsafe fn create_image(
         .usage(usage)
         .sharing_mode(vk::SharingMode::EXCLUSIVE)
         .samples(vk::SampleCountFlagBits::_1)
-        .flags(vk::ImageCreateFlags::empty());
-
-    let image = device
-        .create_image(&image_info, None)
-        .expect("failed to create texture image");
-
-    let mem_reqs = device.get_image_memory_requirements(image);
-    let mem_type_index = find_memory_type(
-        physical_device.memory_properties,
-        mem_reqs.memory_type_bits,
-        properties,
-    );
-    let alloc_info = vk::MemoryAllocateInfoBuilder::new()
-        .allocation_size(mem_reqs.size)
-        .memory_type_index(mem_type_index);
-
-    let mem = device
-        .allocate_memory(&alloc_info, None)
-        .expect("failed to allocate memory");
-    device
-        .bind_image_memory(image, mem, 0)
-        .expect("failed to bind image memory");
-
-    (image, mem)
+        .flags(vk::ImageCreateFlags::empty())
 }
 
 pub unsafe fn upload_uniform_buffers(
@@ -183,151 +140,37 @@ pub unsafe fn allocate_buffer(
     (buffer, buffer_memory)
 }
 
-pub unsafe fn create_vertex_buffer(
-    ctx: &Context,
-    vertices: &[Vertex],
-    copy_queue_family: u32,
-    copy_queue: vk::Queue,
-) -> (vk::Buffer, vk::DeviceMemory) {
-    let size = size_of_val(&vertices[0]) * vertices.len();
-    let (staging_buf, staging_mem) = allocate_buffer(
-        &ctx.device,
-        &ctx.physical_device,
-        size,
-        vk::BufferUsageFlags::TRANSFER_SRC,
-        vk::MemoryPropertyFlags::HOST_VISIBLE | vk::MemoryPropertyFlags::HOST_COHERENT,
-    );
-
-    copy_to_gpu(
-        &ctx.device,
-        vertices.as_ptr() as *const c_void,
-        staging_mem,
-        size,
-    );
-
-    let (vertex_buf, vertex_mem) = allocate_buffer(
-        &ctx.device,
-        &ctx.physical_device,
-        size,
-        vk::BufferUsageFlags::TRANSFER_DST | vk::BufferUsageFlags::VERTEX_BUFFER,
-        vk::MemoryPropertyFlags::DEVICE_LOCAL,
-    );
-
-    copy_buffer(
-        ctx,
-        vertex_buf,
-        staging_buf,
-        size,
-        copy_queue_family,
-        copy_queue,
-    );
-
-    ctx.device.destroy_buffer(staging_buf, None);
-    ctx.device.free_memory(staging_mem, None);
-
-    (vertex_buf, vertex_mem)
-}
-
-pub unsafe fn copy_buffer_to_image(
-    device: &DeviceLoader,
-    buffer: vk::Buffer,
-    image: vk::Image,
-    size: (u32, u32),
-    copy_queue: vk::Queue,
-    copy_queue_family: u32,
-) {
-    let (cmd_buf, cmd_pool) = g::begin_once_commands(device, copy_queue_family);
-
-    let region = vk::BufferImageCopyBuilder::new()
-        .buffer_offset(0)
-        .buffer_row_length(0)
-        .buffer_image_height(0)
-        .image_subresource(vk::ImageSubresourceLayers {
-            aspect_mask: vk::ImageAspectFlags::COLOR,
-            mip_level: 0,
-            base_array_layer: 0,
-            layer_count: 1,
-        })
-        .image_offset(vk::Offset3D { x: 0, y: 0, z: 0 })
-        .image_extent(vk::Extent3D {
-            width: size.0,
-            height: size.1,
-            depth: 1,
-        });
-
-    device.cmd_copy_buffer_to_image(
-        cmd_buf,
-        buffer,
-        image,
-        vk::ImageLayout::TRANSFER_DST_OPTIMAL,
-        &[region],
-    );
-
-    g::end_once_commands(device, cmd_pool, cmd_buf, copy_queue)
-}
-
-pub unsafe fn copy_buffer(
-    ctx: &Context,
-    dst_buf: vk::Buffer,
-    src_buf: vk::Buffer,
-    size: usize,
-    copy_queue_family: u32,
-    copy_queue: vk::Queue,
-) {
-    let (cmd_buf, cmd_pool) = g::begin_once_commands(&ctx.device, copy_queue_family);
-
-    let copy_region = vk::BufferCopyBuilder::new().size(size as vk::DeviceSize);
-
-    ctx.device
-        .cmd_copy_buffer(cmd_buf, src_buf, dst_buf, &[copy_region]);
-
-    g::end_once_commands(&ctx.device, cmd_pool, cmd_buf, copy_queue);
-}
-
-pub unsafe fn create_index_buffer(
-    ctx: &Context,
-    indices: &[u16],
-    copy_queue_family: u32,
-    copy_queue: vk::Queue,
-) -> (vk::Buffer, vk::DeviceMemory) {
-    let buf_size = size_of_val(&indices[0]) * indices.len();
-
-    let (staging_buf, staging_mem) = allocate_buffer(
-        &ctx.device,
-        &ctx.physical_device,
-        buf_size,
-        vk::BufferUsageFlags::TRANSFER_SRC,
-        vk::MemoryPropertyFlags::HOST_COHERENT | vk::MemoryPropertyFlags::HOST_VISIBLE,
-    );
-
-    copy_to_gpu(
-        &ctx.device,
-        indices.as_ptr() as *const c_void,
-        staging_mem,
-        buf_size,
-    );
-
-    let (index_buf, index_mem) = allocate_buffer(
+pub unsafe fn create_vertex_buffer(ctx: &mut Context, vertices: &[Vertex]) -> VertexBuffer {
+    let (handle, allocation) = ctx.uploader.upload_buffer(
         &ctx.device,
-        &ctx.physical_device,
-        buf_size,
-        vk::BufferUsageFlags::TRANSFER_DST | vk::BufferUsageFlags::INDEX_BUFFER,
-        vk::MemoryPropertyFlags::DEVICE_LOCAL,
+        &mut ctx.allocator,
+        vertices,
+        vk::BufferUsageFlags::VERTEX_BUFFER,
     );
 
-    copy_buffer(
-        ctx,
-        index_buf,
-        staging_buf,
-        buf_size,
-        copy_queue_family,
-        copy_queue,
-    );
-
-    ctx.device.destroy_buffer(staging_buf, None);
-    ctx.device.free_memory(staging_mem, None);
-
-    (index_buf, index_mem)
+    VertexBuffer { allocation, handle }
+}
+
+pub unsafe fn create_index_buffer(ctx: &mut Context, indices: &Indices) -> IndexBuffer {
+    let usage = vk::BufferUsageFlags::INDEX_BUFFER;
+    let ((handle, allocation), index_type) = match indices {
+        Indices::U16(indices) => (
+            ctx.uploader
+                .upload_buffer(&ctx.device, &mut ctx.allocator, indices, usage),
+            vk::IndexType::UINT16,
+        ),
+        Indices::U32(indices) => (
+            ctx.uploader
+                .upload_buffer(&ctx.device, &mut ctx.allocator, indices, usage),
+            vk::IndexType::UINT32,
+        ),
+    };
+
+    IndexBuffer {
+        allocation,
+        handle,
+        index_type,
+    }
 }
 
 pub unsafe fn find_memory_type(
@@ -368,24 +211,6 @@ pub unsafe fn create_command_buffers(
         .expect("failed to create command buffer")
 }
 
-pub unsafe fn create_depth_buffer(
-    device: &DeviceLoader,
-    physical_device: &PhysicalDevice,
-    format: vk::Format,
-    extent: &vk::Extent2D,
-) -> (vk::Image, vk::DeviceMemory) {
-    create_image(
-        device,
-        physical_device,
-        extent.width,
-        extent.height,
-        format,
-        vk::ImageTiling::OPTIMAL,
-        vk::ImageUsageFlags::DEPTH_STENCIL_ATTACHMENT,
-        vk::MemoryPropertyFlags::DEVICE_LOCAL,
-    )
-}
-
 fn has_stencil_component(format: vk::Format) -> bool {
     format == vk::Format::D32_SFLOAT_S8_UINT || format == vk::Format::D32_SFLOAT_S8_UINT
 }
@@ -395,6 +220,7 @@ pub unsafe fn create_image_view(
     image: vk::Image,
     format: vk::Format,
     aspect_flags: vk::ImageAspectFlags,
+    mip_levels: u32,
 ) -> vk::ImageView {
     let image_view_info = vk::ImageViewCreateInfoBuilder::new()
         .image(image)
@@ -403,7 +229,7 @@ pub unsafe fn create_image_view(
         .subresource_range(vk::ImageSubresourceRange {
             aspect_mask: aspect_flags,
             base_mip_level: 0,
-            level_count: 1,
+            level_count: mip_levels,
             base_array_layer: 0,
             layer_count: 1,
         });
@@ -1,9 +1,12 @@
+pub mod allocator;
 pub mod context;
 pub mod descriptor;
 pub mod g;
 pub mod memory;
+pub mod offscreen;
 pub mod physical_device;
 pub mod resource;
 pub mod swapchain;
 pub mod sync_pool;
+pub mod upload;
 pub mod validation;
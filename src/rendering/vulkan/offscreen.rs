@@ -0,0 +1,160 @@
+use std::ffi::c_void;
+
+use erupt::{vk, DeviceLoader};
+use image::RgbaImage;
+
+use crate::rendering::vulkan::allocator::{Allocation, Allocator};
+use crate::rendering::vulkan::{memory, physical_device::PhysicalDevice, swapchain::DepthBuffer};
+
+/// Colour and depth images rendered into instead of a swapchain image, with a host visible
+/// buffer the colour image is copied to for reading back.
+pub struct OffscreenTarget {
+    extent: vk::Extent2D,
+    format: vk::Format,
+    image: vk::Image,
+    allocation: Allocation,
+    image_view: vk::ImageView,
+    depth_buffer: DepthBuffer,
+    framebuffer: vk::Framebuffer,
+    readback_buf: vk::Buffer,
+    readback_alloc: Allocation,
+}
+
+impl OffscreenTarget {
+    /// Creates a target for `render_pass`, which must leave the colour attachment in the
+    /// `TRANSFER_SRC_OPTIMAL` layout.
+    pub unsafe fn new(
+        device: &DeviceLoader,
+        physical_device: &PhysicalDevice,
+        allocator: &mut Allocator,
+        render_pass: vk::RenderPass,
+        extent: vk::Extent2D,
+    ) -> Self {
+        let format = physical_device.surface_format.format;
+        let (image, allocation) = allocator.create_image(
+            device,
+            extent.width,
+            extent.height,
+            format,
+            vk::ImageTiling::OPTIMAL,
+            vk::ImageUsageFlags::COLOR_ATTACHMENT | vk::ImageUsageFlags::TRANSFER_SRC,
+            vk::MemoryPropertyFlags::DEVICE_LOCAL,
+        );
+        let image_view =
+            memory::create_image_view(device, image, format, vk::ImageAspectFlags::COLOR, 1);
+        let depth_buffer =
+            DepthBuffer::new(device, allocator, physical_device.depth_format, &extent);
+
+        let attachments = [image_view, depth_buffer.image_view];
+        let framebuffer_info = vk::FramebufferCreateInfoBuilder::new()
+            .render_pass(render_pass)
+            .attachments(&attachments)
+            .width(extent.width)
+            .height(extent.height)
+            .layers(1);
+        let framebuffer = device.create_framebuffer(&framebuffer_info, None).unwrap();
+
+        let (readback_buf, readback_alloc) = allocator.allocate_buffer(
+            device,
+            (extent.width * extent.height * 4) as usize,
+            vk::BufferUsageFlags::TRANSFER_DST,
+            vk::MemoryPropertyFlags::HOST_VISIBLE | vk::MemoryPropertyFlags::HOST_COHERENT,
+        );
+
+        Self {
+            extent,
+            format,
+            image,
+            allocation,
+            image_view,
+            depth_buffer,
+            framebuffer,
+            readback_buf,
+            readback_alloc,
+        }
+    }
+
+    pub fn extent(&self) -> &vk::Extent2D {
+        &self.extent
+    }
+
+    pub fn framebuffer(&self) -> vk::Framebuffer {
+        self.framebuffer
+    }
+
+    /// Records copying the rendered image to the readback buffer.
+    pub unsafe fn cmd_copy_to_readback(&self, device: &DeviceLoader, cmd_buf: vk::CommandBuffer) {
+        let region = vk::BufferImageCopyBuilder::new()
+            .buffer_offset(0)
+            .buffer_row_length(0)
+            .buffer_image_height(0)
+            .image_subresource(vk::ImageSubresourceLayers {
+                aspect_mask: vk::ImageAspectFlags::COLOR,
+                mip_level: 0,
+                base_array_layer: 0,
+                layer_count: 1,
+            })
+            .image_offset(vk::Offset3D { x: 0, y: 0, z: 0 })
+            .image_extent(vk::Extent3D {
+                width: self.extent.width,
+                height: self.extent.height,
+                depth: 1,
+            });
+        device.cmd_copy_image_to_buffer(
+            cmd_buf,
+            self.image,
+            vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+            self.readback_buf,
+            &[region],
+        );
+
+        let barrier = vk::BufferMemoryBarrierBuilder::new()
+            .src_access_mask(vk::AccessFlags::TRANSFER_WRITE)
+            .dst_access_mask(vk::AccessFlags::HOST_READ)
+            .src_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+            .dst_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+            .buffer(self.readback_buf)
+            .offset(0)
+            .size(vk::WHOLE_SIZE);
+        device.cmd_pipeline_barrier(
+            cmd_buf,
+            vk::PipelineStageFlags::TRANSFER,
+            vk::PipelineStageFlags::HOST,
+            vk::DependencyFlags::empty(),
+            &[],
+            &[barrier],
+            &[],
+        );
+    }
+
+    /// Reads the image copied by [`Self::cmd_copy_to_readback`] once the commands have
+    /// finished.
+    pub unsafe fn read(&self) -> RgbaImage {
+        let size = (self.extent.width * self.extent.height * 4) as usize;
+        let mut pixels = vec![0; size];
+        self.readback_alloc
+            .read(pixels.as_mut_ptr() as *mut c_void, size);
+
+        if matches!(
+            self.format,
+            vk::Format::B8G8R8A8_SRGB | vk::Format::B8G8R8A8_UNORM
+        ) {
+            for pixel in pixels.chunks_exact_mut(4) {
+                pixel.swap(0, 2);
+            }
+        }
+
+        RgbaImage::from_raw(self.extent.width, self.extent.height, pixels)
+            .expect("readback buffer holds the whole image")
+    }
+
+    pub unsafe fn destroy(&self, device: &DeviceLoader, allocator: &mut Allocator) {
+        device.destroy_buffer(self.readback_buf, None);
+        allocator.free(&self.readback_alloc);
+        device.destroy_framebuffer(self.framebuffer, None);
+        self.depth_buffer.destroy(device, allocator);
+        device.destroy_image_view(self.image_view, None);
+        device.destroy_image(self.image, None);
+        allocator.free(&self.allocation);
+    }
+}
@@ -3,10 +3,18 @@ use std::os::raw::c_char;
 
 use erupt::{vk, InstanceLoader};
 
+/// Colour format rendered to when there is no surface to take the format from.
+pub const OFFSCREEN_FORMAT: vk::SurfaceFormatKHR = vk::SurfaceFormatKHR {
+    format: vk::Format::R8G8B8A8_SRGB,
+    color_space: vk::ColorSpaceKHR::SRGB_NONLINEAR_KHR,
+};
+
 #[derive(Clone)]
 pub struct PhysicalDevice {
     pub handle: vk::PhysicalDevice,
     pub graphics_queue_family: u32,
+    /// Family of queues only doing transfers, which uploads run on where there is one.
+    pub transfer_queue_family: Option<u32>,
     pub depth_format: vk::Format,
     pub properties: vk::PhysicalDeviceProperties,
     pub features: vk::PhysicalDeviceFeatures,
@@ -24,9 +32,11 @@ pub struct SurfaceProperties {
 }
 
 impl PhysicalDevice {
+    /// Picks the device to render with. Without a `surface` any device with a graphics queue
+    /// will do and the surface properties are those of offscreen rendering.
     pub unsafe fn new(
         instance: &InstanceLoader,
-        surface: vk::SurfaceKHR,
+        surface: Option<vk::SurfaceKHR>,
         required_extensions: &[*const c_char],
     ) -> PhysicalDevice {
         instance
@@ -34,50 +44,46 @@ impl PhysicalDevice {
             .unwrap()
             .into_iter()
             .filter_map(|physical_device| {
-                let graphics_queue_family = match instance
-                    .get_physical_device_queue_family_properties(physical_device, None)
-                    .into_iter()
-                    .enumerate()
-                    .position(|(i, queue_family_properties)| {
+                let queue_families =
+                    instance.get_physical_device_queue_family_properties(physical_device, None);
+                let graphics_queue_family = match queue_families.iter().enumerate().position(
+                    |(i, queue_family_properties)| {
                         queue_family_properties
                             .queue_flags
                             .contains(vk::QueueFlags::GRAPHICS)
-                            && instance
-                                .get_physical_device_surface_support_khr(
-                                    physical_device,
-                                    i as u32,
-                                    surface,
-                                )
-                                .unwrap()
-                    }) {
+                            && surface.map_or(true, |surface| {
+                                instance
+                                    .get_physical_device_surface_support_khr(
+                                        physical_device,
+                                        i as u32,
+                                        surface,
+                                    )
+                                    .unwrap()
+                            })
+                    },
+                ) {
                     Some(queue_family) => queue_family as u32,
                     None => return None,
                 };
-
-                let formats = instance
-                    .get_physical_device_surface_formats_khr(physical_device, surface, None)
-                    .unwrap();
-
-                let surface_format = match formats
+                let transfer_queue_family = queue_families
                     .iter()
-                    .find(|surface_format| {
-                        (surface_format.format == vk::Format::B8G8R8A8_SRGB
-                            || surface_format.format == vk::Format::R8G8B8A8_SRGB)
-                            && surface_format.color_space == vk::ColorSpaceKHR::SRGB_NONLINEAR_KHR
+                    .position(|properties| {
+                        properties.queue_flags.contains(vk::QueueFlags::TRANSFER)
+                            && !properties
+                                .queue_flags
+                                .intersects(vk::QueueFlags::GRAPHICS | vk::QueueFlags::COMPUTE)
                     })
-                    .or_else(|| formats.get(0))
-                {
-                    Some(surface_format) => *surface_format,
-                    None => return None,
+                    .map(|queue_family| queue_family as u32);
+
+                let (surface_format, present_mode, surface_capabilities) = match surface {
+                    Some(surface) => surface_properties(instance, physical_device, surface)?,
+                    None => (
+                        OFFSCREEN_FORMAT,
+                        vk::PresentModeKHR::FIFO_KHR,
+                        vk::SurfaceCapabilitiesKHR::default(),
+                    ),
                 };
 
-                let present_mode = instance
-                    .get_physical_device_surface_present_modes_khr(physical_device, surface, None)
-                    .unwrap()
-                    .into_iter()
-                    .find(|present_mode| present_mode == &vk::PresentModeKHR::MAILBOX_KHR)
-                    .unwrap_or(vk::PresentModeKHR::FIFO_KHR);
-
                 let supported_device_extensions = instance
                     .enumerate_device_extension_properties(physical_device, None, None)
                     .unwrap();
@@ -98,10 +104,6 @@ impl PhysicalDevice {
 
                 let properties = instance.get_physical_device_properties(physical_device);
 
-                let surface_capabilities = instance
-                    .get_physical_device_surface_capabilities_khr(physical_device, surface)
-                    .expect("failed to query surface capabilities");
-
                 let depth_format = find_depth_format(&instance, physical_device)
                     .expect("failed to find supported depth format");
 
@@ -111,6 +113,7 @@ impl PhysicalDevice {
                 Some(PhysicalDevice {
                     handle: physical_device,
                     graphics_queue_family,
+                    transfer_queue_family,
                     surface_format,
                     depth_format,
                     surface_capabilities,
@@ -129,6 +132,57 @@ impl PhysicalDevice {
             )
             .expect("a Vulkan supported device and OS are required to run")
     }
+
+    /// Whether optimally tiled images of `format` can be blitted between with linear filtering,
+    /// as generating mipmaps takes.
+    pub unsafe fn supports_linear_blit(
+        &self,
+        instance: &InstanceLoader,
+        format: vk::Format,
+    ) -> bool {
+        let props = instance.get_physical_device_format_properties(self.handle, format);
+        props.optimal_tiling_features.contains(
+            vk::FormatFeatureFlags::BLIT_SRC
+                | vk::FormatFeatureFlags::BLIT_DST
+                | vk::FormatFeatureFlags::SAMPLED_IMAGE_FILTER_LINEAR,
+        )
+    }
+}
+
+unsafe fn surface_properties(
+    instance: &InstanceLoader,
+    physical_device: vk::PhysicalDevice,
+    surface: vk::SurfaceKHR,
+) -> Option<(
+    vk::SurfaceFormatKHR,
+    vk::PresentModeKHR,
+    vk::SurfaceCapabilitiesKHR,
+)> {
+    let formats = instance
+        .get_physical_device_surface_formats_khr(physical_device, surface, None)
+        .unwrap();
+
+    let surface_format = *formats
+        .iter()
+        .find(|surface_format| {
+            (surface_format.format == vk::Format::B8G8R8A8_SRGB
+                || surface_format.format == vk::Format::R8G8B8A8_SRGB)
+                && surface_format.color_space == vk::ColorSpaceKHR::SRGB_NONLINEAR_KHR
+        })
+        .or_else(|| formats.get(0))?;
+
+    let present_mode = instance
+        .get_physical_device_surface_present_modes_khr(physical_device, surface, None)
+        .unwrap()
+        .into_iter()
+        .find(|present_mode| present_mode == &vk::PresentModeKHR::MAILBOX_KHR)
+        .unwrap_or(vk::PresentModeKHR::FIFO_KHR);
+
+    let surface_capabilities = instance
+        .get_physical_device_surface_capabilities_khr(physical_device, surface)
+        .expect("failed to query surface capabilities");
+
+    Some((surface_format, present_mode, surface_capabilities))
 }
 
 pub unsafe fn find_depth_format(
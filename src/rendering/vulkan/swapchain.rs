@@ -7,6 +7,7 @@ use erupt::{utils::VulkanResult, vk, DeviceLoader, InstanceLoader};
 use smallvec::SmallVec;
 
 use crate::logging::debug;
+use crate::rendering::vulkan::allocator::{Allocation, Allocator};
 use crate::rendering::vulkan::memory;
 use crate::rendering::vulkan::physical_device::PhysicalDevice;
 
@@ -25,6 +26,7 @@ impl Swapchain {
     pub fn new(
         device: &DeviceLoader,
         physical_device: &PhysicalDevice,
+        allocator: &mut Allocator,
         present_queue: vk::Queue,
         surface: vk::SurfaceKHR,
         surface_size: &vk::Extent2D,
@@ -46,7 +48,7 @@ impl Swapchain {
 
             let depth_buffer = DepthBuffer::new(
                 device,
-                physical_device,
+                allocator,
                 physical_device.depth_format,
                 &image_extent,
             );
@@ -101,10 +103,11 @@ impl Swapchain {
         instance: &InstanceLoader,
         device: &DeviceLoader,
         physical_device: &PhysicalDevice,
+        allocator: &mut Allocator,
         surface: Option<vk::SurfaceKHR>,
         surface_size: &vk::Extent2D,
     ) {
-        self.release_dependents(device);
+        self.release_dependents(device, allocator);
 
         let surface = if let Some(surface) = surface {
             surface
@@ -123,7 +126,7 @@ impl Swapchain {
         );
         let new_depth_buffer = DepthBuffer::new(
             device,
-            physical_device,
+            allocator,
             physical_device.depth_format,
             &new_image_extent,
         );
@@ -176,8 +179,13 @@ impl Swapchain {
         &self.image_extent
     }
 
-    pub unsafe fn destroy(&mut self, device: &DeviceLoader, instance: &InstanceLoader) {
-        self.release_dependents(device);
+    pub unsafe fn destroy(
+        &mut self,
+        device: &DeviceLoader,
+        instance: &InstanceLoader,
+        allocator: &mut Allocator,
+    ) {
+        self.release_dependents(device, allocator);
         device.destroy_swapchain_khr(self.handle, None);
         instance.destroy_surface_khr(self.surface, None);
 
@@ -185,8 +193,8 @@ impl Swapchain {
         self.surface = vk::SurfaceKHR::null();
     }
 
-    unsafe fn release_dependents(&mut self, device: &DeviceLoader) {
-        self.depth_buffer.destroy(device);
+    unsafe fn release_dependents(&mut self, device: &DeviceLoader, allocator: &mut Allocator) {
+        self.depth_buffer.destroy(device, allocator);
 
         if let Some(framebuffers) = self.framebuffers.borrow().deref() {
             for fb in framebuffers {
@@ -312,40 +320,48 @@ unsafe fn create_framebuffers(
 }
 
 pub struct DepthBuffer {
-    pub memory: vk::DeviceMemory,
+    pub allocation: Allocation,
     pub image: vk::Image,
     pub image_view: vk::ImageView,
 }
 
 impl DepthBuffer {
-    pub fn destroy(&self, device: &DeviceLoader) {
+    pub fn destroy(&self, device: &DeviceLoader, allocator: &mut Allocator) {
         unsafe {
             device.destroy_image_view(self.image_view, None);
             device.destroy_image(self.image, None);
-            device.free_memory(self.memory, None);
         }
+        allocator.free(&self.allocation);
     }
 }
 
 impl DepthBuffer {
     pub fn new(
         device: &DeviceLoader,
-        physical_device: &PhysicalDevice,
+        allocator: &mut Allocator,
         format: vk::Format,
         extent: &vk::Extent2D,
     ) -> Self {
         unsafe {
-            let (depth_buffer_image, depth_buffer_mem) =
-                memory::create_depth_buffer(device, physical_device, format, &extent);
+            let (depth_buffer_image, depth_buffer_alloc) = allocator.create_image(
+                device,
+                extent.width,
+                extent.height,
+                format,
+                vk::ImageTiling::OPTIMAL,
+                vk::ImageUsageFlags::DEPTH_STENCIL_ATTACHMENT,
+                vk::MemoryPropertyFlags::DEVICE_LOCAL,
+            );
             let depth_buffer_view = memory::create_image_view(
                 device,
                 depth_buffer_image,
                 format,
                 vk::ImageAspectFlags::DEPTH,
+                1,
             );
 
             Self {
-                memory: depth_buffer_mem,
+                allocation: depth_buffer_alloc,
                 image: depth_buffer_image,
                 image_view: depth_buffer_view,
             }
@@ -0,0 +1,593 @@
+use std::ffi::c_void;
+use std::mem::size_of_val;
+use std::ops::Range;
+use std::sync::Arc;
+
+use erupt::{vk, vk1_0::CommandBufferResetFlags, DeviceLoader};
+use image::RgbaImage;
+
+use crate::rendering::vulkan::{
+    allocator::{Allocation, Allocator},
+    memory,
+    sync_pool::SyncPool,
+};
+
+/// Size the staging buffer starts at, it grows when a single upload doesn't fit.
+const STAGING_SIZE: usize = 32 * 1024 * 1024;
+/// Alignment of the data in the staging buffer, enough for copying any texel or index.
+const STAGING_ALIGNMENT: usize = 16;
+
+/// Layout of an image with the accesses and pipeline stages it is used by there.
+type ImageState = (vk::ImageLayout, vk::AccessFlags, vk::PipelineStageFlags);
+
+const UNDEFINED: ImageState = (
+    vk::ImageLayout::UNDEFINED,
+    vk::AccessFlags::empty(),
+    vk::PipelineStageFlags::TOP_OF_PIPE,
+);
+const TRANSFER_DST: ImageState = (
+    vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+    vk::AccessFlags::TRANSFER_WRITE,
+    vk::PipelineStageFlags::TRANSFER,
+);
+const TRANSFER_SRC: ImageState = (
+    vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+    vk::AccessFlags::TRANSFER_READ,
+    vk::PipelineStageFlags::TRANSFER,
+);
+
+/// Copies data to device local buffers and images. The copies are recorded into one command
+/// buffer and submitted together with [`Uploader::submit`], the data waiting in a persistent
+/// staging buffer until then. Mip levels are blitted on the graphics queue, in a command buffer
+/// of its own when uploads have a dedicated queue.
+pub struct Uploader {
+    queue: vk::Queue,
+    graphics_queue: vk::Queue,
+    /// Families of the upload and the graphics queue, the same unless there is a dedicated
+    /// transfer queue family.
+    queue_families: [u32; 2],
+    cmd_pool: vk::CommandPool,
+    cmd_buf: vk::CommandBuffer,
+    recording: bool,
+    graphics_cmd_pool: vk::CommandPool,
+    /// Blits submitted after the uploads, only used with a dedicated upload queue.
+    graphics_cmd_buf: vk::CommandBuffer,
+    graphics_recording: bool,
+    /// Signalled once the last submitted uploads are done.
+    fence: vk::Fence,
+    /// Signalled along with the fence for the graphics queue to wait on, when uploading on a
+    /// queue of its own.
+    semaphore: vk::Semaphore,
+    /// Signalled once the graphics queue is done waiting on the semaphore, so it can be
+    /// signalled again.
+    graphics_fence: vk::Fence,
+    staging_buf: vk::Buffer,
+    staging_alloc: Allocation,
+    staging: StagingArena,
+}
+
+impl Uploader {
+    /// Creates an uploader submitting to `queue` of `queue_family`, for resources used on the
+    /// graphics queue.
+    pub unsafe fn new(
+        device: &Arc<DeviceLoader>,
+        allocator: &mut Allocator,
+        sync_pool: &mut SyncPool,
+        (queue, queue_family): (vk::Queue, u32),
+        (graphics_queue, graphics_queue_family): (vk::Queue, u32),
+    ) -> Self {
+        let cmd_pool = memory::create_command_pool(device, queue_family);
+        let cmd_buf = memory::create_command_buffers(device, cmd_pool, 1)[0];
+        let graphics_cmd_pool = memory::create_command_pool(device, graphics_queue_family);
+        let graphics_cmd_buf = memory::create_command_buffers(device, graphics_cmd_pool, 1)[0];
+        let (staging_buf, staging_alloc) = create_staging_buffer(device, allocator, STAGING_SIZE);
+
+        Self {
+            queue,
+            graphics_queue,
+            queue_families: [queue_family, graphics_queue_family],
+            cmd_pool,
+            cmd_buf,
+            recording: false,
+            graphics_cmd_pool,
+            graphics_cmd_buf,
+            graphics_recording: false,
+            fence: sync_pool.fence(device, true),
+            semaphore: sync_pool.semaphore(device),
+            graphics_fence: sync_pool.fence(device, true),
+            staging_buf,
+            staging_alloc,
+            staging: StagingArena::new(STAGING_SIZE),
+        }
+    }
+
+    /// Creates a device local buffer and records copying `data` to it.
+    pub unsafe fn upload_buffer<T>(
+        &mut self,
+        device: &DeviceLoader,
+        allocator: &mut Allocator,
+        data: &[T],
+        usage: vk::BufferUsageFlags,
+    ) -> (vk::Buffer, Allocation) {
+        let size = size_of_val(data);
+        let offset = self.stage(device, allocator, data.as_ptr() as *const c_void, size);
+
+        let buffer_info = vk::BufferCreateInfoBuilder::new()
+            .size(size as vk::DeviceSize)
+            .usage(vk::BufferUsageFlags::TRANSFER_DST | usage)
+            .sharing_mode(self.sharing_mode())
+            .queue_family_indices(&self.queue_families);
+        let buffer = device
+            .create_buffer(&buffer_info, None)
+            .expect("Failed to create buffer");
+
+        let mem_reqs = device.get_buffer_memory_requirements(buffer);
+        let allocation = allocator.allocate(
+            device,
+            &mem_reqs,
+            vk::MemoryPropertyFlags::DEVICE_LOCAL,
+            true,
+        );
+        device
+            .bind_buffer_memory(buffer, allocation.memory, allocation.offset)
+            .expect("Failed to bind memory");
+
+        let region = vk::BufferCopyBuilder::new()
+            .src_offset(offset as vk::DeviceSize)
+            .dst_offset(0)
+            .size(size as vk::DeviceSize);
+        device.cmd_copy_buffer(self.cmd_buf, self.staging_buf, buffer, &[region]);
+
+        (buffer, allocation)
+    }
+
+    /// Creates a device local image with a full mip chain to sample from. `levels` are copied
+    /// to the first mip levels and the rest are blitted from the last of them, which takes a
+    /// format supporting linear blits.
+    pub unsafe fn upload_image(
+        &mut self,
+        device: &DeviceLoader,
+        allocator: &mut Allocator,
+        levels: &[&RgbaImage],
+        format: vk::Format,
+    ) -> (vk::Image, Allocation) {
+        let (width, height) = levels[0].dimensions();
+        let mip_levels = mip_level_count(width, height);
+        let given = levels.len() as u32;
+
+        let image_info = memory::image_info(
+            width,
+            height,
+            mip_levels,
+            format,
+            vk::ImageTiling::OPTIMAL,
+            vk::ImageUsageFlags::TRANSFER_SRC
+                | vk::ImageUsageFlags::TRANSFER_DST
+                | vk::ImageUsageFlags::SAMPLED,
+        )
+        .sharing_mode(self.sharing_mode())
+        .queue_family_indices(&self.queue_families);
+        let image = device
+            .create_image(&image_info, None)
+            .expect("failed to create texture image");
+
+        let mem_reqs = device.get_image_memory_requirements(image);
+        let allocation = allocator.allocate(
+            device,
+            &mem_reqs,
+            vk::MemoryPropertyFlags::DEVICE_LOCAL,
+            false,
+        );
+        device
+            .bind_image_memory(image, allocation.memory, allocation.offset)
+            .expect("failed to bind image memory");
+
+        self.begin(device);
+        self.cmd_transition(
+            device,
+            self.cmd_buf,
+            image,
+            0..mip_levels,
+            UNDEFINED,
+            TRANSFER_DST,
+        );
+        for (level, pixels) in levels.iter().enumerate() {
+            let pixels = pixels.as_raw();
+            let offset = self.stage(
+                device,
+                allocator,
+                pixels.as_ptr() as *const c_void,
+                pixels.len(),
+            );
+
+            let (level_width, level_height) = mip_size(width, height, level as u32);
+            let region = vk::BufferImageCopyBuilder::new()
+                .buffer_offset(offset as vk::DeviceSize)
+                .buffer_row_length(0)
+                .buffer_image_height(0)
+                .image_subresource(color_layers(level as u32))
+                .image_offset(vk::Offset3D { x: 0, y: 0, z: 0 })
+                .image_extent(vk::Extent3D {
+                    width: level_width,
+                    height: level_height,
+                    depth: 1,
+                });
+            device.cmd_copy_buffer_to_image(
+                self.cmd_buf,
+                self.staging_buf,
+                image,
+                vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                &[region],
+            );
+        }
+
+        // Levels blitted from are read before being sampled, the others are done with
+        let cmd_buf = if given < mip_levels {
+            self.blit_cmd_buf(device)
+        } else {
+            self.cmd_buf
+        };
+        let sampled = self.sampled(cmd_buf);
+        for level in 0..mip_levels {
+            if level >= given {
+                self.cmd_blit_level(device, cmd_buf, image, width, height, level);
+                let blitted_from = level - 1..level;
+                self.cmd_transition(device, cmd_buf, image, blitted_from, TRANSFER_SRC, sampled);
+            }
+            let next = if level + 1 >= given && level + 1 < mip_levels {
+                TRANSFER_SRC
+            } else {
+                sampled
+            };
+            self.cmd_transition(device, cmd_buf, image, level..level + 1, TRANSFER_DST, next);
+        }
+
+        (image, allocation)
+    }
+
+    /// Submits the uploads recorded so far. Work submitted to the graphics queue afterwards
+    /// waits for them to finish.
+    pub unsafe fn submit(&mut self, device: &DeviceLoader) {
+        if !self.recording {
+            return;
+        }
+        self.recording = false;
+
+        if !self.dedicated() {
+            let barrier = vk::MemoryBarrierBuilder::new()
+                .src_access_mask(vk::AccessFlags::TRANSFER_WRITE)
+                .dst_access_mask(
+                    vk::AccessFlags::VERTEX_ATTRIBUTE_READ | vk::AccessFlags::INDEX_READ,
+                );
+            device.cmd_pipeline_barrier(
+                self.cmd_buf,
+                vk::PipelineStageFlags::TRANSFER,
+                vk::PipelineStageFlags::VERTEX_INPUT,
+                vk::DependencyFlags::empty(),
+                &[barrier],
+                &[],
+                &[],
+            );
+        }
+        device
+            .end_command_buffer(self.cmd_buf)
+            .expect("failed to end command buffer");
+        let blits = self.graphics_recording as usize;
+        if self.graphics_recording {
+            self.graphics_recording = false;
+            device
+                .end_command_buffer(self.graphics_cmd_buf)
+                .expect("failed to end command buffer");
+        }
+        let graphics_cmd_bufs = [self.graphics_cmd_buf];
+
+        let cmd_bufs = [self.cmd_buf];
+        let semaphores = [self.semaphore];
+        let mut submit_info = vk::SubmitInfoBuilder::new().command_buffers(&cmd_bufs);
+        if self.dedicated() {
+            submit_info = submit_info.signal_semaphores(&semaphores);
+        }
+        device.reset_fences(&[self.fence]).unwrap();
+        device
+            .queue_submit(self.queue, &[submit_info], self.fence)
+            .expect("failed to submit uploads");
+
+        if self.dedicated() {
+            // Blits the mip levels and holds back everything submitted to the graphics queue
+            // later
+            let wait_stages = [vk::PipelineStageFlags::ALL_COMMANDS];
+            let wait_info = vk::SubmitInfoBuilder::new()
+                .wait_semaphores(&semaphores)
+                .wait_dst_stage_mask(&wait_stages)
+                .command_buffers(&graphics_cmd_bufs[..blits]);
+            device.reset_fences(&[self.graphics_fence]).unwrap();
+            device
+                .queue_submit(self.graphics_queue, &[wait_info], self.graphics_fence)
+                .expect("failed to submit to queue");
+        }
+    }
+
+    /// Destroys the command pools and the staging buffer, the fences and the semaphore belong
+    /// to the sync pool.
+    pub unsafe fn destroy(&self, device: &DeviceLoader, allocator: &mut Allocator) {
+        device.destroy_command_pool(self.cmd_pool, None);
+        device.destroy_command_pool(self.graphics_cmd_pool, None);
+        device.destroy_buffer(self.staging_buf, None);
+        allocator.free(&self.staging_alloc);
+    }
+
+    /// Copies `size` bytes from `src` to the staging buffer and returns their offset there.
+    /// When the buffer is full, the uploads so far are submitted and waited for before its
+    /// space is reused.
+    unsafe fn stage(
+        &mut self,
+        device: &DeviceLoader,
+        allocator: &mut Allocator,
+        src: *const c_void,
+        size: usize,
+    ) -> usize {
+        self.begin(device);
+        let offset = match self.staging.reserve(size) {
+            Some(offset) => offset,
+            None => {
+                self.submit(device);
+                if size > self.staging.capacity {
+                    self.wait(device);
+                    device.destroy_buffer(self.staging_buf, None);
+                    allocator.free(&self.staging_alloc);
+
+                    let capacity = size.next_power_of_two();
+                    (self.staging_buf, self.staging_alloc) =
+                        create_staging_buffer(device, allocator, capacity);
+                    self.staging = StagingArena::new(capacity);
+                }
+                self.begin(device);
+                self.staging
+                    .reserve(size)
+                    .expect("staging buffer fits the upload")
+            }
+        };
+        self.staging_alloc.write_at(offset, src, size);
+
+        offset
+    }
+
+    /// Starts recording, once the previous uploads are done with the command buffer and the
+    /// staging buffer.
+    unsafe fn begin(&mut self, device: &DeviceLoader) {
+        if self.recording {
+            return;
+        }
+
+        self.wait(device);
+        self.staging.rewind();
+        begin_one_time(device, self.cmd_buf);
+        self.recording = true;
+    }
+
+    /// Command buffer recording on the graphics queue, which can blit unlike a transfer only
+    /// queue. Takes [`Self::begin`] first.
+    unsafe fn blit_cmd_buf(&mut self, device: &DeviceLoader) -> vk::CommandBuffer {
+        if !self.dedicated() {
+            return self.cmd_buf;
+        }
+
+        if !self.graphics_recording {
+            // Beginning the uploads waited for the last blits
+            begin_one_time(device, self.graphics_cmd_buf);
+            self.graphics_recording = true;
+        }
+        self.graphics_cmd_buf
+    }
+
+    unsafe fn wait(&self, device: &DeviceLoader) {
+        device
+            .wait_for_fences(&[self.fence, self.graphics_fence], true, u64::MAX)
+            .expect("failed to wait for uploads");
+    }
+
+    /// Records moving `levels` of `image` from one layout to another.
+    unsafe fn cmd_transition(
+        &self,
+        device: &DeviceLoader,
+        cmd_buf: vk::CommandBuffer,
+        image: vk::Image,
+        levels: Range<u32>,
+        (old_layout, src_access, src_stage): ImageState,
+        (new_layout, dst_access, dst_stage): ImageState,
+    ) {
+        let barrier = vk::ImageMemoryBarrierBuilder::new()
+            .old_layout(old_layout)
+            .new_layout(new_layout)
+            .src_access_mask(src_access)
+            .dst_access_mask(dst_access)
+            .src_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+            .dst_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+            .image(image)
+            .subresource_range(vk::ImageSubresourceRange {
+                aspect_mask: vk::ImageAspectFlags::COLOR,
+                base_mip_level: levels.start,
+                level_count: levels.end - levels.start,
+                base_array_layer: 0,
+                layer_count: 1,
+            });
+        device.cmd_pipeline_barrier(
+            cmd_buf,
+            src_stage,
+            dst_stage,
+            vk::DependencyFlags::empty(),
+            &[],
+            &[],
+            &[barrier],
+        );
+    }
+
+    /// Records halving mip `level - 1` of a `width` by `height` image into `level`.
+    unsafe fn cmd_blit_level(
+        &self,
+        device: &DeviceLoader,
+        cmd_buf: vk::CommandBuffer,
+        image: vk::Image,
+        width: u32,
+        height: u32,
+        level: u32,
+    ) {
+        let corner = |level| {
+            let (width, height) = mip_size(width, height, level);
+            vk::Offset3D {
+                x: width as i32,
+                y: height as i32,
+                z: 1,
+            }
+        };
+        let origin = vk::Offset3D { x: 0, y: 0, z: 0 };
+        let blit = vk::ImageBlitBuilder::new()
+            .src_subresource(color_layers(level - 1))
+            .src_offsets([origin, corner(level - 1)])
+            .dst_subresource(color_layers(level))
+            .dst_offsets([origin, corner(level)]);
+        device.cmd_blit_image(
+            cmd_buf,
+            image,
+            vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+            image,
+            vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+            &[blit],
+            vk::Filter::LINEAR,
+        );
+    }
+
+    /// State of images sampled after `cmd_buf`. A transfer queue can't wait for fragment
+    /// shaders, the semaphore orders the reads then.
+    fn sampled(&self, cmd_buf: vk::CommandBuffer) -> ImageState {
+        if self.dedicated() && cmd_buf == self.cmd_buf {
+            (
+                vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+                vk::AccessFlags::empty(),
+                vk::PipelineStageFlags::BOTTOM_OF_PIPE,
+            )
+        } else {
+            (
+                vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+                vk::AccessFlags::SHADER_READ,
+                vk::PipelineStageFlags::FRAGMENT_SHADER,
+            )
+        }
+    }
+
+    fn dedicated(&self) -> bool {
+        self.queue_families[0] != self.queue_families[1]
+    }
+
+    /// Resources are shared between the two queue families rather than transferring their
+    /// ownership.
+    fn sharing_mode(&self) -> vk::SharingMode {
+        if self.dedicated() {
+            vk::SharingMode::CONCURRENT
+        } else {
+            vk::SharingMode::EXCLUSIVE
+        }
+    }
+}
+
+unsafe fn begin_one_time(device: &DeviceLoader, cmd_buf: vk::CommandBuffer) {
+    device
+        .reset_command_buffer(cmd_buf, CommandBufferResetFlags::empty())
+        .unwrap();
+    let begin_info = vk::CommandBufferBeginInfoBuilder::new()
+        .flags(vk::CommandBufferUsageFlags::ONE_TIME_SUBMIT);
+    device
+        .begin_command_buffer(cmd_buf, &begin_info)
+        .expect("failed to begin command buffer");
+}
+
+unsafe fn create_staging_buffer(
+    device: &DeviceLoader,
+    allocator: &mut Allocator,
+    size: usize,
+) -> (vk::Buffer, Allocation) {
+    allocator.allocate_buffer(
+        device,
+        size,
+        vk::BufferUsageFlags::TRANSFER_SRC,
+        vk::MemoryPropertyFlags::HOST_VISIBLE | vk::MemoryPropertyFlags::HOST_COHERENT,
+    )
+}
+
+fn color_layers(mip_level: u32) -> vk::ImageSubresourceLayers {
+    vk::ImageSubresourceLayers {
+        aspect_mask: vk::ImageAspectFlags::COLOR,
+        mip_level,
+        base_array_layer: 0,
+        layer_count: 1,
+    }
+}
+
+/// Number of mip levels of a `width` by `height` image, halving it down to a single pixel.
+pub fn mip_level_count(width: u32, height: u32) -> u32 {
+    u32::BITS - width.max(height).max(1).leading_zeros()
+}
+
+/// Size of mip `level` of a `width` by `height` image.
+pub fn mip_size(width: u32, height: u32, level: u32) -> (u32, u32) {
+    ((width >> level).max(1), (height >> level).max(1))
+}
+
+/// Space in the staging buffer handed out from the start onwards. It is only rewound to the
+/// start once every upload reading it is done, nothing is reclaimed while some are in flight.
+#[derive(Debug)]
+struct StagingArena {
+    capacity: usize,
+    head: usize,
+}
+
+impl StagingArena {
+    fn new(capacity: usize) -> Self {
+        Self { capacity, head: 0 }
+    }
+
+    /// Offset of `size` bytes at the head, `None` when they don't fit before the end.
+    fn reserve(&mut self, size: usize) -> Option<usize> {
+        let offset = (self.head + STAGING_ALIGNMENT - 1) / STAGING_ALIGNMENT * STAGING_ALIGNMENT;
+        if offset + size > self.capacity {
+            return None;
+        }
+
+        self.head = offset + size;
+        Some(offset)
+    }
+
+    fn rewind(&mut self) {
+        self.head = 0;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_staging_arena() {
+        let mut arena = StagingArena::new(64);
+
+        assert_eq!(arena.reserve(10), Some(0));
+        assert_eq!(arena.reserve(20), Some(16));
+        assert_eq!(arena.reserve(20), None);
+        assert_eq!(arena.reserve(16), Some(48));
+
+        arena.rewind();
+        assert_eq!(arena.reserve(64), Some(0));
+        assert_eq!(arena.reserve(0), Some(64));
+    }
+
+    #[test]
+    fn test_mip_levels() {
+        assert_eq!(mip_level_count(1, 1), 1);
+        assert_eq!(mip_level_count(256, 256), 9);
+        assert_eq!(mip_level_count(300, 20), 9);
+
+        assert_eq!(mip_size(300, 20, 0), (300, 20));
+        assert_eq!(mip_size(300, 20, 5), (9, 1));
+        assert_eq!(mip_size(300, 20, 8), (1, 1));
+    }
+}
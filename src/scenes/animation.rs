@@ -0,0 +1,67 @@
+use nalgebra::{Point3, Vector3};
+
+use crate::math::ease_in_out;
+
+/// Eased movement between two points along a vertical arc.
+#[derive(Clone, Debug)]
+pub struct Tween {
+    from: Point3<f32>,
+    to: Point3<f32>,
+    /// Height of the top of the arc above the straight line between the ends.
+    arc_height: f32,
+    duration: f32,
+    elapsed: f32,
+}
+
+impl Tween {
+    pub fn new(from: Point3<f32>, to: Point3<f32>, arc_height: f32, duration: f32) -> Self {
+        Self {
+            from,
+            to,
+            arc_height,
+            duration,
+            elapsed: 0.0,
+        }
+    }
+
+    /// Moves the tween `time_delta` seconds on and returns the new position.
+    pub fn advance(&mut self, time_delta: f32) -> Point3<f32> {
+        self.elapsed = (self.elapsed + time_delta).min(self.duration);
+        self.position()
+    }
+
+    pub fn position(&self) -> Point3<f32> {
+        let t = if self.duration > 0.0 {
+            ease_in_out(self.elapsed / self.duration)
+        } else {
+            1.0
+        };
+        let lift = self.arc_height * 4.0 * t * (1.0 - t);
+
+        self.from + (self.to - self.from) * t + Vector3::y() * lift
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.elapsed >= self.duration
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_tween_arc() {
+        let from = Point3::origin();
+        let to = Point3::new(2.0, 0.0, 0.0);
+        let mut tween = Tween::new(from, to, 0.5, 1.0);
+
+        assert_eq!(tween.position(), from);
+        let middle = tween.advance(0.5);
+        assert!((middle - Point3::new(1.0, 0.5, 0.0)).norm() < 1e-5);
+        assert!(!tween.is_finished());
+
+        assert_eq!(tween.advance(1.0), to);
+        assert!(tween.is_finished());
+    }
+}
@@ -0,0 +1,213 @@
+use nalgebra::{Point3, Vector3, Vector4};
+
+use crate::{
+    chess::{Color, Piece, Position, Square},
+    picking::{Aabb, Ray},
+    rendering::mesh::BBox,
+    transform::Transform,
+};
+
+/// Overlays are lifted above the cells to stay visible over them.
+const OVERLAY_HEIGHT: f32 = 0.002;
+/// Distance in cells from the edge files to the trays of captured pieces.
+const TRAY_OFFSET: f32 = 1.25;
+/// Distance in cells between captured pieces in a tray.
+const TRAY_SPACING: f32 = 0.5;
+
+/// What a ray cast at the board hit first.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Pick {
+    Piece(Square),
+    Square(Square),
+}
+
+impl Pick {
+    pub fn square(self) -> Square {
+        match self {
+            Pick::Piece(square) | Pick::Square(square) => square,
+        }
+    }
+}
+
+/// Maps board squares to world space. Files run along x and ranks along -z with the centre of a1
+/// at the origin, one cell per square. Seen from white's side with y up, a1 is then in the bottom
+/// left corner like on a real board.
+#[derive(Clone, Debug)]
+pub struct BoardLayout {
+    cell_w: f32,
+    cell_l: f32,
+}
+
+impl BoardLayout {
+    pub fn from_cell(bbox: &BBox) -> Self {
+        Self {
+            cell_w: bbox.width,
+            cell_l: bbox.length,
+        }
+    }
+
+    pub fn square_center(&self, square: Square) -> Point3<f32> {
+        Point3::new(
+            self.cell_w * square.file() as f32,
+            0.0,
+            -self.cell_l * square.rank() as f32,
+        )
+    }
+
+    /// Width along x and length along z of the board together with the trays.
+    pub fn extent(&self) -> (f32, f32) {
+        (self.cell_w * (8.0 + 2.0 * TRAY_OFFSET), self.cell_l * 8.0)
+    }
+
+    /// Middle of the board, between the four central squares.
+    pub fn center(&self) -> Point3<f32> {
+        Point3::new(self.cell_w * 3.5, 0.0, -self.cell_l * 3.5)
+    }
+
+    /// The square under `point`, ignoring its height.
+    pub fn square_at(&self, point: &Point3<f32>) -> Option<Square> {
+        let file = (point.x / self.cell_w).round();
+        let rank = (-point.z / self.cell_l).round();
+        let on_board = |i: f32| (0.0..8.0).contains(&i);
+        (on_board(file) && on_board(rank)).then(|| Square::new(file as u8, rank as u8))
+    }
+
+    /// Transform of the cell mesh for `square`, the plane laid flat on the board.
+    pub fn cell_transform(&self, square: Square) -> Transform {
+        Transform::new(
+            self.square_center(square),
+            Vector4::new(1.0, 0.0, 0.0, 90.0),
+            1.0,
+        )
+    }
+
+    /// Transform of an overlay plane on `square`, `scale` relative to the cell.
+    pub fn overlay_transform(&self, square: Square, scale: f32) -> Transform {
+        let mut transform = self.cell_transform(square);
+        transform.position.y += OVERLAY_HEIGHT;
+        transform.scale *= scale;
+        transform
+    }
+
+    /// Transform of a piece standing on `square`. Piece meshes are modelled for a cell one unit
+    /// wide.
+    pub fn piece_transform(&self, square: Square) -> Transform {
+        self.piece_transform_at(self.square_center(square))
+    }
+
+    /// Transform of a piece standing at `position`, e.g. while it moves between squares.
+    pub fn piece_transform_at(&self, position: Point3<f32>) -> Transform {
+        Transform::new(position, Vector4::zeros(), self.piece_scale())
+    }
+
+    /// Where the `index`th captured piece of `color` rests. Black pieces line up beside the
+    /// h-file from the first rank on, white pieces beside the a-file from the eighth rank on.
+    pub fn tray_position(&self, color: Color, index: usize) -> Point3<f32> {
+        let along = index as f32 * TRAY_SPACING;
+        let (file, rank) = match color {
+            Color::White => (-TRAY_OFFSET, 7.0 - along),
+            Color::Black => (7.0 + TRAY_OFFSET, along),
+        };
+        Point3::new(self.cell_w * file, 0.0, -self.cell_l * rank)
+    }
+
+    /// World space bounds of a piece mesh with bounding box `bbox` standing on `square`.
+    pub fn piece_bounds(&self, square: Square, bbox: &BBox) -> Aabb {
+        let center = self.square_center(square);
+        let half = Vector3::new(bbox.width / 2.0, 0.0, bbox.length / 2.0) * self.piece_scale();
+        Aabb {
+            min: center - half,
+            max: center + half + Vector3::y() * bbox.height * self.piece_scale(),
+        }
+    }
+
+    /// Finds the piece or square of `position` that `ray` hits first, `piece_bbox` giving the
+    /// mesh bounds of each piece.
+    pub fn pick(
+        &self,
+        ray: &Ray,
+        position: &Position,
+        piece_bbox: impl Fn(Piece) -> BBox,
+    ) -> Option<Pick> {
+        let piece_hit = Square::all()
+            .filter_map(|square| {
+                let piece = position.piece_at(square)?;
+                let distance =
+                    ray.intersect_aabb(&self.piece_bounds(square, &piece_bbox(piece)))?;
+                Some((distance, square))
+            })
+            .min_by(|a, b| a.0.total_cmp(&b.0));
+        if let Some((_, square)) = piece_hit {
+            return Some(Pick::Piece(square));
+        }
+
+        let distance = ray.intersect_horizontal_plane(0.0)?;
+        self.square_at(&ray.at(distance)).map(Pick::Square)
+    }
+
+    fn piece_scale(&self) -> f32 {
+        self.cell_w.min(self.cell_l)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn layout() -> BoardLayout {
+        BoardLayout::from_cell(&BBox {
+            width: 1.0,
+            length: 1.0,
+            height: 0.0,
+        })
+    }
+
+    fn piece_bbox(_: Piece) -> BBox {
+        BBox {
+            width: 0.6,
+            length: 0.6,
+            height: 1.0,
+        }
+    }
+
+    #[test]
+    fn test_square_at() {
+        let board = layout();
+        for square in Square::all() {
+            let center = board.square_center(square);
+            assert_eq!(board.square_at(&center), Some(square));
+            assert_eq!(
+                board.square_at(&(center + Vector3::new(0.4, 1.0, -0.4))),
+                Some(square)
+            );
+        }
+        assert_eq!(board.square_at(&Point3::new(-0.6, 0.0, 0.0)), None);
+        assert_eq!(board.square_at(&Point3::new(0.0, 0.0, -7.6)), None);
+    }
+
+    #[test]
+    fn test_pick() {
+        let board = layout();
+        let position = Position::starting();
+        let e2 = Square::parse("e2").unwrap();
+        let e4 = Square::parse("e4").unwrap();
+
+        // Looking straight down at e4 misses every piece
+        let down = Ray::new(board.square_center(e4) + Vector3::y() * 5.0, -Vector3::y());
+        assert_eq!(
+            board.pick(&down, &position, piece_bbox),
+            Some(Pick::Square(e4))
+        );
+
+        // Looking at e4 from just behind e2 hits the pawn first
+        let eye = board.square_center(e2) + Vector3::new(0.0, 0.5, 0.5);
+        let ray = Ray::new(eye, board.square_center(e4) - eye);
+        assert_eq!(
+            board.pick(&ray, &position, piece_bbox),
+            Some(Pick::Piece(e2))
+        );
+
+        let away = Ray::new(eye, Vector3::y());
+        assert_eq!(board.pick(&away, &position, piece_bbox), None);
+    }
+}
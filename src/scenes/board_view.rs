@@ -0,0 +1,316 @@
+use nalgebra::Point3;
+
+use crate::{
+    chess::{Color, Move, MoveKind, Piece, PieceKind, Position, Square},
+    picking::Ray,
+    scenes::{animation::Tween, board::BoardLayout},
+};
+
+/// Seconds a piece takes to move between squares.
+const MOVE_TIME: f32 = 0.35;
+/// Seconds a captured piece takes to reach its tray.
+const CAPTURE_TIME: f32 = 0.5;
+/// Height of the arc a piece moves along relative to the distance travelled.
+const ARC_HEIGHT: f32 = 0.2;
+/// Height above the board that dragged pieces follow the cursor at.
+const DRAG_HEIGHT: f32 = 0.3;
+
+/// A piece drawn on the board or in a tray.
+#[derive(Clone, Debug)]
+pub struct PieceView {
+    pub piece: Piece,
+    /// Square the piece stands on or is moving to, `None` once captured.
+    pub square: Option<Square>,
+    pub position: Point3<f32>,
+    tween: Option<Tween>,
+}
+
+/// Where the pieces of a game are drawn. Follows the game position, animating the moves between
+/// positions, the pieces dragged by the player and the captured pieces going to the trays.
+pub struct BoardView {
+    board: BoardLayout,
+    position: Position,
+    pieces: Vec<PieceView>,
+    /// Number of captured pieces of each colour in the trays.
+    captured: [usize; 2],
+}
+
+impl BoardView {
+    pub fn new(board: BoardLayout, position: &Position) -> Self {
+        let mut view = Self {
+            board,
+            position: position.clone(),
+            pieces: Vec::with_capacity(32),
+            captured: [0; 2],
+        };
+        view.reset();
+
+        view
+    }
+
+    /// Brings the view to `position`. The change is animated if `last_move` leads there from the
+    /// shown position, otherwise the pieces are placed right away. Returns whether the position
+    /// changed.
+    pub fn sync(&mut self, position: &Position, last_move: Option<Move>) -> bool {
+        if &self.position == position {
+            return false;
+        }
+
+        let follows = last_move.filter(|&mv| {
+            if !self.position.legal_moves().contains(&mv) {
+                return false;
+            }
+            let mut next = self.position.clone();
+            next.make_move(mv);
+            &next == position
+        });
+        match follows {
+            Some(mv) => {
+                self.animate(mv);
+                self.position = position.clone();
+            }
+            None => {
+                self.position = position.clone();
+                self.reset();
+            }
+        }
+
+        true
+    }
+
+    /// Lifts the piece on `square` to where `ray` meets the drag height above the board.
+    pub fn drag(&mut self, square: Square, ray: &Ray) {
+        let Some(distance) = ray.intersect_horizontal_plane(DRAG_HEIGHT) else {
+            return;
+        };
+        if let Some(i) = self.index_at(square) {
+            self.pieces[i].position = ray.at(distance);
+            self.pieces[i].tween = None;
+        }
+    }
+
+    /// Moves the piece on `square` back onto it, e.g. after it was dropped on an illegal square.
+    pub fn settle(&mut self, square: Square) {
+        if let Some(i) = self.index_at(square) {
+            if self.pieces[i].position != self.board.square_center(square) {
+                self.move_to(i, square, MOVE_TIME);
+            }
+        }
+    }
+
+    pub fn advance(&mut self, time_delta: f32) {
+        for view in &mut self.pieces {
+            if let Some(tween) = &mut view.tween {
+                view.position = tween.advance(time_delta);
+                if tween.is_finished() {
+                    view.tween = None;
+                }
+            }
+        }
+    }
+
+    pub fn is_animating(&self) -> bool {
+        self.pieces.iter().any(|view| view.tween.is_some())
+    }
+
+    pub fn pieces(&self) -> &[PieceView] {
+        &self.pieces
+    }
+
+    /// Places the pieces of the shown position on their squares. Material missing from a full
+    /// set is shown in the trays.
+    fn reset(&mut self) {
+        self.pieces.clear();
+        self.captured = [0; 2];
+        for (square, piece) in self.position.pieces() {
+            self.pieces.push(PieceView {
+                piece,
+                square: Some(square),
+                position: self.board.square_center(square),
+                tween: None,
+            });
+        }
+
+        for color in [Color::White, Color::Black] {
+            for kind in PieceKind::ALL {
+                let piece = Piece::new(color, kind);
+                let on_board = self.position.pieces().filter(|&(_, p)| p == piece).count();
+                for _ in on_board..full_set_count(kind) {
+                    let index = &mut self.captured[color.index()];
+                    self.pieces.push(PieceView {
+                        piece,
+                        square: None,
+                        position: self.board.tray_position(color, *index),
+                        tween: None,
+                    });
+                    *index += 1;
+                }
+            }
+        }
+    }
+
+    /// Animates `mv` played in the shown position.
+    fn animate(&mut self, mv: Move) {
+        let Some(mover) = self.index_at(mv.from) else {
+            return;
+        };
+        let color = self.pieces[mover].piece.color;
+
+        let captured_square = match mv.kind {
+            MoveKind::EnPassant => Some(Square::new(mv.to.file(), mv.from.rank())),
+            MoveKind::Castle(_) => None,
+            _ => Some(mv.to),
+        };
+        if let Some(captured) = captured_square.and_then(|square| self.index_at(square)) {
+            self.capture(captured);
+        }
+
+        // Find the rook before the king moves, in Chess960 the king may land on its square
+        let castling_rook = match mv.kind {
+            MoveKind::Castle(side) => {
+                let rank = color.back_rank();
+                let rook_from = self
+                    .position
+                    .castling_rights()
+                    .rook_file(color, side)
+                    .map(|file| Square::new(file, rank));
+                rook_from
+                    .and_then(|square| self.index_at(square))
+                    .map(|rook| (rook, Square::new(side.rook_file(), rank)))
+            }
+            _ => None,
+        };
+
+        if let Some(kind) = mv.promotion() {
+            self.pieces[mover].piece = Piece::new(color, kind);
+        }
+        self.move_to(mover, mv.to, MOVE_TIME);
+        if let Some((rook, rook_to)) = castling_rook {
+            self.move_to(rook, rook_to, MOVE_TIME);
+        }
+    }
+
+    fn capture(&mut self, i: usize) {
+        let color = self.pieces[i].piece.color;
+        let index = &mut self.captured[color.index()];
+        let target = self.board.tray_position(color, *index);
+        *index += 1;
+
+        self.pieces[i].square = None;
+        self.start_tween(i, target, CAPTURE_TIME);
+    }
+
+    fn move_to(&mut self, i: usize, square: Square, duration: f32) {
+        self.pieces[i].square = Some(square);
+        self.start_tween(i, self.board.square_center(square), duration);
+    }
+
+    fn start_tween(&mut self, i: usize, target: Point3<f32>, duration: f32) {
+        let view = &mut self.pieces[i];
+        let arc_height = (target - view.position).norm() * ARC_HEIGHT;
+        view.tween = Some(Tween::new(view.position, target, arc_height, duration));
+    }
+
+    fn index_at(&self, square: Square) -> Option<usize> {
+        self.pieces
+            .iter()
+            .position(|view| view.square == Some(square))
+    }
+}
+
+fn full_set_count(kind: PieceKind) -> usize {
+    match kind {
+        PieceKind::Pawn => 8,
+        PieceKind::Knight | PieceKind::Bishop | PieceKind::Rook => 2,
+        PieceKind::Queen | PieceKind::King => 1,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use nalgebra::Vector3;
+
+    use super::*;
+    use crate::rendering::mesh::BBox;
+
+    fn layout() -> BoardLayout {
+        BoardLayout::from_cell(&BBox {
+            width: 1.0,
+            length: 1.0,
+            height: 0.0,
+        })
+    }
+
+    fn sq(name: &str) -> Square {
+        Square::parse(name).unwrap()
+    }
+
+    fn piece_on<'a>(view: &'a BoardView, square: &str) -> &'a PieceView {
+        let square = sq(square);
+        view.pieces()
+            .iter()
+            .find(|piece| piece.square == Some(square))
+            .unwrap()
+    }
+
+    #[test]
+    fn test_animate_capture() {
+        let board = layout();
+        let mut position = Position::starting();
+        for san in ["e4", "d5"] {
+            position.make_move(position.parse_san(san).unwrap());
+        }
+        let mut view = BoardView::new(board.clone(), &position);
+        assert!(!view.sync(&position, None));
+
+        let mv = position.parse_san("exd5").unwrap();
+        position.make_move(mv);
+        assert!(view.sync(&position, Some(mv)));
+        assert!(view.is_animating());
+        assert_eq!(
+            piece_on(&view, "d5").position,
+            board.square_center(sq("e4"))
+        );
+
+        view.advance(0.2);
+        assert!(piece_on(&view, "d5").position.y > 0.0);
+        view.advance(1.0);
+        assert!(!view.is_animating());
+        assert_eq!(
+            piece_on(&view, "d5").position,
+            board.square_center(sq("d5"))
+        );
+
+        let captured: Vec<&PieceView> = view
+            .pieces()
+            .iter()
+            .filter(|p| p.square.is_none())
+            .collect();
+        assert_eq!(captured.len(), 1);
+        assert_eq!(captured[0].piece, Piece::new(Color::Black, PieceKind::Pawn));
+        assert_eq!(captured[0].position, board.tray_position(Color::Black, 0));
+
+        // Jumping back places the pieces without animating
+        let position = Position::starting();
+        assert!(view.sync(&position, Some(mv)));
+        assert!(!view.is_animating());
+        assert!(view.pieces().iter().all(|p| p.square.is_some()));
+    }
+
+    #[test]
+    fn test_drag_and_settle() {
+        let board = layout();
+        let mut view = BoardView::new(board.clone(), &Position::starting());
+        let e2 = sq("e2");
+
+        let above_e4 = board.square_center(sq("e4")) + Vector3::y() * 5.0;
+        view.drag(e2, &Ray::new(above_e4, -Vector3::y()));
+        let dragged = piece_on(&view, "e2").position;
+        assert!((dragged - board.square_center(sq("e4"))).y > 0.0);
+        assert_eq!(board.square_at(&dragged), Some(sq("e4")));
+
+        view.settle(e2);
+        view.advance(1.0);
+        assert_eq!(piece_on(&view, "e2").position, board.square_center(e2));
+    }
+}
@@ -1,3 +1,9 @@
+mod animation;
+mod board;
+mod board_view;
+mod move_input;
+mod overlay;
+mod pieces;
 mod playground;
 
 use winit::window::Window;
@@ -8,11 +14,13 @@ use crate::assets::Assets;
 use crate::camera::Camera;
 use crate::input_state::InputState;
 use crate::object::Object;
+use crate::rendering::light::Lighting;
 
 pub trait Scene {
     fn objects(&self) -> &[Object];
     fn active_camera(&self) -> &Camera;
     fn active_camera_mut(&mut self) -> &mut Camera;
+    fn lighting(&self) -> &Lighting;
 }
 
 pub trait DynamicScene {
@@ -0,0 +1,253 @@
+use crate::chess::{Move, MoveKind, PieceKind, Position, Square};
+
+/// Click-to-move state of the player: nothing selected, a piece selected with its legal moves,
+/// or a pawn move waiting for the promotion piece.
+#[derive(Clone, Debug, Default)]
+pub struct MoveInput {
+    state: State,
+}
+
+#[derive(Clone, Debug, Default)]
+enum State {
+    #[default]
+    Idle,
+    Selected {
+        from: Square,
+        /// Legal moves with a square clicking which plays them.
+        moves: Vec<(Square, Move)>,
+    },
+    Promoting {
+        to: Square,
+        moves: Vec<Move>,
+    },
+}
+
+/// What a click on the board did.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ClickOutcome {
+    Selected(Square),
+    Deselected,
+    Played(Move),
+    /// The move needs a promotion piece, see [`MoveInput::promotion_choices`].
+    Promoting(Square),
+    /// The square is not a legal destination or the piece on it can't be moved.
+    Rejected(Square),
+    Ignored,
+}
+
+impl MoveInput {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Handles a click on `square` by the side to move in `position`.
+    pub fn click(&mut self, position: &Position, square: Square) -> ClickOutcome {
+        match std::mem::take(&mut self.state) {
+            State::Idle => self.select(position, square),
+            State::Selected { from, .. } if from == square => ClickOutcome::Deselected,
+            State::Selected { moves, .. } => {
+                let moves: Vec<Move> = moves
+                    .into_iter()
+                    .filter(|&(target, _)| target == square)
+                    .map(|(_, mv)| mv)
+                    .collect();
+                match moves.first() {
+                    Some(mv) if mv.promotion().is_some() => {
+                        self.state = State::Promoting { to: square, moves };
+                        ClickOutcome::Promoting(square)
+                    }
+                    Some(&mv) => ClickOutcome::Played(mv),
+                    None if Self::is_own_piece(position, square) => self.select(position, square),
+                    None => ClickOutcome::Rejected(square),
+                }
+            }
+            State::Promoting { .. } => ClickOutcome::Deselected,
+        }
+    }
+
+    /// Finishes a promotion with `kind`, `None` if no promotion is pending.
+    pub fn choose_promotion(&mut self, kind: PieceKind) -> Option<Move> {
+        let State::Promoting { moves, .. } = &self.state else {
+            return None;
+        };
+
+        let mv = moves
+            .iter()
+            .find(|mv| mv.promotion() == Some(kind))
+            .copied();
+        if mv.is_some() {
+            self.state = State::Idle;
+        }
+
+        mv
+    }
+
+    pub fn clear(&mut self) {
+        self.state = State::Idle;
+    }
+
+    pub fn selected(&self) -> Option<Square> {
+        match &self.state {
+            State::Selected { from, .. } => Some(*from),
+            _ => None,
+        }
+    }
+
+    /// Squares the selected piece can be moved by clicking, including the rooks it can castle
+    /// with.
+    pub fn targets(&self) -> impl Iterator<Item = Square> + '_ {
+        let moves = match &self.state {
+            State::Selected { moves, .. } => moves.as_slice(),
+            _ => &[],
+        };
+        moves.iter().map(|&(target, _)| target)
+    }
+
+    /// Squares to show the promotion pieces on while a promotion is pending, starting at the
+    /// promotion square and going back down the file.
+    pub fn promotion_choices(&self) -> Option<[(Square, PieceKind); 4]> {
+        let State::Promoting { to, .. } = self.state else {
+            return None;
+        };
+
+        let back = if to.rank() == 7 { -1 } else { 1 };
+        Some(std::array::from_fn(|i| {
+            let square = to.offset(0, back * i as i8).unwrap();
+            (square, PieceKind::PROMOTIONS[i])
+        }))
+    }
+
+    fn select(&mut self, position: &Position, square: Square) -> ClickOutcome {
+        if position.piece_at(square).is_none() {
+            return ClickOutcome::Ignored;
+        }
+        if !Self::is_own_piece(position, square) {
+            return ClickOutcome::Rejected(square);
+        }
+
+        // Castling is also played by taking the rook, the only way to castle when the king
+        // already stands on its destination in Chess960
+        let moves = position
+            .legal_moves()
+            .into_iter()
+            .filter(|mv| mv.from == square)
+            .flat_map(|mv| {
+                [Some(mv.to), castling_rook(position, mv)]
+                    .into_iter()
+                    .flatten()
+                    .filter(move |&target| target != square)
+                    .map(move |target| (target, mv))
+            })
+            .collect();
+        self.state = State::Selected {
+            from: square,
+            moves,
+        };
+
+        ClickOutcome::Selected(square)
+    }
+
+    fn is_own_piece(position: &Position, square: Square) -> bool {
+        position
+            .piece_at(square)
+            .is_some_and(|piece| piece.color == position.side_to_move())
+    }
+}
+
+/// Square of the rook `mv` castles with, `None` unless it is castling.
+fn castling_rook(position: &Position, mv: Move) -> Option<Square> {
+    let MoveKind::Castle(side) = mv.kind else {
+        return None;
+    };
+    let file = position
+        .castling_rights()
+        .rook_file(position.side_to_move(), side)?;
+    Some(Square::new(file, mv.from.rank()))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn sq(name: &str) -> Square {
+        Square::parse(name).unwrap()
+    }
+
+    #[test]
+    fn test_select_and_move() {
+        let position = Position::starting();
+        let mut input = MoveInput::new();
+
+        assert_eq!(input.click(&position, sq("e4")), ClickOutcome::Ignored);
+        assert_eq!(
+            input.click(&position, sq("e7")),
+            ClickOutcome::Rejected(sq("e7"))
+        );
+
+        assert_eq!(
+            input.click(&position, sq("g1")),
+            ClickOutcome::Selected(sq("g1"))
+        );
+        let mut targets: Vec<Square> = input.targets().collect();
+        targets.sort();
+        assert_eq!(targets, [sq("f3"), sq("h3")]);
+
+        // Clicking another own piece switches the selection
+        assert_eq!(
+            input.click(&position, sq("e2")),
+            ClickOutcome::Selected(sq("e2"))
+        );
+        assert_eq!(
+            input.click(&position, sq("e5")),
+            ClickOutcome::Rejected(sq("e5"))
+        );
+        assert_eq!(input.selected(), None);
+
+        input.click(&position, sq("e2"));
+        let mv = position.parse_san("e4").unwrap();
+        assert_eq!(input.click(&position, sq("e4")), ClickOutcome::Played(mv));
+
+        input.click(&position, sq("e2"));
+        assert_eq!(input.click(&position, sq("e2")), ClickOutcome::Deselected);
+        assert_eq!(input.targets().count(), 0);
+    }
+
+    #[test]
+    fn test_castle_by_taking_rook() {
+        // Chess960 position with the king already on its castled square
+        let position = Position::from_fen("4k3/8/8/8/8/8/8/6KR w H - 0 1").unwrap();
+        let castle = position.parse_san("O-O").unwrap();
+        assert_eq!(castle.from, castle.to);
+
+        let mut input = MoveInput::new();
+        input.click(&position, sq("g1"));
+        assert!(input.targets().any(|target| target == sq("h1")));
+        assert_eq!(
+            input.click(&position, sq("h1")),
+            ClickOutcome::Played(castle)
+        );
+
+        input.click(&position, sq("g1"));
+        assert_eq!(input.click(&position, sq("g1")), ClickOutcome::Deselected);
+    }
+
+    #[test]
+    fn test_promotion() {
+        let position = Position::from_fen("4k3/1P6/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+        let mut input = MoveInput::new();
+
+        input.click(&position, sq("b7"));
+        assert_eq!(
+            input.click(&position, sq("b8")),
+            ClickOutcome::Promoting(sq("b8"))
+        );
+        let choices = input.promotion_choices().unwrap();
+        assert_eq!(choices[0], (sq("b8"), PieceKind::Queen));
+        assert_eq!(choices[3], (sq("b5"), PieceKind::Knight));
+
+        let mv = input.choose_promotion(PieceKind::Knight).unwrap();
+        assert_eq!(mv, position.parse_san("b8=N").unwrap());
+        assert!(input.promotion_choices().is_none());
+        assert_eq!(input.choose_promotion(PieceKind::Queen), None);
+    }
+}
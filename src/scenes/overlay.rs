@@ -0,0 +1,38 @@
+use crate::{
+    assets::{Assets, MaterialId, MeshId, WHITE_TEXTURE},
+    rendering::mesh::Mesh,
+};
+
+const SELECTED: [f32; 3] = [0.85, 0.75, 0.2];
+const TARGET: [f32; 3] = [0.3, 0.65, 0.3];
+const REJECTED: [f32; 3] = [0.8, 0.15, 0.1];
+const PROMOTION: [f32; 3] = [0.55, 0.55, 0.6];
+
+/// Tinted cell planes drawn on top of the board to show the interaction state.
+pub struct OverlayMeshes {
+    pub selected: MeshId,
+    pub target: MeshId,
+    pub rejected: MeshId,
+    pub promotion: MeshId,
+}
+
+impl OverlayMeshes {
+    /// Creates the overlays with `material_id`, which must shade with the vertex colour.
+    pub fn new(assets: &mut Assets, material_id: MaterialId) -> Self {
+        let mut tinted_plane = |name: &str, color: [f32; 3]| {
+            let white_texture_id = assets.id_of(WHITE_TEXTURE).unwrap();
+            let mut mesh = Mesh::new_plane(white_texture_id, material_id);
+            for vertex in &mut mesh.vertices {
+                vertex.color = color;
+            }
+            assets.insert_mesh(name, mesh)
+        };
+
+        Self {
+            selected: tinted_plane("selected_overlay", SELECTED),
+            target: tinted_plane("target_overlay", TARGET),
+            rejected: tinted_plane("rejected_overlay", REJECTED),
+            promotion: tinted_plane("promotion_overlay", PROMOTION),
+        }
+    }
+}
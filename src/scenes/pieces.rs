@@ -0,0 +1,133 @@
+use crate::{
+    assets::{Assets, MaterialId, MeshId, LIT_MATERIAL},
+    chess::{Color, Piece, PieceKind},
+    rendering::mesh::{BBox, Mesh},
+};
+
+const LATHE_SEGMENTS: u16 = 24;
+const WHITE: [f32; 3] = [0.92, 0.88, 0.78];
+const BLACK: [f32; 3] = [0.16, 0.14, 0.13];
+
+/// Lathe meshes for every piece, lit and coloured by their vertices.
+pub struct PieceMeshes {
+    ids: [[MeshId; 6]; 2],
+    bboxes: [BBox; 6],
+    material_id: MaterialId,
+}
+
+impl PieceMeshes {
+    pub fn new(assets: &mut Assets) -> Self {
+        let material_id = assets.id_of(LIT_MATERIAL).unwrap();
+
+        let mut ids = [[0; 6]; 2];
+        let mut bboxes: [BBox; 6] = Default::default();
+        for (color, color_name, vertex_color) in [
+            (Color::White, "white", WHITE),
+            (Color::Black, "black", BLACK),
+        ] {
+            for kind in PieceKind::ALL {
+                let mesh =
+                    Mesh::new_lathe(profile(kind), LATHE_SEGMENTS, vertex_color, material_id);
+                bboxes[kind.index()] = mesh.bbox.clone();
+                let name = format!("{color_name}_{kind:?}").to_lowercase();
+                ids[color.index()][kind.index()] = assets.insert_mesh(&name, mesh);
+            }
+        }
+
+        Self {
+            ids,
+            bboxes,
+            material_id,
+        }
+    }
+
+    pub fn mesh_id(&self, piece: Piece) -> MeshId {
+        self.ids[piece.color.index()][piece.kind.index()]
+    }
+
+    pub fn bbox(&self, piece: Piece) -> &BBox {
+        &self.bboxes[piece.kind.index()]
+    }
+
+    /// The lit vertex colour material the pieces are drawn with.
+    pub fn material_id(&self) -> MaterialId {
+        self.material_id
+    }
+}
+
+/// Outline of each piece as `[radius, height]` points for a board cell one unit wide.
+fn profile(kind: PieceKind) -> &'static [[f32; 2]] {
+    match kind {
+        PieceKind::Pawn => &[
+            [0.0, 0.0],
+            [0.3, 0.0],
+            [0.3, 0.06],
+            [0.2, 0.1],
+            [0.11, 0.3],
+            [0.18, 0.33],
+            [0.17, 0.42],
+            [0.12, 0.5],
+            [0.0, 0.54],
+        ],
+        PieceKind::Knight => &[
+            [0.0, 0.0],
+            [0.32, 0.0],
+            [0.32, 0.07],
+            [0.22, 0.12],
+            [0.14, 0.3],
+            [0.24, 0.45],
+            [0.22, 0.58],
+            [0.08, 0.66],
+            [0.0, 0.66],
+        ],
+        PieceKind::Bishop => &[
+            [0.0, 0.0],
+            [0.32, 0.0],
+            [0.32, 0.07],
+            [0.21, 0.12],
+            [0.11, 0.42],
+            [0.2, 0.46],
+            [0.17, 0.6],
+            [0.06, 0.72],
+            [0.05, 0.76],
+            [0.0, 0.78],
+        ],
+        PieceKind::Rook => &[
+            [0.0, 0.0],
+            [0.34, 0.0],
+            [0.34, 0.08],
+            [0.24, 0.13],
+            [0.2, 0.46],
+            [0.27, 0.5],
+            [0.27, 0.62],
+            [0.18, 0.62],
+            [0.18, 0.56],
+            [0.0, 0.56],
+        ],
+        PieceKind::Queen => &[
+            [0.0, 0.0],
+            [0.36, 0.0],
+            [0.36, 0.08],
+            [0.24, 0.14],
+            [0.12, 0.56],
+            [0.22, 0.62],
+            [0.26, 0.8],
+            [0.12, 0.84],
+            [0.07, 0.9],
+            [0.0, 0.92],
+        ],
+        PieceKind::King => &[
+            [0.0, 0.0],
+            [0.36, 0.0],
+            [0.36, 0.08],
+            [0.24, 0.14],
+            [0.13, 0.6],
+            [0.23, 0.66],
+            [0.2, 0.82],
+            [0.06, 0.86],
+            [0.06, 0.98],
+            [0.03, 1.02],
+            [0.0, 1.02],
+        ],
+    }
+}
@@ -1,36 +1,610 @@
 use std::path::Path;
 
-use nalgebra::{Point3, Vector4};
+use nalgebra::{Point3, Vector3};
 use winit::window::Window;
 
 use crate::{
-    assets::{Assets, ShaderId, DEFAULT_MATERIAL, FALLBACK_TEXTURE},
-    camera::{Camera, CameraControl, FreeCameraMouseControl, FreeCameraTouchControl},
-    input_state::InputState,
+    assets::{AssetLocator, Assets, ShaderId, FALLBACK_TEXTURE, LIT_MATERIAL},
+    camera::{
+        Camera, CameraControl, FreeCameraMouseControl, FreeCameraTouchControl, OrbitCameraControl,
+        TopDownCameraControl,
+    },
+    chess::{
+        search::{Engine, SearchLimits},
+        uci::{GoParams, UciEngine, UciError, UciEvent},
+        Color, FenError, Game, Move, PgnError, PgnGame, Piece, PieceKind, Position, Replay, Square,
+    },
+    clock::{Clock, TimeControl},
+    input_state::{InputState, Key, MouseButton, VirtualKeyCode},
+    logging::{debug, info, trace, warn},
+    math::Point2D,
     object::Object,
+    picking::Ray,
     rendering::{
-        material::Material,
+        light::{Lighting, PointLight},
+        material::{Material, SamplerSettings},
         mesh::Mesh,
         shader::{Shader, ShaderStage},
         texture::Texture,
     },
     rendering::{projection::Projection, PrimitiveType},
-    scenes::{DynamicScene, Scene},
-    transform::Transform,
+    scenes::{
+        board::{BoardLayout, Pick},
+        board_view::BoardView,
+        move_input::{ClickOutcome, MoveInput},
+        overlay::OverlayMeshes,
+        pieces::PieceMeshes,
+        DynamicScene, Scene,
+    },
 };
 
+const TARGET_OVERLAY_SCALE: f32 = 0.35;
+const PROMOTION_PIECE_SCALE: f32 = 0.8;
+/// Seconds an illegal destination stays highlighted.
+const REJECTED_FLASH_TIME: f32 = 0.4;
+/// Pixels the pointer has to move while held on a piece to start dragging it.
+const DRAG_THRESHOLD: f64 = 6.0;
+/// Distance of the orbit camera from the board centre.
+const ORBIT_DISTANCE: f32 = 10.0;
+const PROMOTION_KEYS: [(VirtualKeyCode, PieceKind); 4] = [
+    (VirtualKeyCode::Q, PieceKind::Queen),
+    (VirtualKeyCode::R, PieceKind::Rook),
+    (VirtualKeyCode::B, PieceKind::Bishop),
+    (VirtualKeyCode::N, PieceKind::Knight),
+];
+
 pub struct PlaygroundScene {
     objects: Vec<Object>,
-    camera_control: Box<dyn CameraControl>,
+    /// Number of objects at the start of `objects` that don't depend on the game.
+    static_objects: usize,
+    board: BoardLayout,
+    piece_meshes: PieceMeshes,
+    overlays: OverlayMeshes,
+    view: BoardView,
+    lighting: Lighting,
+    move_input: MoveInput,
+    drag: Option<Drag>,
+    /// Id of the touch acting as the pointer.
+    pointer_touch: Option<u64>,
+    /// Square of the last rejected click and how long it stays highlighted.
+    rejected: Option<(Square, f32)>,
+    camera_mode: CameraMode,
+    orbit_camera: OrbitCameraControl,
+    top_down_camera: TopDownCameraControl,
+    free_camera: Box<dyn CameraControl>,
+    /// Whether three fingers were on the screen in the last frame.
+    three_finger_touch: bool,
+    game: Game,
+    replay: Option<Replay>,
+    opponent: Option<Opponent>,
+    chess960: bool,
+    clock: Option<Clock>,
+    /// Moves in the game when the clock was last pressed.
+    clock_moves: usize,
+}
+
+/// Which camera the scene is viewed through.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum CameraMode {
+    /// Orbiting the board in perspective.
+    Orbit,
+    /// Looking down at the board in orthographic projection.
+    TopDown,
+    /// Flying around freely, for debugging.
+    Free,
+}
+
+/// Piece held down by the pointer, dragged once the pointer moved far enough.
+#[derive(Clone, Copy)]
+struct Drag {
+    from: Square,
+    start: Point2D,
+    lifted: bool,
+    /// The press deselected the piece, select it again if it gets dragged.
+    reselect: bool,
+}
+
+/// Window positions of the primary pointer, the left mouse button or the first finger on the
+/// screen, in this frame.
+#[derive(Default)]
+struct PointerInput {
+    pressed: Option<Point2D>,
+    held: Option<Point2D>,
+    released: Option<Point2D>,
+}
+
+/// Engine playing one side of the game in the background.
+struct Opponent {
+    color: Color,
+    limits: SearchLimits,
+    engine: OpponentEngine,
+    thinking_on: Option<Position>,
+}
+
+enum OpponentEngine {
+    Builtin(Engine),
+    Uci(UciEngine),
+}
+
+impl OpponentEngine {
+    fn start(&mut self, game: &Game, limits: SearchLimits) -> Result<(), UciError> {
+        match self {
+            OpponentEngine::Builtin(engine) => {
+                engine.start(game, limits, |info| {
+                    trace!("depth {} {} nodes {}", info.depth, info.score, info.nodes);
+                });
+                Ok(())
+            }
+            OpponentEngine::Uci(engine) => {
+                engine.set_position(game)?;
+                engine.go(limits)
+            }
+        }
+    }
+
+    /// The move found by the finished search, `None` while it is still running.
+    fn poll(&mut self) -> Result<Option<Move>, UciError> {
+        match self {
+            OpponentEngine::Builtin(engine) => Ok(engine.poll().and_then(|result| {
+                debug!(
+                    "Engine searched depth {}, {}, {} nodes",
+                    result.info.depth, result.info.score, result.info.nodes
+                );
+                result.best_move
+            })),
+            OpponentEngine::Uci(engine) => {
+                while let Some(event) = engine.poll()? {
+                    match event {
+                        UciEvent::Info(info) => trace!("{info:?}"),
+                        UciEvent::BestMove { mv, .. } => return Ok(mv),
+                    }
+                }
+                Ok(None)
+            }
+        }
+    }
 }
 
 impl PlaygroundScene {
     pub fn new(assets: &mut Assets) -> Self {
-        let objects = Self::setup_objects(assets);
-        let camera_control = Self::camera_control();
-        Self {
+        Self::with_game(assets, Game::new())
+    }
+
+    /// Creates the scene with a game starting from the position described by `fen`.
+    pub fn from_fen(assets: &mut Assets, fen: &str) -> Result<Self, FenError> {
+        let position = Position::from_fen(fen)?;
+        Ok(Self::with_game(assets, Game::from_position(position)))
+    }
+
+    /// Creates the scene with a Chess960 game from the start position numbered `index`
+    /// (0-959), `None` if the index is out of range.
+    pub fn chess960(assets: &mut Assets, index: u16) -> Option<Self> {
+        let position = Position::chess960(index)?;
+        let mut scene = Self::with_game(assets, Game::from_position(position));
+        scene.chess960 = true;
+        Some(scene)
+    }
+
+    fn with_game(assets: &mut Assets, game: Game) -> Self {
+        let (objects, board) = Self::setup_objects(assets);
+        let piece_meshes = PieceMeshes::new(assets);
+        let overlays = OverlayMeshes::new(assets, piece_meshes.material_id());
+        let orbit_camera = Self::orbit_camera_control(&board);
+        let top_down_camera = Self::top_down_camera_control(&board);
+        // The perspective view is hard to use on small phone screens
+        let camera_mode = if cfg!(target_os = "android") {
+            CameraMode::TopDown
+        } else {
+            CameraMode::Orbit
+        };
+        let view = BoardView::new(board.clone(), game.position());
+        let lighting = Self::board_lighting(&board);
+        let mut scene = Self {
+            static_objects: objects.len(),
             objects,
-            camera_control,
+            board,
+            piece_meshes,
+            overlays,
+            view,
+            lighting,
+            move_input: MoveInput::new(),
+            drag: None,
+            pointer_touch: None,
+            rejected: None,
+            camera_mode,
+            orbit_camera,
+            top_down_camera,
+            free_camera: Self::free_camera_control(),
+            three_finger_touch: false,
+            game,
+            replay: None,
+            opponent: None,
+            chess960: false,
+            clock: None,
+            clock_moves: 0,
+        };
+        scene.sync_objects();
+
+        scene
+    }
+
+    pub fn game(&self) -> &Game {
+        &self.game
+    }
+
+    /// The board cells, drawn before the pieces and overlays.
+    #[cfg(test)]
+    pub fn board_objects(&self) -> &[Object] {
+        &self.objects[..self.static_objects]
+    }
+
+    /// Loads the first game of a PGN file from the assets for stepping through with the
+    /// `.` and `,` keys.
+    pub fn load_pgn(&mut self, locator: &AssetLocator, path: &Path) -> Result<(), PgnError> {
+        let pgn = PgnGame::from_asset(locator, path)?
+            .into_iter()
+            .next()
+            .ok_or(PgnError::NoGames)?;
+
+        self.game = pgn.game();
+        self.replay = Some(Replay::new(pgn.mainline()));
+
+        Ok(())
+    }
+
+    /// Lets the built-in engine play `color`, searching each move within `limits`.
+    pub fn set_opponent(&mut self, color: Color, limits: SearchLimits) {
+        self.set_opponent_engine(color, limits, OpponentEngine::Builtin(Engine::new()));
+    }
+
+    /// Lets an external UCI engine play `color`, searching each move within `limits`.
+    pub fn set_uci_opponent(&mut self, color: Color, limits: SearchLimits, mut engine: UciEngine) {
+        if self.chess960 {
+            if let Err(e) = engine.set_chess960(true) {
+                let name = engine.name().unwrap_or("UCI engine");
+                warn!("{name} may not support Chess960: {e}");
+            }
+        }
+        self.set_opponent_engine(color, limits, OpponentEngine::Uci(engine));
+    }
+
+    fn set_opponent_engine(&mut self, color: Color, limits: SearchLimits, engine: OpponentEngine) {
+        self.opponent = Some(Opponent {
+            color,
+            limits,
+            engine,
+            thinking_on: None,
+        });
+    }
+
+    /// Plays the game under `control`, starting the time of the side to move.
+    pub fn set_clock(&mut self, control: TimeControl) {
+        let mut clock = Clock::new(control);
+        clock.start(self.game.side_to_move());
+        self.clock = Some(clock);
+        self.clock_moves = self.game.moves().count();
+    }
+
+    pub fn pause_clock(&mut self) {
+        if let Some(clock) = &mut self.clock {
+            clock.pause();
+        }
+    }
+
+    pub fn resume_clock(&mut self) {
+        if let Some(clock) = &mut self.clock {
+            clock.resume();
+        }
+    }
+
+    fn is_flagged(&self) -> bool {
+        self.clock
+            .as_ref()
+            .is_some_and(|clock| clock.flagged().is_some())
+    }
+
+    fn step_clock(&mut self) {
+        let Some(clock) = &mut self.clock else {
+            return;
+        };
+
+        let moves = self.game.moves().count();
+        if moves == self.clock_moves + 1 {
+            clock.press();
+        } else if moves != self.clock_moves {
+            // Moves were taken back or replayed, keep the time but hand the turn over
+            clock.start(self.game.side_to_move());
+        }
+        self.clock_moves = moves;
+
+        if clock.running().is_some() {
+            if let Some(color) = clock.flagged() {
+                info!("{color:?} ran out of time");
+                clock.stop();
+            } else if self.game.is_over() {
+                clock.stop();
+            }
+        }
+    }
+
+    /// Search limits for `color` from the time left on the clock.
+    fn clock_limits(clock: &Clock, color: Color) -> SearchLimits {
+        let params = GoParams {
+            wtime: Some(clock.remaining(Color::White)),
+            btime: Some(clock.remaining(Color::Black)),
+            winc: Some(clock.increment(Color::White)),
+            binc: Some(clock.increment(Color::Black)),
+            movestogo: clock.moves_to_go(color),
+            ..GoParams::default()
+        };
+        params.limits(color)
+    }
+
+    fn step_opponent(&mut self) {
+        if self.is_flagged() {
+            return;
+        }
+        let Some(opponent) = &mut self.opponent else {
+            return;
+        };
+
+        if opponent.thinking_on.is_some() {
+            let mv = match opponent.engine.poll() {
+                Ok(Some(mv)) => mv,
+                Ok(None) => return,
+                Err(e) => {
+                    warn!("Dropping opponent: {e}");
+                    self.opponent = None;
+                    return;
+                }
+            };
+
+            // The game may have moved on while the engine was thinking, e.g. by stepping a replay
+            if opponent.thinking_on.take().as_ref() == Some(self.game.position()) {
+                debug!("Engine plays {mv}");
+                if let Err(e) = self.game.play(mv) {
+                    warn!("Engine move rejected: {e}");
+                }
+            }
+        }
+
+        if self.game.side_to_move() == opponent.color && !self.game.is_over() {
+            let limits = match &self.clock {
+                Some(clock) => Self::clock_limits(clock, opponent.color),
+                None => opponent.limits,
+            };
+            if let Err(e) = opponent.engine.start(&self.game, limits) {
+                warn!("Dropping opponent: {e}");
+                self.opponent = None;
+                return;
+            }
+            opponent.thinking_on = Some(self.game.position().clone());
+        }
+    }
+
+    /// The piece or square under `position`, in window pixels.
+    pub fn pick(&self, window: &Window, position: Point2D) -> Option<Pick> {
+        let ray = self.ray(window, position)?;
+        self.board.pick(&ray, self.game.position(), |piece| {
+            self.piece_meshes.bbox(piece).clone()
+        })
+    }
+
+    fn ray(&self, window: &Window, position: Point2D) -> Option<Ray> {
+        let size = window.inner_size();
+        let camera = self.camera_control().camera();
+        camera.ray(position, size.width as f32, size.height as f32)
+    }
+
+    fn pointer_input(&mut self, input_state: &InputState) -> PointerInput {
+        let left = Key::MouseButton(MouseButton::Left);
+        if let Some(cursor) = input_state.cursor_position() {
+            let pressed = input_state.is_pressed(left);
+            let held = input_state.is_held(left);
+            let released = input_state.is_released(left);
+            if pressed || held || released {
+                return PointerInput {
+                    pressed: pressed.then_some(cursor),
+                    held: held.then_some(cursor),
+                    released: released.then_some(cursor),
+                };
+            }
+        }
+
+        // Two fingers move the camera
+        if input_state.touches().count() > 1 {
+            self.pointer_touch = None;
+            return PointerInput::default();
+        }
+
+        let touch = match self.pointer_touch {
+            Some(id) => input_state.touches().find(|touch| touch.id == id),
+            None => input_state.touches().next(),
+        };
+        let Some(touch) = touch else {
+            self.pointer_touch = None;
+            return PointerInput::default();
+        };
+
+        let pressed = self.pointer_touch.is_none().then_some(touch.start_position);
+        self.pointer_touch = touch.end_position.is_none().then_some(touch.id);
+        PointerInput {
+            pressed,
+            held: Some(touch.move_position),
+            released: touch.end_position,
+        }
+    }
+
+    /// Whether the side to move is played from the board rather than by an engine.
+    fn player_to_move(&self) -> bool {
+        let engine_to_move = self
+            .opponent
+            .as_ref()
+            .is_some_and(|opponent| opponent.color == self.game.side_to_move());
+        !engine_to_move && !self.game.is_over() && !self.is_flagged()
+    }
+
+    fn step_move_input(&mut self, window: &Window, input_state: &InputState) {
+        if !self.player_to_move() {
+            self.cancel_drag();
+            return;
+        }
+
+        if self.move_input.promotion_choices().is_some() {
+            for (key, kind) in PROMOTION_KEYS {
+                if input_state.is_pressed(Key::KeyboardKey(key)) {
+                    if let Some(mv) = self.move_input.choose_promotion(kind) {
+                        self.play_player_move(mv);
+                        return;
+                    }
+                }
+            }
+            if input_state.is_pressed(Key::KeyboardKey(VirtualKeyCode::Escape)) {
+                self.move_input.clear();
+            }
+        }
+
+        let pointer = self.pointer_input(input_state);
+        if let Some(position) = pointer.pressed {
+            self.pointer_pressed(window, position);
+        }
+        self.step_drag(window, &pointer);
+    }
+
+    fn pointer_pressed(&mut self, window: &Window, position: Point2D) {
+        let Some(square) = self.pick(window, position).map(Pick::square) else {
+            self.move_input.clear();
+            return;
+        };
+
+        let chosen = self
+            .move_input
+            .promotion_choices()
+            .and_then(|choices| choices.into_iter().find(|&(s, _)| s == square));
+        if let Some((_, kind)) = chosen {
+            if let Some(mv) = self.move_input.choose_promotion(kind) {
+                self.play_player_move(mv);
+            }
+            return;
+        }
+
+        let selected = self.move_input.selected();
+        let outcome = self.move_input.click(self.game.position(), square);
+        let drag = |reselect| Drag {
+            from: square,
+            start: position,
+            lifted: false,
+            reselect,
+        };
+        match outcome {
+            ClickOutcome::Played(mv) => self.play_player_move(mv),
+            ClickOutcome::Rejected(square) => self.reject(square),
+            ClickOutcome::Selected(_) => self.drag = Some(drag(false)),
+            ClickOutcome::Deselected if selected == Some(square) => {
+                self.drag = Some(drag(true));
+            }
+            outcome => trace!("{outcome:?}"),
+        }
+    }
+
+    /// Moves the held piece with the pointer and plays it where it is dropped.
+    fn step_drag(&mut self, window: &Window, pointer: &PointerInput) {
+        let Some(mut drag) = self.drag else {
+            return;
+        };
+
+        if let Some(position) = pointer.held {
+            let moved = (position.x - drag.start.x).hypot(position.y - drag.start.y);
+            if !drag.lifted && moved >= DRAG_THRESHOLD {
+                drag.lifted = true;
+                if drag.reselect {
+                    self.move_input.click(self.game.position(), drag.from);
+                }
+            }
+            if drag.lifted {
+                if let Some(ray) = self.ray(window, position) {
+                    self.view.drag(drag.from, &ray);
+                }
+            }
+            self.drag = Some(drag);
+        }
+
+        if let Some(position) = pointer.released {
+            self.drag = None;
+            if drag.lifted {
+                self.drop_piece(window, drag.from, position);
+            }
+        } else if pointer.held.is_none() {
+            // The button went up outside the window
+            self.cancel_drag();
+        }
+    }
+
+    fn drop_piece(&mut self, window: &Window, from: Square, position: Point2D) {
+        let square = self.ray(window, position).and_then(|ray| {
+            let distance = ray.intersect_horizontal_plane(0.0)?;
+            self.board.square_at(&ray.at(distance))
+        });
+
+        // Dropping the piece back on its square or off the board keeps it selected. A king
+        // castling onto its own square is dropped on the rook instead
+        let Some(square) = square.filter(|&square| square != from) else {
+            self.view.settle(from);
+            return;
+        };
+
+        match self.move_input.click(self.game.position(), square) {
+            ClickOutcome::Played(mv) => self.play_player_move(mv),
+            outcome => {
+                if let ClickOutcome::Rejected(square) = outcome {
+                    self.reject(square);
+                }
+                trace!("{outcome:?}");
+                self.view.settle(from);
+            }
+        }
+    }
+
+    fn cancel_drag(&mut self) {
+        if let Some(drag) = self.drag.take() {
+            self.view.settle(drag.from);
+        }
+    }
+
+    fn reject(&mut self, square: Square) {
+        debug!("Rejected move input on {square}");
+        self.rejected = Some((square, REJECTED_FLASH_TIME));
+    }
+
+    fn play_player_move(&mut self, mv: Move) {
+        debug!("Player plays {mv}");
+        if let Err(e) = self.game.play(mv) {
+            warn!("Player move rejected: {e}");
+        }
+    }
+
+    fn step_rejected(&mut self, time_delta: f32) {
+        if let Some((_, time_left)) = &mut self.rejected {
+            *time_left -= time_delta;
+            if *time_left <= 0.0 {
+                self.rejected = None;
+            }
+        }
+    }
+
+    fn step_replay(&mut self, input_state: &InputState) {
+        if let Some(replay) = &mut self.replay {
+            let stepped = if input_state.is_pressed(Key::KeyboardKey(VirtualKeyCode::Period)) {
+                replay.step_forward(&mut self.game)
+            } else if input_state.is_pressed(Key::KeyboardKey(VirtualKeyCode::Comma)) {
+                replay.step_back(&mut self.game)
+            } else {
+                None
+            };
+
+            if let Some(mv) = stepped {
+                debug!("Replay at ply {}/{} ({mv})", replay.ply(), replay.len());
+            }
         }
     }
 
@@ -46,7 +620,63 @@ impl PlaygroundScene {
         assets.insert_shader("green_frag", green_frag)
     }
 
-    fn setup_objects(assets: &mut Assets) -> Vec<Object> {
+    /// Moves the pieces towards the game position.
+    fn step_view(&mut self, time_delta: f32) {
+        let last_move = self.game.moves().last();
+        if self.view.sync(self.game.position(), last_move) {
+            // A selection made in another position is meaningless
+            self.move_input.clear();
+            self.drag = None;
+        }
+        self.view.advance(time_delta);
+    }
+
+    /// Rebuilds the overlay and piece objects from the move input and the board view.
+    fn sync_objects(&mut self) {
+        let objects = &mut self.objects;
+        objects.truncate(self.static_objects);
+        let board = &self.board;
+        let overlay = |mesh_id, square, scale| Object {
+            mesh_id,
+            primitive_type: PrimitiveType::Triangles,
+            transform: board.overlay_transform(square, scale),
+        };
+
+        if let Some(square) = self.move_input.selected() {
+            objects.push(overlay(self.overlays.selected, square, 1.0));
+        }
+        for square in self.move_input.targets() {
+            objects.push(overlay(self.overlays.target, square, TARGET_OVERLAY_SCALE));
+        }
+        if let Some((square, _)) = self.rejected {
+            objects.push(overlay(self.overlays.rejected, square, 1.0));
+        }
+
+        let choices = self.move_input.promotion_choices();
+        let color = self.game.side_to_move();
+        let covered = |square: Square| choices.is_some_and(|c| c.iter().any(|&(s, _)| s == square));
+        for view in self.view.pieces() {
+            if !view.square.is_some_and(covered) {
+                objects.push(Object {
+                    mesh_id: self.piece_meshes.mesh_id(view.piece),
+                    primitive_type: PrimitiveType::Triangles,
+                    transform: board.piece_transform_at(view.position),
+                });
+            }
+        }
+        for (square, kind) in choices.into_iter().flatten() {
+            objects.push(overlay(self.overlays.promotion, square, 1.0));
+            let mut transform = board.piece_transform(square);
+            transform.scale *= PROMOTION_PIECE_SCALE;
+            objects.push(Object {
+                mesh_id: self.piece_meshes.mesh_id(Piece::new(color, kind)),
+                primitive_type: PrimitiveType::Triangles,
+                transform,
+            });
+        }
+    }
+
+    fn setup_objects(assets: &mut Assets) -> (Vec<Object>, BoardLayout) {
         let table_path = Path::new("models/table/table.obj");
         let plant_path = Path::new("models/indoor_plant/indoor plant_02.obj");
         let m1887_path = Path::new("models/m1887/M1887.obj");
@@ -65,23 +695,23 @@ impl PlaygroundScene {
         //     transform: Transform::new(Point3::new(5.0, 0.1, 5.0), Vector4::zeros(), 0.001),
         // });
 
-        let mut chess_chells = Self::create_chess_board(assets);
+        let (mut chess_chells, board) = Self::create_chess_board(assets);
         objects.append(&mut chess_chells);
 
-        objects
+        (objects, board)
     }
 
-    fn create_chess_board(assets: &mut Assets) -> Vec<Object> {
+    fn create_chess_board(assets: &mut Assets) -> (Vec<Object>, BoardLayout) {
         let locator = assets.asset_locator();
         let shrek_texture = Texture::from_asset(locator, Path::new("textures/shrek.jpg")).unwrap();
         let shrek_texture_id = assets.insert_texture("shrek", shrek_texture);
 
-        let default_material_id = assets.id_of(DEFAULT_MATERIAL).unwrap();
-        let default_material = assets.material(default_material_id).unwrap();
+        let lit_material_id = assets.id_of(LIT_MATERIAL).unwrap();
+        let lit_material = assets.material(lit_material_id).unwrap();
         let shrek_material = Material {
             id: 0,
             texture_id: shrek_texture_id,
-            ..*default_material
+            ..*lit_material
         };
         let shrek_material_id = assets.insert_material("shrek_material", shrek_material);
 
@@ -93,45 +723,109 @@ impl PlaygroundScene {
             vertex_shader_id: unlit_vert_shader,
             fragment_shader_id: green_frag_shader_id,
             texture_id: fallback_texture_id,
+            sampler: SamplerSettings::default(),
         };
         let green_material_id = assets.insert_material("green_material", green_material);
 
         let shrek_chess_cell = Mesh::new_plane(shrek_texture_id, shrek_material_id);
-        let cell_w = shrek_chess_cell.bbox.width;
-        let cell_l = shrek_chess_cell.bbox.length;
+        let board = BoardLayout::from_cell(&shrek_chess_cell.bbox);
 
         let shrek_chess_cell_id = assets.insert_mesh("shrek_chess_cell", shrek_chess_cell);
 
         let green_chess_cell = Mesh::new_plane(fallback_texture_id, green_material_id);
         let green_chess_cell_id = assets.insert_mesh("green_chess_cell", green_chess_cell);
 
-        let mut objects = Vec::with_capacity(16);
-        for row in 0..8 {
-            for col in 0..8 {
-                let mesh_id;
-                if (col + row) % 2 == 0 {
-                    mesh_id = shrek_chess_cell_id;
-                } else {
-                    mesh_id = green_chess_cell_id;
-                }
-
-                let o = Object {
-                    mesh_id,
-                    primitive_type: PrimitiveType::Lines,
-                    transform: Transform::new(
-                        Point3::new(cell_w * row as f32, 0.0, cell_l * col as f32),
-                        Vector4::new(1.0, 0.0, 0.0, 90.0),
-                        1.0,
-                    ),
-                };
-                objects.push(o);
+        let mut objects = Vec::with_capacity(64);
+        for square in Square::all() {
+            let mesh_id;
+            if (square.file() + square.rank()) % 2 == 0 {
+                mesh_id = shrek_chess_cell_id;
+            } else {
+                mesh_id = green_chess_cell_id;
             }
+
+            let o = Object {
+                mesh_id,
+                primitive_type: PrimitiveType::Triangles,
+                transform: board.cell_transform(square),
+            };
+            objects.push(o);
+        }
+
+        (objects, board)
+    }
+
+    fn camera_control(&self) -> &dyn CameraControl {
+        match self.camera_mode {
+            CameraMode::Orbit => &self.orbit_camera,
+            CameraMode::TopDown => &self.top_down_camera,
+            CameraMode::Free => self.free_camera.as_ref(),
+        }
+    }
+
+    fn camera_control_mut(&mut self) -> &mut dyn CameraControl {
+        match self.camera_mode {
+            CameraMode::Orbit => &mut self.orbit_camera,
+            CameraMode::TopDown => &mut self.top_down_camera,
+            CameraMode::Free => self.free_camera.as_mut(),
+        }
+    }
+
+    /// Switches between the 2D and 3D views with `V` or a three finger tap, and to the free
+    /// camera and back with `C`.
+    fn step_camera(&mut self, window: &Window, input_state: &InputState, time_delta: f32) {
+        let three_fingers = input_state.touches().count() >= 3;
+        let toggle_2d = input_state.is_pressed(Key::KeyboardKey(VirtualKeyCode::V))
+            || (three_fingers && !self.three_finger_touch);
+        self.three_finger_touch = three_fingers;
+
+        let mode = self.camera_mode;
+        if toggle_2d {
+            self.camera_mode = match mode {
+                CameraMode::TopDown => CameraMode::Orbit,
+                _ => CameraMode::TopDown,
+            };
+        } else if input_state.is_pressed(Key::KeyboardKey(VirtualKeyCode::C)) {
+            self.camera_mode = match mode {
+                CameraMode::Free => CameraMode::Orbit,
+                _ => CameraMode::Free,
+            };
+        }
+        if self.camera_mode != mode {
+            debug!("Switched to the {:?} camera", self.camera_mode);
         }
 
-        objects
+        self.camera_control_mut()
+            .update(window, input_state, time_delta);
     }
 
-    fn camera_control() -> Box<dyn CameraControl> {
+    /// The default lights with a warm lamp hanging over the middle of the board.
+    fn board_lighting(board: &BoardLayout) -> Lighting {
+        let (_, length) = board.extent();
+        let lamp = PointLight {
+            position: board.center() + Vector3::y() * length * 0.75,
+            color: [1.0, 0.85, 0.6],
+            intensity: 0.6,
+            range: length * 1.5,
+        };
+
+        Lighting {
+            point_lights: vec![lamp],
+            ..Default::default()
+        }
+    }
+
+    fn orbit_camera_control(board: &BoardLayout) -> OrbitCameraControl {
+        let camera = Self::new_camera();
+        OrbitCameraControl::new(camera, board.center(), ORBIT_DISTANCE, 60.0)
+    }
+
+    fn top_down_camera_control(board: &BoardLayout) -> TopDownCameraControl {
+        let (width, length) = board.extent();
+        TopDownCameraControl::new(board.center(), width, length)
+    }
+
+    fn free_camera_control() -> Box<dyn CameraControl> {
         if cfg!(target_os = "android") {
             Box::new(Self::touch_camera_control())
         } else if cfg!(not(any(target_os = "iOS"))) {
@@ -161,16 +855,20 @@ impl PlaygroundScene {
 
 impl Scene for PlaygroundScene {
     fn active_camera(&self) -> &Camera {
-        &self.camera_control.camera()
+        self.camera_control().camera()
     }
 
     fn active_camera_mut(&mut self) -> &mut Camera {
-        self.camera_control.camera_mut()
+        self.camera_control_mut().camera_mut()
     }
 
     fn objects(&self) -> &[Object] {
         &self.objects
     }
+
+    fn lighting(&self) -> &Lighting {
+        &self.lighting
+    }
 }
 
 impl DynamicScene for PlaygroundScene {
@@ -181,6 +879,13 @@ impl DynamicScene for PlaygroundScene {
         time_delta: f32,
         _assets: &mut Assets,
     ) {
-        self.camera_control.update(window, input_state, time_delta);
+        self.step_camera(window, input_state, time_delta);
+        self.step_move_input(window, input_state);
+        self.step_rejected(time_delta);
+        self.step_replay(input_state);
+        self.step_opponent();
+        self.step_clock();
+        self.step_view(time_delta);
+        self.sync_objects();
     }
 }
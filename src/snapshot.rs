@@ -0,0 +1,46 @@
+//! Renders a position offscreen and saves it as a PNG, e.g. for thumbnails. Needs no window or
+//! GPU, a software Vulkan driver such as lavapipe will do.
+//!
+//! Usage: `cargo run --bin chessno-snapshot -- <output.png> [<width>x<height>] [fen]`
+
+use std::{env, process};
+
+use chessno::chess::STARTING_FEN;
+
+const DEFAULT_SIZE: (u32, u32) = (512, 512);
+
+fn main() {
+    let mut args = env::args().skip(1);
+    let Some(output) = args.next() else {
+        eprintln!("usage: chessno-snapshot <output.png> [<width>x<height>] [fen]");
+        process::exit(2);
+    };
+
+    let mut rest: Vec<String> = args.collect();
+    let size = rest.first().and_then(|arg| parse_size(arg));
+    if size.is_some() {
+        rest.remove(0);
+    }
+    let (width, height) = size.unwrap_or(DEFAULT_SIZE);
+    let fen = rest.join(" ");
+    let fen = if fen.is_empty() { STARTING_FEN } else { &fen };
+
+    let image = match chessno::render_position(fen, width, height) {
+        Ok(image) => image,
+        Err(e) => {
+            eprintln!("invalid FEN: {e}");
+            process::exit(2);
+        }
+    };
+    if let Err(e) = image.save(&output) {
+        eprintln!("failed to save {output}: {e}");
+        process::exit(1);
+    }
+}
+
+fn parse_size(arg: &str) -> Option<(u32, u32)> {
+    let (width, height) = arg.split_once('x')?;
+    let size = (width.parse().ok()?, height.parse().ok()?);
+
+    (size.0 > 0 && size.1 > 0).then_some(size)
+}
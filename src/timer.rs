@@ -1,21 +1,84 @@
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
-pub struct Timer {
+/// Where a [`Timer`] reads the current time from, so that code measuring time can be driven by
+/// a fake clock in tests.
+pub trait TimeSource {
+    fn now(&self) -> Instant;
+}
+
+/// The monotonic system clock.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RealTime;
+
+impl TimeSource for RealTime {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+/// Time that only moves when told to. Clones share the same time.
+#[cfg(test)]
+#[derive(Clone, Debug)]
+pub struct ManualTime {
+    now: std::rc::Rc<std::cell::Cell<Instant>>,
+}
+
+#[cfg(test)]
+impl Default for ManualTime {
+    fn default() -> Self {
+        Self {
+            now: std::rc::Rc::new(std::cell::Cell::new(Instant::now())),
+        }
+    }
+}
+
+#[cfg(test)]
+impl ManualTime {
+    pub fn advance(&self, by: Duration) {
+        self.now.set(self.now.get() + by);
+    }
+}
+
+#[cfg(test)]
+impl TimeSource for ManualTime {
+    fn now(&self) -> Instant {
+        self.now.get()
+    }
+}
+
+pub struct Timer<S: TimeSource = RealTime> {
+    source: S,
     set_time: Instant,
 }
 
 impl Timer {
     pub fn new() -> Self {
-        Timer {
-            set_time: Instant::now(),
-        }
+        Self::with_source(RealTime)
+    }
+}
+
+impl Default for Timer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<S: TimeSource> Timer<S> {
+    pub fn with_source(source: S) -> Self {
+        let set_time = source.now();
+        Timer { source, set_time }
     }
 
     pub fn reset(&mut self) {
-        self.set_time = Instant::now();
+        self.set_time = self.source.now();
     }
 
     pub fn elapsed(&self) -> f32 {
-        self.set_time.elapsed().as_secs_f32()
+        self.duration().as_secs_f32()
+    }
+
+    /// Time since the timer was created or last reset.
+    pub fn duration(&self) -> Duration {
+        self.source.now().saturating_duration_since(self.set_time)
     }
 }
@@ -0,0 +1,123 @@
+//! Speaks UCI over stdin and stdout using the built-in search, for use with chess GUIs and
+//! engine tournament managers.
+
+use std::io::{self, BufRead};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::thread;
+use std::time::Duration;
+
+use chessno::chess::{
+    search::{Engine, Searcher},
+    uci::{format_move, parse_position, GoParams, UciInfo},
+    Game, Move,
+};
+
+const NAME: &str = "Chessno";
+const AUTHOR: &str = "the Chessno developers";
+const DEFAULT_HASH_MEGABYTES: usize = 16;
+const MAX_HASH_MEGABYTES: usize = 4096;
+/// How often the engine is checked for a finished search while waiting for input.
+const POLL_INTERVAL: Duration = Duration::from_millis(5);
+
+fn main() {
+    // Input is read on its own thread so a finished search is reported without waiting for the
+    // next command
+    let (sender, commands) = mpsc::channel();
+    thread::spawn(move || {
+        for line in io::stdin().lock().lines().map_while(Result::ok) {
+            if sender.send(line).is_err() {
+                break;
+            }
+        }
+    });
+
+    let mut engine = Engine::with_searcher(Searcher::with_hash_size(DEFAULT_HASH_MEGABYTES));
+    let mut game = Game::new();
+    let mut chess960 = false;
+    // Under `go infinite` the best move must be held back until `stop`
+    let mut infinite = false;
+    let mut held_result = None;
+
+    loop {
+        let line = match commands.recv_timeout(POLL_INTERVAL) {
+            Ok(line) => line,
+            Err(RecvTimeoutError::Timeout) => {
+                if let Some(result) = engine.poll() {
+                    if infinite {
+                        held_result = Some(result);
+                    } else {
+                        print_best_move(&game, result.best_move, chess960);
+                    }
+                }
+                continue;
+            }
+            Err(RecvTimeoutError::Disconnected) => break,
+        };
+
+        let line = line.trim();
+        let (command, args) = line.split_once(' ').unwrap_or((line, ""));
+        match command {
+            "uci" => {
+                println!("id name {NAME}");
+                println!("id author {AUTHOR}");
+                println!(
+                    "option name Hash type spin default {DEFAULT_HASH_MEGABYTES} min 1 max {MAX_HASH_MEGABYTES}"
+                );
+                println!("option name UCI_Chess960 type check default false");
+                println!("uciok");
+            }
+            "isready" => println!("readyok"),
+            "setoption" => {
+                let args = args.trim().strip_prefix("name ").unwrap_or(args);
+                match args.split_once(" value ") {
+                    Some((name, value)) if name.trim().eq_ignore_ascii_case("uci_chess960") => {
+                        chess960 = value.trim() == "true";
+                    }
+                    Some((name, value)) if name.trim().eq_ignore_ascii_case("hash") => {
+                        match value.trim().parse::<usize>() {
+                            Ok(megabytes) => {
+                                let megabytes = megabytes.clamp(1, MAX_HASH_MEGABYTES);
+                                engine = Engine::with_searcher(Searcher::with_hash_size(megabytes));
+                            }
+                            Err(_) => println!("info string invalid Hash value {value}"),
+                        }
+                    }
+                    _ => println!("info string unknown option {args}"),
+                }
+            }
+            "ucinewgame" => {
+                engine.new_game();
+                game = Game::new();
+            }
+            "position" => match parse_position(args) {
+                Some(parsed) => game = parsed,
+                None => println!("info string invalid position {args}"),
+            },
+            "go" => {
+                let params = GoParams::parse(args);
+                infinite = params.infinite;
+                held_result = None;
+                let position = game.position().clone();
+                engine.start(&game, params.limits(game.side_to_move()), move |info| {
+                    println!("{}", UciInfo::from(info).to_uci(&position, chess960));
+                });
+            }
+            "stop" => {
+                engine.stop();
+                infinite = false;
+                if let Some(result) = held_result.take().or_else(|| engine.wait()) {
+                    print_best_move(&game, result.best_move, chess960);
+                }
+            }
+            "quit" => break,
+            _ => {}
+        }
+    }
+}
+
+fn print_best_move(game: &Game, mv: Option<Move>, chess960: bool) {
+    match mv {
+        Some(mv) => println!("bestmove {}", format_move(game.position(), mv, chess960)),
+        None => println!("bestmove 0000"),
+    }
+}
@@ -0,0 +1,77 @@
+use std::time::Duration;
+
+use chessno::chess::{
+    search::{Score, SearchLimits},
+    uci::{parse_move, UciEngine},
+    Game,
+};
+
+fn mock_engine() -> UciEngine {
+    UciEngine::spawn(env!("CARGO_BIN_EXE_mock_engine"), &[] as &[&str]).unwrap()
+}
+
+#[test]
+fn test_handshake_and_options() {
+    let mut engine = mock_engine();
+    assert_eq!(engine.name(), Some("Mock Engine"));
+    assert_eq!(engine.author(), Some("chessno"));
+    let names: Vec<&str> = engine.options().iter().map(|o| o.name.as_str()).collect();
+    assert_eq!(names, ["Hash", "Skill Level"]);
+
+    engine.set_option("Skill Level", "3").unwrap();
+    engine.go(SearchLimits::depth(1)).unwrap();
+    let mut strings = Vec::new();
+    engine
+        .best_move(|info| strings.extend(info.string.clone()))
+        .unwrap();
+    assert_eq!(strings, ["set Skill Level to 3"]);
+}
+
+#[test]
+fn test_best_move_and_info() {
+    let mut engine = mock_engine();
+    engine.new_game().unwrap();
+
+    let mut game = Game::new();
+    let e4 = parse_move(game.position(), "e2e4").unwrap();
+    game.play(e4).unwrap();
+    engine.set_position(&game).unwrap();
+    engine.go(SearchLimits::depth(1)).unwrap();
+
+    let mut infos = Vec::new();
+    let best = engine.best_move(|info| infos.push(info.clone())).unwrap();
+    let expected = game.legal_moves()[0];
+    assert_eq!(best, Some(expected));
+    assert_eq!(infos.len(), 1);
+    assert_eq!(infos[0].depth, Some(1));
+    assert_eq!(infos[0].score, Some(Score::Centipawns(13)));
+    assert_eq!(infos[0].pv, [expected]);
+}
+
+#[test]
+fn test_is_ready_keeps_search_output() {
+    let mut engine = mock_engine();
+    engine.set_position(&Game::new()).unwrap();
+    engine.go(SearchLimits::depth(1)).unwrap();
+    // The mock engine answers the search before `isready`
+    engine.is_ready().unwrap();
+
+    let mut infos = 0;
+    let best = engine.best_move(|_| infos += 1).unwrap();
+    assert_eq!(best, Some(Game::new().legal_moves()[0]));
+    assert_eq!(infos, 1);
+}
+
+#[test]
+fn test_infinite_search_waits_for_stop() {
+    let mut engine = mock_engine();
+    engine.set_position(&Game::new()).unwrap();
+    engine.go(SearchLimits::infinite()).unwrap();
+    engine.is_ready().unwrap();
+    std::thread::sleep(Duration::from_millis(50));
+    assert_eq!(engine.poll().unwrap(), None);
+
+    engine.stop().unwrap();
+    let best = engine.best_move(|_| {}).unwrap();
+    assert_eq!(best, Some(Game::new().legal_moves()[0]));
+}
@@ -0,0 +1,74 @@
+use std::thread;
+use std::time::{Duration, Instant};
+
+use chessno::chess::{
+    search::SearchLimits,
+    uci::{parse_move, GoParams, UciEngine, UciEvent},
+    Game, Position,
+};
+
+fn server() -> UciEngine {
+    UciEngine::spawn(env!("CARGO_BIN_EXE_chessno-uci"), &[] as &[&str]).unwrap()
+}
+
+#[test]
+fn test_handshake() {
+    let mut engine = server();
+    assert_eq!(engine.name(), Some("Chessno"));
+    assert!(engine.options().iter().any(|o| o.name == "Hash"));
+
+    engine.set_option("Hash", "4").unwrap();
+    engine.new_game().unwrap();
+}
+
+#[test]
+fn test_depth_search_streams_info() {
+    let mut engine = server();
+    let mut game =
+        Game::from_position(Position::from_fen("6k1/5ppp/8/8/8/8/5PPP/R5K1 w - - 0 1").unwrap());
+    engine.set_position(&game).unwrap();
+    engine.go(SearchLimits::depth(3)).unwrap();
+
+    let mut depths = Vec::new();
+    let best = engine.best_move(|info| depths.extend(info.depth)).unwrap();
+    assert_eq!(best, parse_move(game.position(), "a1a8"));
+    assert!(!depths.is_empty());
+
+    game.play(best.unwrap()).unwrap();
+    assert!(game.is_over());
+}
+
+#[test]
+fn test_infinite_search_runs_until_stopped() {
+    let mut engine = server();
+    engine.set_position(&Game::new()).unwrap();
+    engine.go(SearchLimits::infinite()).unwrap();
+
+    thread::sleep(Duration::from_millis(200));
+    let mut finished_early = false;
+    while let Some(event) = engine.poll().unwrap() {
+        finished_early |= matches!(event, UciEvent::BestMove { .. });
+    }
+    assert!(!finished_early);
+
+    engine.stop().unwrap();
+    let best = engine.best_move(|_| {}).unwrap().unwrap();
+    assert!(Game::new().legal_moves().contains(&best));
+}
+
+#[test]
+fn test_clock_search_respects_time() {
+    let mut engine = server();
+    engine.set_position(&Game::new()).unwrap();
+
+    let started = Instant::now();
+    engine
+        .go_with(&GoParams {
+            wtime: Some(Duration::from_millis(3000)),
+            btime: Some(Duration::from_millis(3000)),
+            ..GoParams::default()
+        })
+        .unwrap();
+    assert!(engine.best_move(|_| {}).unwrap().is_some());
+    assert!(started.elapsed() < Duration::from_secs(1));
+}